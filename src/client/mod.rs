@@ -17,10 +17,7 @@ use engine::{
 
 use crate::shared::{follow::MayhemBehaviors, input::PlayerInput};
 
-use crate::shared::systems::{
-  collisions::CollisionSystem, item_drop::ItemDropSystem, combat::CombatSystem, goal::GoalRegistry, death::DeathSystem, lifetime::LifetimeSystem,
-  player_movement::PlayerMovementSystem, spawn::SpawnSystem,
-};
+use crate::shared::systems::{goal::GoalRegistry, lifetime::LifetimeSystem, sky::SkySystem};
 
 // 4k
 /*
@@ -68,16 +65,10 @@ pub async fn main(
   // runner.attach_middleware::<AttackTransitions>();
   // runner.attach_middleware::<MayhemBehaviors>();
   runner.attach_system::<world::WorldSystem>();
-  runner.attach_system::<PlayerMovementSystem>();
-  //runner.attach_system::<GoalSystem>();
-  runner.attach_system::<CollisionSystem>();
-
+  runner.attach_system::<SkySystem>();
+  // Movement, combat, pickups, spawning and death are all up to the server,
+  // the client only renders what it replicates
   runner.attach_system::<camera::CameraSystem>();
-  runner.attach_system::<CombatSystem>();
   // runner.attach_system::<LifetimeSystem>();
-  runner.attach_system::<ItemDropSystem>();
-  // runner.attach_system::<DeathSystem>();
-  // runner.attach_system::<SpawnSystem>();
-  // runner.attach_system::<EnemyAiSystem>();
   runner.run().await;
 }
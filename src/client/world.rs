@@ -1,21 +1,36 @@
 #![cfg(target_arch = "wasm32")]
-use crate::shared::input::PlayerInput;
+use crate::shared::{
+  components::{
+    match_component::MatchComponent, wave_director_component::WaveDirectorComponent,
+    warmth_component::WarmthComponent, respawn_component::RespawnComponent,
+    weapon_component::WeaponComponent, despawn_component::DespawnComponent,
+    attack_component::AttackComponent, health_component::HealthComponent,
+    shield_component::ShieldComponent, movement_component::MovementComponent,
+    inventory_component::InventoryComponent, resource_component::ResourceComponent,
+    magnetic_pickup_component::MagneticPickupComponent, respawn_component::LifeState,
+  },
+  events::{GameEvent, GameEvents},
+  input::PlayerInput,
+};
 use serde::{Deserialize, Serialize};
 
 use engine::{
   application::{
-    components::LightComponent,
+    components::{LightComponent, PhysicsComponent},
+    scene::component_registry::Access,
     // input::DefaultInput,
-    scene::{Scene, UnpackEntity},
+    scene::{IdComponent, PrefabId, Scene, UnpackEntity},
   },
   systems::{
     input::{CanvasController, InputsReader},
     network::{ServerReceiver, ServerSender},
+    physics::PhysicsController,
     Backpack, Initializable, Inventory, System,
   },
-  utils::units::{Radians, Time},
+  utils::units::{Radians, Seconds, Time},
 };
 use nalgebra::Vector3;
+use std::collections::HashMap;
 
 // use crate::shared::player_controller::PlayerController;
 use crate::shared::systems::combat::CombatSystem;
@@ -24,13 +39,21 @@ use crate::shared::systems::player_movement::PlayerMovementSystem;
 pub struct WorldSystem {
   inputs: InputsReader<PlayerInput>,
   canvas: CanvasController,
+  physics: PhysicsController,
+  life_states: HashMap<PrefabId, LifeState>,
 }
 
 impl Initializable for WorldSystem {
   fn initialize(inventory: &Inventory) -> Self {
     let inputs = inventory.get::<InputsReader<PlayerInput>>().clone();
     let canvas = inventory.get::<CanvasController>().clone();
-    Self { inputs, canvas }
+    let physics = inventory.get::<PhysicsController>().clone();
+    Self {
+      inputs,
+      canvas,
+      physics,
+      life_states: HashMap::new(),
+    }
   }
 }
 
@@ -39,13 +62,92 @@ impl WorldSystem {
     // self.canvas.capture_mouse(true);
     // self.canvas.request_fullscreen(true);
   }
+
+  /// Raises `PlayerDied` and `PlayerRespawned` as the replicated respawn
+  /// state of players flips, and counts their respawn down in between.
+  fn respawns(&mut self, scene: &mut Scene, backpack: &mut Backpack, dt: f32) {
+    let mut events = vec![];
+    for (entity, (id, respawn)) in scene.query_mut::<(&IdComponent, &mut RespawnComponent)>() {
+      if !respawn.is_alive() {
+        respawn.respawn_in = Seconds::new((*respawn.respawn_in - dt).max(0.0));
+      }
+
+      match self.life_states.insert(**id, respawn.state) {
+        Some(LifeState::Alive) if respawn.state == LifeState::Dead => {
+          events.push(GameEvent::PlayerDied { player: entity });
+        }
+        Some(LifeState::Dead) if respawn.state == LifeState::Alive => {
+          events.push(GameEvent::PlayerRespawned { player: entity });
+        }
+        _ => {}
+      }
+    }
+
+    for event in events {
+      GameEvents::emit(backpack, event);
+    }
+  }
+
+  /// Removes whatever the server despawned, along with the entity that
+  /// carried the list over.
+  fn despawn(&mut self, scene: &mut Scene) {
+    let mut carriers = vec![];
+    let mut despawned = vec![];
+    for (entity, despawn) in scene.query_mut::<&DespawnComponent>() {
+      carriers.push(entity);
+      despawned.extend(despawn.ids.iter().copied());
+    }
+
+    for id in despawned {
+      self.life_states.remove(&id);
+      let entity = match scene.get_entity_mut(id) {
+        Some(entity) => entity.clone(),
+        None => continue,
+      };
+
+      if let Some(physics) = scene.get_components::<&PhysicsComponent>(entity) {
+        self.physics.despawn(&physics);
+      }
+      scene.remove_entity(entity);
+    }
+
+    for carrier in carriers {
+      scene.remove_entity(carrier);
+    }
+  }
 }
 
 impl System for WorldSystem {
+  fn provide(&mut self, _: &Inventory) {
+    // The gameplay systems only run on the server, but the client still has
+    // to unpack what they replicate
+    MovementComponent::register();
+    AttackComponent::register();
+    HealthComponent::register();
+    ShieldComponent::register();
+    InventoryComponent::register();
+    ResourceComponent::register();
+    MagneticPickupComponent::register();
+    MatchComponent::register();
+    WaveDirectorComponent::register();
+    WarmthComponent::register();
+    RespawnComponent::register();
+    WeaponComponent::register();
+    DespawnComponent::register();
+  }
+
   fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
     let delta = backpack.get::<Time>().unwrap();
     let input = self.inputs.read();
 
+    // Nothing on this side closes the frame, so events only last one
+    if let Some(events) = backpack.get_mut::<GameEvents>() {
+      events.clear();
+    }
+
+    self.despawn(scene);
+    self.respawns(scene, backpack, **delta);
+
     if input.left_click && !input.mouse_lock {
       // self.capture_mouse(&input);
     }
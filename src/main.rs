@@ -15,6 +15,9 @@ mod server;
 
 mod shared;
 
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests;
+
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
 pub async fn init_main(
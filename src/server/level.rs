@@ -0,0 +1,163 @@
+use crate::shared::{
+  components::{
+    health_component::HealthComponent, inventory_component::InventoryComponent,
+    shield_component::ShieldComponent, warmth_component::WarmthComponent,
+    respawn_component::RespawnComponent,
+  },
+  replication::ReplicationQueue,
+};
+
+use engine::{
+  application::{
+    components::PhysicsComponent,
+    scene::{IdComponent, Prefab, Scene, TransformComponent},
+  },
+  networking::connection::PlayerId,
+  systems::{physics::PhysicsController, Backpack},
+  Entity,
+};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Everything needed to bring the level back to the state it was loaded in,
+/// without reloading the gamefile. Recorded by the `NetworkController`.
+#[derive(Debug, Default)]
+pub struct LevelSnapshot {
+  entities: Vec<Prefab>,
+  players: HashMap<PlayerId, Prefab>,
+}
+
+impl LevelSnapshot {
+  pub fn new() -> Self {
+    Self {
+      entities: vec![],
+      players: HashMap::new(),
+    }
+  }
+
+  pub fn insert_entity(&mut self, prefab: Prefab) {
+    self.entities.push(prefab);
+  }
+
+  pub fn insert_player(&mut self, player_id: PlayerId, prefab: Prefab) {
+    self.players.insert(player_id, prefab);
+  }
+
+  pub fn remove_player(&mut self, player_id: &PlayerId) {
+    self.players.remove(player_id);
+  }
+
+  pub fn is_player(&self, id: &IdComponent) -> bool {
+    self.players.keys().any(|player_id| **player_id == ***id)
+  }
+
+  /// The player owning the entity with this id, and the prefab they joined with.
+  pub fn player(&self, id: &IdComponent) -> Option<(PlayerId, &Prefab)> {
+    self
+      .players
+      .iter()
+      .find(|(player_id, _)| ***player_id == ***id)
+      .map(|(player_id, prefab)| (*player_id, prefab))
+  }
+
+  /// Despawns everything but the players, on the clients too, recreates the
+  /// level entities, and puts the players back where and how they joined.
+  pub fn restore(&self, scene: &mut Scene, backpack: &mut Backpack, physics: &mut PhysicsController) {
+    let mut stale = vec![];
+    let mut players = vec![];
+
+    for (entity, id) in scene.query_mut::<&IdComponent>() {
+      if self.is_player(id) {
+        players.push((entity, id.clone()));
+      } else {
+        stale.push(entity);
+      }
+    }
+
+    // The level entities come back with the same ids, so clients only drop
+    // the ones spawned since
+    for entity in stale {
+      if let Some(body) = scene.get_components::<&PhysicsComponent>(entity) {
+        physics.despawn(&body);
+      }
+      ReplicationQueue::despawn(scene, backpack, entity);
+      scene.remove_entity(entity);
+    }
+
+    for prefab in &self.entities {
+      let entity = scene.create_raw_entity(prefab.tag.name.as_str());
+      scene.create_with_prefab(entity, prefab.clone());
+      ReplicationQueue::mark(backpack, entity);
+    }
+
+    for (entity, id) in players {
+      if let Some((player_id, prefab)) = self.player(&id) {
+        self.restore_player(scene, entity, &player_id);
+        move_player(scene, physics, entity, &prefab.transform);
+        ReplicationQueue::mark(backpack, entity);
+      }
+    }
+  }
+
+  /// Puts the player's stats back to the ones they joined with, leaving
+  /// where they stand and what they wield alone.
+  pub fn restore_player(&self, scene: &mut Scene, entity: Entity, player_id: &PlayerId) {
+    let prefab = match self.players.get(player_id) {
+      Some(prefab) => prefab,
+      None => return,
+    };
+
+    for component in prefab.components.iter() {
+      if let Some(initial) = component.as_any().downcast_ref::<HealthComponent>()
+        && let Ok(health) = scene.query_one_mut::<&mut HealthComponent>(entity)
+      {
+        *health = initial.clone();
+      }
+
+      if let Some(initial) = component.as_any().downcast_ref::<ShieldComponent>()
+        && let Ok(shield) = scene.query_one_mut::<&mut ShieldComponent>(entity)
+      {
+        *shield = initial.clone();
+      }
+
+      if let Some(initial) = component.as_any().downcast_ref::<InventoryComponent>()
+        && let Ok(inventory) = scene.query_one_mut::<&mut InventoryComponent>(entity)
+      {
+        *inventory = initial.clone();
+      }
+
+      if let Some(initial) = component.as_any().downcast_ref::<WarmthComponent>()
+        && let Ok(warmth) = scene.query_one_mut::<&mut WarmthComponent>(entity)
+      {
+        *warmth = initial.clone();
+      }
+
+      if let Some(initial) = component.as_any().downcast_ref::<RespawnComponent>()
+        && let Ok(respawn) = scene.query_one_mut::<&mut RespawnComponent>(entity)
+      {
+        *respawn = initial.clone();
+      }
+    }
+  }
+}
+
+/// Puts a player at `transform`. Moving the transform alone would leave the
+/// rigid body behind, so it gets released and replaced by a new one, the way
+/// the `DeathSystem` gives the entities it creates bodies of their own.
+pub fn move_player(
+  scene: &mut Scene,
+  physics: &mut PhysicsController,
+  entity: Entity,
+  transform: &TransformComponent,
+) {
+  if let Ok(current) = scene.query_one_mut::<&mut TransformComponent>(entity) {
+    current.translation = transform.translation;
+    current.rotation = transform.rotation;
+  }
+
+  if let Ok(body) = scene.query_one_mut::<&mut PhysicsComponent>(entity) {
+    physics.despawn(body);
+    body.joint.id = Uuid::new_v4();
+    body.joint.body.id = Uuid::new_v4();
+  }
+}
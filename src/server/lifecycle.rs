@@ -0,0 +1,146 @@
+use crate::server::{level::LevelSnapshot, replication::ConnectedPlayers};
+use crate::shared::{
+  components::match_component::{MatchComponent, MatchPhase},
+  events::{GameEvent, GameEvents},
+  match_state::{MatchRules, MatchState},
+  replication::ReplicationQueue,
+  systems::goal::components::FireComponent,
+};
+
+use engine::application::scene::component_registry::Access;
+use engine::{
+  application::scene::{Prefab, Scene},
+  systems::{physics::PhysicsController, Backpack, Initializable, Inventory, System},
+  utils::units::{Seconds, Time},
+  Entity,
+};
+use std::str::FromStr;
+
+fn env_or<T: FromStr>(key: &str, default: T) -> T {
+  dotenv::var(key)
+    .ok()
+    .and_then(|value| value.parse().ok())
+    .unwrap_or(default)
+}
+
+/// Drives the match from the lobby to the results and back, resetting the
+/// level in between rounds. The rules can be tuned through the environment.
+pub struct MatchSystem {
+  physics: PhysicsController,
+  rules: MatchRules,
+  match_entity: Option<Entity>,
+}
+
+impl Initializable for MatchSystem {
+  fn initialize(inventory: &Inventory) -> Self {
+    let physics = inventory.get::<PhysicsController>().clone();
+    let defaults = MatchRules::default();
+    let rules = MatchRules {
+      min_players: env_or("MATCH_MIN_PLAYERS", defaults.min_players),
+      countdown: Seconds::new(env_or("MATCH_COUNTDOWN", *defaults.countdown)),
+      time_limit: Seconds::new(env_or("MATCH_TIME_LIMIT", *defaults.time_limit)),
+      results_duration: Seconds::new(env_or("MATCH_RESULTS_DURATION", *defaults.results_duration)),
+    };
+
+    Self {
+      physics,
+      rules,
+      match_entity: None,
+    }
+  }
+}
+
+impl System for MatchSystem {
+  fn attach(&mut self, _: &mut Scene, backpack: &mut Backpack) {
+    backpack.insert(MatchState::new(self.rules.clone()));
+  }
+
+  fn provide(&mut self, _: &Inventory) {
+    MatchComponent::register();
+  }
+
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    let dt = **backpack.get::<Time>().unwrap();
+
+    let players = match backpack.get::<ConnectedPlayers>() {
+      Some(players) => players.len(),
+      None => 0,
+    };
+
+    let fire_out = scene
+      .query_mut::<&FireComponent>()
+      .into_iter()
+      .any(|(_, fire)| fire.is_out());
+
+    let (previous, next) = match backpack.get_mut::<MatchState>() {
+      Some(state) => (state.phase(), state.update(dt, players, fire_out)),
+      None => return,
+    };
+
+    let phase = match next {
+      Some(phase) => phase,
+      None => return,
+    };
+
+    log::info!("[match] {:?} -> {:?} ({} players)", previous, phase, players);
+
+    if phase == MatchPhase::Lobby && (previous == MatchPhase::Won || previous == MatchPhase::Lost) {
+      self.reset(scene, backpack);
+    }
+
+    GameEvents::emit(backpack, GameEvent::MatchPhaseChanged { phase });
+    self.broadcast(scene, backpack, players);
+  }
+}
+
+impl MatchSystem {
+  fn reset(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    if let Some(snapshot) = backpack.take::<LevelSnapshot>() {
+      log::info!("[match] resetting level");
+      snapshot.restore(scene, backpack, &mut self.physics);
+      backpack.insert(snapshot);
+    }
+
+    // The restore despawns the match entity along with the rest of the level
+    self.match_entity = None;
+  }
+
+  fn match_entity(&mut self, scene: &mut Scene) -> Option<Entity> {
+    if let Some(entity) = self.match_entity {
+      return Some(entity);
+    }
+
+    let entity = scene.create_raw_entity("Match");
+    let mut prefab = match Prefab::pack(scene, entity) {
+      Ok(prefab) => prefab,
+      Err(_) => {
+        log::warn!("[match] couldn't create the match entity");
+        let _ = scene.despawn(entity);
+        return None;
+      }
+    };
+    prefab.components.push(Box::new(MatchComponent::new()));
+    scene.create_with_prefab(entity, prefab);
+
+    self.match_entity = Some(entity);
+    Some(entity)
+  }
+
+  fn broadcast(&mut self, scene: &mut Scene, backpack: &mut Backpack, players: usize) {
+    let component = match backpack.get::<MatchState>() {
+      Some(state) => state.to_component(players),
+      None => return,
+    };
+
+    let entity = match self.match_entity(scene) {
+      Some(entity) => entity,
+      None => return,
+    };
+    if let Ok(current) = scene.query_one_mut::<&mut MatchComponent>(entity) {
+      *current = component;
+    }
+
+    // Goes out to every player along with the rest of the frame
+    ReplicationQueue::mark(backpack, entity);
+  }
+}
@@ -1,4 +1,9 @@
+mod level;
+mod lifecycle;
 mod network_controller;
+mod replication;
+mod respawn;
+mod spawn_points;
 
 //use std::io::Write;
 
@@ -16,11 +21,30 @@ use crate::shared::components::{
   lifetime_component::LifetimeComponent, magnetic_pickup_component::MagneticPickupComponent,
   movement_component::MovementComponent, shield_component::ShieldComponent,
   spawn_component::SpawnComponent, top_down_camera_component::TopDownCameraComponent,
-  resource_component::ResourceComponent,
+  resource_component::ResourceComponent, wave_director_component::WaveDirectorComponent,
+  day_night_component::DayNightComponent, warmth_component::WarmthComponent,
+  respawn_component::RespawnComponent, weapon_component::WeaponComponent,
+  despawn_component::DespawnComponent,
 };
 use crate::shared::follow::MayhemBehaviors;
-use crate::shared::systems::goal::GoalRegistry;
+use crate::shared::systems::goal::{GoalRegistry, GoalSystem};
+use crate::shared::systems::collisions::CollisionSystem;
+use crate::shared::systems::combat::CombatSystem;
+use crate::shared::systems::death::DeathSystem;
+use crate::shared::systems::deposit::DepositSystem;
+use crate::shared::systems::enemy_ai::EnemyAiSystem;
+use crate::shared::systems::fire::FireSystem;
+use crate::shared::systems::harvest::HarvestSystem;
+use crate::shared::systems::heal::HealSystem;
+use crate::shared::systems::item_drop::ItemDropSystem;
+use crate::shared::systems::player_movement::PlayerMovementSystem;
+use crate::shared::systems::spawn::SpawnSystem;
+use crate::shared::systems::threat::ThreatSystem;
+use crate::shared::systems::warmth::WarmthSystem;
 use crate::{server::network_controller::NetworkController, shared::systems::sky::SkySystem};
+use crate::server::lifecycle::MatchSystem;
+use crate::server::replication::ReplicationSystem;
+use crate::server::respawn::RespawnSystem;
 
 const FRAMES_PER_SECOND: u64 = 60;
 
@@ -42,6 +66,12 @@ impl Plugin for CustomComponentsPlugin {
     LifetimeComponent::register();
     TopDownCameraComponent::register();
     ResourceComponent::register();
+    WaveDirectorComponent::register();
+    DayNightComponent::register();
+    WarmthComponent::register();
+    RespawnComponent::register();
+    WeaponComponent::register();
+    DespawnComponent::register();
   }
 }
 pub async fn main() {
@@ -74,7 +104,23 @@ pub async fn main() {
   runner.attach_registry::<GoalRegistry>();
   runner.attach_plugin(custom_components);
   runner.attach_system::<SkySystem>();
-  //runner.attach_system::<GoalSystem>();
+  runner.attach_system::<PlayerMovementSystem>();
+  runner.attach_system::<EnemyAiSystem>();
+  runner.attach_system::<CollisionSystem>();
+  runner.attach_system::<CombatSystem>();
+  runner.attach_system::<HealSystem>();
+  runner.attach_system::<ThreatSystem>();
+  runner.attach_system::<FireSystem>();
+  runner.attach_system::<DepositSystem>();
+  runner.attach_system::<HarvestSystem>();
+  runner.attach_system::<SpawnSystem>();
+  runner.attach_system::<WarmthSystem>();
+  runner.attach_system::<RespawnSystem>();
+  runner.attach_system::<DeathSystem>();
+  runner.attach_system::<ItemDropSystem>();
+  runner.attach_system::<MatchSystem>();
+  runner.attach_system::<GoalSystem>();
+  runner.attach_system::<ReplicationSystem>();
 
   runner.run().await;
 
@@ -1,5 +1,16 @@
 use crate::shared::game_types::game_types::ModelNames;
 use crate::shared::game_types::game_types::ParticleType;
+use crate::server::level::LevelSnapshot;
+use crate::server::replication::ConnectedPlayers;
+use crate::server::spawn_points::SpawnPoints;
+use crate::shared::input::PlayerInput;
+use crate::shared::player_inputs::PlayerInputs;
+use crate::shared::replication::ReplicationQueue;
+use crate::shared::systems::sky::SkySystem;
+use crate::shared::components::respawn_component::RespawnComponent;
+use crate::shared::components::warmth_component::WarmthComponent;
+use crate::shared::components::weapon_component::WeaponComponent;
+use crate::shared::systems::goal::{PlannerDefinition, PlannerDefinitions};
 
 use async_trait::async_trait;
 use engine::application::gamefile::Gamefile;
@@ -8,7 +19,7 @@ use engine::systems::Backpack;
 use engine::{
   application::{
     assets::{AssetPack, Store},
-    components::SelfComponent,
+    components::{PhysicsComponent, SelfComponent},
     config::Config,
     downloader::DownloadSender,
     input::TrustedInput,
@@ -26,6 +37,7 @@ use engine::{
   },
   systems::{
     network::{ChannelEvents, ClientSender},
+    physics::PhysicsController,
     Initializable, Inventory,
   },
   Entity,
@@ -35,12 +47,11 @@ use std::collections::HashSet;
 
 pub struct NetworkController {
   spectator_points: Vec<TransformComponent>,
-  spawn_points: Vec<TransformComponent>,
-  assigned_spawns: Vec<Option<PlayerId>>,
   prefabs: HashMap<ModelNames, Prefab>,
   particle_prefabs: HashMap<ParticleType, Prefab>,
   download_sender: DownloadSender,
   client_sender: ClientSender<TrustedInput>,
+  physics: PhysicsController,
   config: Option<Config>,
   store: Store,
 }
@@ -49,14 +60,14 @@ impl Initializable for NetworkController {
   fn initialize(inventory: &Inventory) -> Self {
     let download_sender = inventory.get::<DownloadSender>().clone();
     let client_sender = inventory.get::<ClientSender<TrustedInput>>().clone();
+    let physics = inventory.get::<PhysicsController>().clone();
     let store = Store::new();
     Self {
       client_sender,
+      physics,
       download_sender,
       store,
       spectator_points: vec![],
-      spawn_points: vec![],
-      assigned_spawns: vec![],
       prefabs: HashMap::new(),
       particle_prefabs: HashMap::new(),
       config: None,
@@ -171,10 +182,18 @@ impl ChannelEvents for NetworkController {
     for (id, asset) in gamefile.scene.behavior_tree {
       self.store.insert_asset(asset.id, asset);
     }
+    let mut planners = PlannerDefinitions::new();
     for (id, asset) in gamefile.scene.planners {
+      match PlannerDefinition::from_asset(&asset) {
+        Ok(definition) => planners.insert(definition),
+        Err(error) => log::warn!("skipping planner {}: {}", id, error),
+      }
       self.store.insert_asset(asset.id, asset);
     }
 
+    let mut snapshot = LevelSnapshot::new();
+    let mut spawn_points = SpawnPoints::new();
+
     for (id, prefab) in gamefile.scene.prefabs {
       match prefab.tag.name.as_str() {
         "DamageParticle" => {
@@ -193,8 +212,7 @@ impl ChannelEvents for NetworkController {
         }
         "EnemySpawn1" | "EnemySpawn2" | "EnemySpawn3" | "EnemySpawn4" => {
           log::info!("creating spawn points {:?}", prefab.tag.name);
-          self.spawn_points.push(prefab.transform);
-          self.assigned_spawns.push(None);
+          spawn_points.insert(prefab.transform);
         }
         "Wizard" => {
           log::info!("creating foxy prefab: {:?}", prefab.tag.name);
@@ -203,7 +221,8 @@ impl ChannelEvents for NetworkController {
         "Wood" => {
           log::info!("creating wood prefab: {:?}", prefab.tag.name);
           self.prefabs.insert(ModelNames::Wood, prefab.clone());
-          // scene.store_prefab("Dreamstone", prefab);
+          scene.store_prefab("Wood", prefab.clone());
+          snapshot.insert_entity(prefab.clone());
           let entity = scene.create_raw_entity(prefab.tag.name.as_str());
           scene.create_with_prefab(entity, prefab);
         }
@@ -214,12 +233,22 @@ impl ChannelEvents for NetworkController {
         }
         _ => {
           log::info!("receiving entity {:?}", prefab.tag.name);
+          snapshot.insert_entity(prefab.clone());
           let entity = scene.create_raw_entity("{prefab.tag.name}");
           scene.create_with_prefab(entity, prefab);
         }
       }
     }
 
+    // Clients light the level from the replicated clock, so there has to be one
+    if let Some(clock) = SkySystem::spawn_clock(scene) {
+      snapshot.insert_entity(clock);
+    }
+
+    backpack.insert(snapshot);
+    backpack.insert(spawn_points);
+    backpack.insert(planners);
+
     /*
     let spectator_prefab = self.prefabs.get(&ModelNames::Spectator).unwrap().clone();
     let mut spectators = vec![];
@@ -243,7 +272,7 @@ impl ChannelEvents for NetworkController {
     scene: &mut Scene,
     backpack: &mut Backpack,
     _: &HashSet<PlayerId>,
-    entity: Entity,
+    _: Entity,
     player_id: PlayerId,
     username: String,
     protocol: Protocol,
@@ -251,18 +280,11 @@ impl ChannelEvents for NetworkController {
     let mut prefab: Prefab = self.prefabs.get(&ModelNames::Wizard).unwrap().clone();
     log::info!("Player joined! New prefab: {:#?}", &prefab);
 
-    if self.spawn_points.len() != 0 {
-      let mut spawn_index = 0;
-      for (index, assigned_spawn) in self.assigned_spawns.iter_mut().enumerate() {
-        if *assigned_spawn == None {
-          *assigned_spawn = Some(player_id);
-          spawn_index = index;
-          break;
-        }
-      }
-
-      let spawn = self.spawn_points[spawn_index];
+    let spawn = backpack
+      .get_mut::<SpawnPoints>()
+      .and_then(|spawn_points| spawn_points.assign(player_id));
 
+    if let Some(spawn) = spawn {
       let transform = &mut prefab.transform;
 
       transform.translation = spawn.translation;
@@ -270,10 +292,49 @@ impl ChannelEvents for NetworkController {
     }
 
     prefab.id = IdComponent::with_id(PrefabId::with_id(*player_id));
+
+    // Every player has to stay warm, even if the level didn't set it up
+    let has_warmth = prefab
+      .components
+      .iter()
+      .any(|component| component.as_any().downcast_ref::<WarmthComponent>().is_some());
+    if !has_warmth {
+      prefab.components.push(Box::new(WarmthComponent::default()));
+    }
+
+    let has_respawn = prefab
+      .components
+      .iter()
+      .any(|component| component.as_any().downcast_ref::<RespawnComponent>().is_some());
+    if !has_respawn {
+      prefab.components.push(Box::new(RespawnComponent::default()));
+    }
+
+    // Players fight with whatever the level hands them, or the great ax
+    let has_weapon = prefab
+      .components
+      .iter()
+      .any(|component| component.as_any().downcast_ref::<WeaponComponent>().is_some());
+    if !has_weapon {
+      prefab.components.push(Box::new(WeaponComponent::default()));
+    }
+
+    if let Some(snapshot) = backpack.get_mut::<LevelSnapshot>() {
+      snapshot.insert_player(player_id, prefab.clone());
+    }
     let entity = scene.create_raw_entity(&username);
     scene.create_with_prefab(entity, prefab);
     self.sync_world(scene, &player_id);
 
+    match backpack.get_mut::<ConnectedPlayers>() {
+      Some(players) => players.insert(player_id),
+      None => {
+        let mut players = ConnectedPlayers::new();
+        players.insert(player_id);
+        backpack.insert(players);
+      }
+    }
+
     // let entity = scene.create_entity_with_id(prefab.id, &prefab.tag.name);
 
     /*
@@ -294,17 +355,64 @@ impl ChannelEvents for NetworkController {
     &mut self,
     scene: &mut Scene,
     backpack: &mut Backpack,
-    entity: Entity,
+    _: Entity,
     player_id: PlayerId,
     protocol: Protocol,
   ) {
     log::info!("[on player left] Player left {player_id:?}");
 
-    for assigned_spawn in &mut self.assigned_spawns {
-      if *assigned_spawn == Some(player_id) {
-        *assigned_spawn = None
+    if let Some(spawn_points) = backpack.get_mut::<SpawnPoints>() {
+      spawn_points.release(&player_id);
+    }
+    if let Some(players) = backpack.get_mut::<ConnectedPlayers>() {
+      players.remove(&player_id);
+    }
+    if let Some(inputs) = backpack.get_mut::<PlayerInputs>() {
+      inputs.remove(&player_id);
+    }
+    if let Some(snapshot) = backpack.get_mut::<LevelSnapshot>() {
+      snapshot.remove_player(&player_id);
+    }
+
+    // The player's wizard is created apart from the entity the engine keeps
+    // for the connection, and shares its id with the player instead
+    let wizard = scene
+      .query_mut::<&IdComponent>()
+      .into_iter()
+      .find(|(_, id)| ***id == *player_id)
+      .map(|(entity, _)| entity);
+    if let Some(wizard) = wizard {
+      if let Some(body) = scene.get_components::<&PhysicsComponent>(wizard) {
+        self.physics.despawn(&body);
+      }
+      ReplicationQueue::despawn(scene, backpack, wizard);
+      scene.remove_entity(wizard);
+    }
+  }
+
+  /// Keeps the latest input of every player in `PlayerInputs`, for the
+  /// gameplay systems to act on. Anything older is stale by now.
+  fn on_player_input(
+    &mut self,
+    _: &mut Scene,
+    backpack: &mut Backpack,
+    player_id: PlayerId,
+    input: PlayerInput,
+  ) {
+    let connected = backpack
+      .get::<ConnectedPlayers>()
+      .map_or(false, |players| players.iter().any(|player| *player == player_id));
+    if !connected {
+      return;
+    }
+
+    match backpack.get_mut::<PlayerInputs>() {
+      Some(inputs) => inputs.insert(&player_id, input),
+      None => {
+        let mut inputs = PlayerInputs::new();
+        inputs.insert(&player_id, input);
+        backpack.insert(inputs);
       }
     }
-    let _ = scene.despawn(entity);
   }
 }
@@ -0,0 +1,119 @@
+use crate::shared::{
+  components::despawn_component::DespawnComponent, events::GameEvents,
+  replication::ReplicationQueue,
+};
+
+use engine::application::scene::component_registry::Access;
+use engine::{
+  application::{
+    input::TrustedInput,
+    scene::{Prefab, Scene},
+  },
+  networking::connection::PlayerId,
+  systems::{network::ClientSender, Backpack, Initializable, Inventory, System},
+};
+use std::collections::HashSet;
+
+/// Players currently connected to the session. Kept up to date by the
+/// `NetworkController`, so that systems know who to send updates to.
+#[derive(Debug, Default)]
+pub struct ConnectedPlayers {
+  players: HashSet<PlayerId>,
+}
+
+impl ConnectedPlayers {
+  pub fn new() -> Self {
+    Self {
+      players: HashSet::new(),
+    }
+  }
+
+  pub fn insert(&mut self, player_id: PlayerId) {
+    self.players.insert(player_id);
+  }
+
+  pub fn remove(&mut self, player_id: &PlayerId) {
+    self.players.remove(player_id);
+  }
+
+  pub fn len(&self) -> usize {
+    self.players.len()
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = &PlayerId> {
+    self.players.iter()
+  }
+}
+
+/// Sends every entity marked in the `ReplicationQueue` to all connected
+/// players, and tells them which ones got despawned. Must be attached last,
+/// since it also closes the frame by clearing the `GameEvents`.
+pub struct ReplicationSystem {
+  client_sender: ClientSender<TrustedInput>,
+  despawns: Option<Prefab>,
+}
+
+impl Initializable for ReplicationSystem {
+  fn initialize(inventory: &Inventory) -> Self {
+    let client_sender = inventory.get::<ClientSender<TrustedInput>>().clone();
+    Self {
+      client_sender,
+      despawns: None,
+    }
+  }
+}
+
+impl System for ReplicationSystem {
+  fn provide(&mut self, _: &Inventory) {
+    DespawnComponent::register();
+  }
+
+  fn attach(&mut self, scene: &mut Scene, _: &mut Backpack) {
+    // Despawns travel in an entity that only ever exists on the clients,
+    // always the same one so that they don't pile up
+    let entity = scene.create_raw_entity("Despawns");
+    self.despawns = Prefab::pack(scene, entity).ok();
+    let _ = scene.despawn(entity);
+  }
+
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    let (dirty, despawned) = match backpack.get_mut::<ReplicationQueue>() {
+      Some(queue) => (
+        queue.drain().collect::<Vec<_>>(),
+        queue.drain_despawned().collect::<Vec<_>>(),
+      ),
+      None => (vec![], vec![]),
+    };
+
+    if (dirty.len() > 0 || despawned.len() > 0) && let Some(players) = backpack.get::<ConnectedPlayers>() {
+      let mut entities = dirty
+        .into_iter()
+        .filter_map(|entity| Prefab::pack(scene, entity).ok())
+        .collect::<Vec<_>>();
+
+      // Ids that came back within the frame, like the level's after a reset,
+      // are sent over again instead
+      let despawned = despawned
+        .into_iter()
+        .filter(|id| !entities.iter().any(|prefab| *prefab.id == *id))
+        .collect::<Vec<_>>();
+      if despawned.len() > 0 && let Some(mut carrier) = self.despawns.clone() {
+        carrier.components.push(Box::new(DespawnComponent::new(despawned)));
+        entities.push(carrier);
+      }
+
+      for player_id in players.iter() {
+        self.client_sender.send_reliable(
+          *player_id,
+          TrustedInput::Entities {
+            entities: entities.clone(),
+          },
+        );
+      }
+    }
+
+    if let Some(events) = backpack.get_mut::<GameEvents>() {
+      events.clear();
+    }
+  }
+}
@@ -0,0 +1,132 @@
+use crate::server::{
+  level::{move_player, LevelSnapshot},
+  spawn_points::SpawnPoints,
+};
+use crate::shared::{
+  components::{health_component::HealthComponent, respawn_component::RespawnComponent},
+  events::{GameEvent, GameEvents},
+  game_types::game_types::PrefabType,
+  replication::ReplicationQueue,
+};
+
+use engine::{
+  application::scene::{IdComponent, Scene, TagComponent, TransformComponent},
+  systems::{physics::PhysicsController, Backpack, Initializable, Inventory, System},
+  utils::units::{Seconds, Time},
+  Entity,
+};
+use nalgebra::Vector3;
+
+/// Takes dead players out of the fight and brings them back once their
+/// respawn timer runs out, at their own spawn point or, when it is taken
+/// over by enemies, at the safest one.
+pub struct RespawnSystem {
+  physics: PhysicsController,
+}
+
+impl Initializable for RespawnSystem {
+  fn initialize(inventory: &Inventory) -> Self {
+    let physics = inventory.get::<PhysicsController>().clone();
+    Self { physics }
+  }
+}
+
+impl System for RespawnSystem {
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    let dt = **backpack.get::<Time>().unwrap();
+
+    let mut died = vec![];
+    let mut ready = vec![];
+
+    for (entity, (health, respawn)) in
+      scene.query_mut::<(&HealthComponent, &mut RespawnComponent)>()
+    {
+      if respawn.is_alive() {
+        if health.health <= 0.0 {
+          respawn.die();
+          died.push(entity);
+        }
+        continue;
+      }
+
+      let remaining = (*respawn.respawn_in - dt).max(0.0);
+      respawn.respawn_in = Seconds::new(remaining);
+
+      if remaining <= 0.0 {
+        ready.push((entity, respawn.deaths));
+      }
+    }
+
+    for entity in died {
+      log::info!("[respawn] player {:?} died", entity);
+      ReplicationQueue::mark(backpack, entity);
+      GameEvents::emit(backpack, GameEvent::PlayerDied { player: entity });
+    }
+
+    for (entity, deaths) in ready {
+      self.respawn(scene, backpack, entity, deaths);
+    }
+  }
+}
+
+impl RespawnSystem {
+  /// Brings the player back in place, so that everything keyed by their
+  /// entity keeps tracking them.
+  fn respawn(&mut self, scene: &mut Scene, backpack: &mut Backpack, entity: Entity, deaths: u32) {
+    let id = match scene.query_one_mut::<&IdComponent>(entity) {
+      Ok(id) => id.clone(),
+      Err(_) => return,
+    };
+
+    let threats = self.threats(scene);
+    let player_id = {
+      let snapshot = match backpack.get::<LevelSnapshot>() {
+        Some(snapshot) => snapshot,
+        None => return,
+      };
+      let player_id = match snapshot.player(&id) {
+        Some((player_id, _)) => player_id,
+        None => return,
+      };
+
+      // Back to the stats they joined with, but the death count survives the
+      // respawn so that the delay keeps growing
+      snapshot.restore_player(scene, entity, &player_id);
+      if let Ok(respawn) = scene.query_one_mut::<&mut RespawnComponent>(entity) {
+        respawn.deaths = deaths;
+      }
+      player_id
+    };
+
+    let spawn = backpack.get::<SpawnPoints>().and_then(|spawn_points| {
+      match spawn_points.assigned_to(&player_id) {
+        Some(spawn) if !Self::is_threatened(&spawn, &threats) => Some(spawn),
+        _ => spawn_points.safest(&threats),
+      }
+    });
+    if let Some(spawn) = spawn {
+      move_player(scene, &mut self.physics, entity, &spawn);
+    }
+
+    log::info!("[respawn] player {:?} is back", player_id);
+    ReplicationQueue::mark(backpack, entity);
+    GameEvents::emit(backpack, GameEvent::PlayerRespawned { player: entity });
+  }
+
+  fn threats(&self, scene: &mut Scene) -> Vec<Vector3<f32>> {
+    scene
+      .query_mut::<(&TagComponent, &TransformComponent)>()
+      .into_iter()
+      .filter(|(_, (tag, _))| PrefabType::from(tag.name.as_str()) == PrefabType::Enemy)
+      .map(|(_, (_, transform))| transform.translation)
+      .collect()
+  }
+
+  fn is_threatened(spawn: &TransformComponent, threats: &[Vector3<f32>]) -> bool {
+    const SAFE_DISTANCE: f32 = 10.0;
+
+    threats
+      .iter()
+      .any(|threat| (threat - spawn.translation).norm() < SAFE_DISTANCE)
+  }
+}
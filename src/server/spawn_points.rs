@@ -0,0 +1,82 @@
+use engine::{application::scene::TransformComponent, networking::connection::PlayerId};
+use nalgebra::{Point3, Vector3};
+
+/// Player spawn points found in the level, and which player each one of
+/// them is assigned to. Slots are released when a player leaves, so that
+/// the next one to join can reuse them.
+#[derive(Debug, Default)]
+pub struct SpawnPoints {
+  points: Vec<TransformComponent>,
+  assigned: Vec<Option<PlayerId>>,
+}
+
+impl SpawnPoints {
+  pub fn new() -> Self {
+    Self {
+      points: vec![],
+      assigned: vec![],
+    }
+  }
+
+  pub fn insert(&mut self, point: TransformComponent) {
+    self.points.push(point);
+    self.assigned.push(None);
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.points.is_empty()
+  }
+
+  /// Assigns the first free slot to the player. When they are all taken,
+  /// the player shares the first one.
+  pub fn assign(&mut self, player_id: PlayerId) -> Option<TransformComponent> {
+    if let Some(point) = self.assigned_to(&player_id) {
+      return Some(point);
+    }
+
+    for (index, assigned_spawn) in self.assigned.iter_mut().enumerate() {
+      if *assigned_spawn == None {
+        *assigned_spawn = Some(player_id);
+        return Some(self.points[index]);
+      }
+    }
+
+    self.points.first().copied()
+  }
+
+  pub fn release(&mut self, player_id: &PlayerId) {
+    for assigned_spawn in &mut self.assigned {
+      if *assigned_spawn == Some(*player_id) {
+        *assigned_spawn = None
+      }
+    }
+  }
+
+  pub fn assigned_to(&self, player_id: &PlayerId) -> Option<TransformComponent> {
+    self
+      .assigned
+      .iter()
+      .position(|assigned_spawn| *assigned_spawn == Some(*player_id))
+      .map(|index| self.points[index])
+  }
+
+  /// The spawn point furthest away from every threat.
+  pub fn safest(&self, threats: &[Vector3<f32>]) -> Option<TransformComponent> {
+    let closest_threat = |point: &TransformComponent| {
+      threats
+        .iter()
+        .map(|threat| nalgebra::distance(&Point3::from(point.translation), &Point3::from(*threat)))
+        .fold(f32::MAX, f32::min)
+    };
+
+    self
+      .points
+      .iter()
+      .max_by(|a, b| {
+        closest_threat(a)
+          .partial_cmp(&closest_threat(b))
+          .unwrap_or(std::cmp::Ordering::Equal)
+      })
+      .copied()
+  }
+}
@@ -79,6 +79,10 @@ pub struct AttackComponent {
   pub heavy_timer: f32,
   #[serde(skip)]
   pub air_timer: f32,
+  #[serde(skip)]
+  pub anim_timer: f32,
+  #[serde(skip)]
+  pub anim_running: bool,
 }
 
 impl AttackComponent {}
@@ -0,0 +1,29 @@
+use engine::utils::units::Seconds;
+use serde::{Deserialize, Serialize};
+use tagged::{Registerable, Schema, Duplicate};
+
+/// Configures the day/night clock. The server owns `time_of_day`, clients
+/// keep advancing their copy in between updates so lighting stays smooth.
+/// Time of day goes from 0.0 to 1.0, where 0.5 is noon.
+#[derive(Debug, Clone, Serialize, Deserialize, Schema, Registerable, Duplicate)]
+pub struct DayNightComponent {
+  #[schema(default = "{seconds: 600.0}")]
+  pub day_length: Seconds,
+  #[schema(default = "0.3")]
+  pub time_of_day: f32,
+  #[schema(default = "1.5")]
+  pub night_burn_multiplier: f32,
+  #[schema(default = "1.5")]
+  pub night_spawn_multiplier: f32,
+}
+
+impl Default for DayNightComponent {
+  fn default() -> Self {
+    Self {
+      day_length: Seconds::new(600.0),
+      time_of_day: 0.3,
+      night_burn_multiplier: 1.5,
+      night_spawn_multiplier: 1.5,
+    }
+  }
+}
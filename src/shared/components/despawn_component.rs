@@ -0,0 +1,17 @@
+use engine::application::scene::PrefabId;
+use serde::{Deserialize, Serialize};
+use tagged::{Registerable, Schema, Duplicate};
+
+/// Client facing list of the entities the server despawned in a frame. It
+/// rides along with the replicated entities, and the client removes them
+/// together with the entity that carried it over.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Schema, Registerable, Duplicate)]
+pub struct DespawnComponent {
+  pub ids: Vec<PrefabId>,
+}
+
+impl DespawnComponent {
+  pub fn new(ids: Vec<PrefabId>) -> Self {
+    Self { ids }
+  }
+}
@@ -7,6 +7,31 @@ pub struct InventoryComponent {
   pub wood: u32,
   #[schema(default = "5")]
   pub max_wood: u32,
+  /// Logs this player has fed to the fire so far.
+  #[serde(default)]
+  pub wood_deposited: u32,
+  #[serde(skip)]
+  pub deposit_requested: bool,
+  #[serde(skip)]
+  pub deposit_timer: f32,
+  #[serde(skip)]
+  pub chop_requested: bool,
+  #[serde(skip)]
+  pub chop_timer: f32,
+}
+
+impl Default for InventoryComponent {
+  fn default() -> Self {
+    Self {
+      wood: 0,
+      max_wood: 5,
+      wood_deposited: 0,
+      deposit_requested: false,
+      deposit_timer: 0.0,
+      chop_requested: false,
+      chop_timer: 0.0,
+    }
+  }
 }
 
 impl InventoryComponent {
@@ -15,7 +40,18 @@ impl InventoryComponent {
   }
 
   pub fn remove_wood(&mut self, amount: u32) {
-    self.wood -= amount;
+    self.wood = self.wood.saturating_sub(amount);
+  }
+
+  /// Takes a single log out of the inventory and credits it as deposited.
+  pub fn deposit_wood(&mut self) -> bool {
+    if self.wood == 0 {
+      return false;
+    }
+
+    self.remove_wood(1);
+    self.wood_deposited += 1;
+    true
   }
 
   pub fn get_wood(&self) -> u32 {
@@ -0,0 +1,42 @@
+use engine::utils::units::Seconds;
+use serde::{Deserialize, Serialize};
+use tagged::{Registerable, Schema, Duplicate};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Schema, Registerable, PartialEq, Eq, Duplicate)]
+pub enum MatchPhase {
+  Lobby,
+  Countdown,
+  Active,
+  Won,
+  Lost,
+}
+
+impl Default for MatchPhase {
+  fn default() -> Self {
+    MatchPhase::Lobby
+  }
+}
+
+/// Client facing copy of the match state. The server owns the real thing,
+/// and sends this over every time the phase changes.
+#[derive(Debug, Clone, Serialize, Deserialize, Schema, Registerable, Duplicate)]
+pub struct MatchComponent {
+  pub phase: MatchPhase,
+  #[schema(default = "{seconds: 0.0}")]
+  pub phase_time: Seconds,
+  #[schema(default = "{seconds: 0.0}")]
+  pub phase_duration: Seconds,
+  #[schema(default = "0")]
+  pub players: usize,
+}
+
+impl MatchComponent {
+  pub fn new() -> Self {
+    Self {
+      phase: MatchPhase::Lobby,
+      phase_time: Seconds::new(0.0),
+      phase_duration: Seconds::new(0.0),
+      players: 0,
+    }
+  }
+}
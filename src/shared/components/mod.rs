@@ -1,11 +1,18 @@
 pub mod attack_component;
+pub mod day_night_component;
+pub mod despawn_component;
 pub mod enemy_ai_component;
 pub mod health_component;
 pub mod inventory_component;
 pub mod lifetime_component;
+pub mod match_component;
 pub mod magnetic_pickup_component;
 pub mod movement_component;
 pub mod shield_component;
 pub mod spawn_component;
 pub mod top_down_camera_component;
+pub mod warmth_component;
 pub mod resource_component;
+pub mod respawn_component;
+pub mod wave_director_component;
+pub mod weapon_component;
@@ -13,15 +13,32 @@ pub struct ResourceComponent {
   pub regen_amount: u32,
   #[schema(default = "{seconds: 30}")]
   pub regen_interval: Seconds,
+  #[serde(skip)]
+  pub regen_timer: Seconds,
 }
 
 impl ResourceComponent {
-  pub fn gather_wood(&mut self) {
-    self.available -= 1;
+  /// Takes a single unit of wood, returns false once the resource is depleted.
+  pub fn gather_wood(&mut self) -> bool {
+    match self.available.checked_sub(1) {
+      Some(available) => {
+        self.available = available;
+        true
+      }
+      None => false,
+    }
   }
 
   pub fn restore_wood(&mut self) {
-    self.available += self.regen_amount;
+    self.available = (self.available + self.regen_amount).min(self.max);
+  }
+
+  pub fn is_depleted(&self) -> bool {
+    self.available == 0
+  }
+
+  pub fn is_full(&self) -> bool {
+    self.available >= self.max
   }
 
   pub fn increase_max(&mut self, amount: u32) {
@@ -29,6 +46,7 @@ impl ResourceComponent {
   }
 
   pub fn decrease_max(&mut self, amount: u32) {
-    self.max -= amount;
+    self.max = self.max.saturating_sub(amount);
+    self.available = self.available.min(self.max);
   }
 }
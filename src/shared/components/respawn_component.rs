@@ -0,0 +1,64 @@
+use engine::utils::units::Seconds;
+use serde::{Deserialize, Serialize};
+use tagged::{Registerable, Schema, Duplicate};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Schema, Registerable, PartialEq, Eq, Duplicate)]
+pub enum LifeState {
+  Alive,
+  Dead,
+}
+
+impl Default for LifeState {
+  fn default() -> Self {
+    LifeState::Alive
+  }
+}
+
+/// Lets a player come back after dying. The wait starts at `base_delay`, and
+/// grows by `delay_per_death` with every death, up to `max_delay`.
+///
+/// The server only replicates it when the player dies and when they're back,
+/// clients count `respawn_in` down on their own in between.
+#[derive(Debug, Clone, Serialize, Deserialize, Registerable, Schema, Duplicate)]
+pub struct RespawnComponent {
+  #[schema(default = "{seconds: 3.0}")]
+  pub base_delay: Seconds,
+  #[schema(default = "{seconds: 2.0}")]
+  pub delay_per_death: Seconds,
+  #[schema(default = "{seconds: 15.0}")]
+  pub max_delay: Seconds,
+  #[serde(default)]
+  pub state: LifeState,
+  #[serde(default)]
+  pub deaths: u32,
+  #[schema(default = "{seconds: 0.0}")]
+  pub respawn_in: Seconds,
+}
+
+impl Default for RespawnComponent {
+  fn default() -> Self {
+    Self {
+      base_delay: Seconds::new(3.0),
+      delay_per_death: Seconds::new(2.0),
+      max_delay: Seconds::new(15.0),
+      state: LifeState::Alive,
+      deaths: 0,
+      respawn_in: Seconds::new(0.0),
+    }
+  }
+}
+
+impl RespawnComponent {
+  pub fn is_alive(&self) -> bool {
+    self.state == LifeState::Alive
+  }
+
+  /// Marks the player as dead and starts the respawn countdown.
+  pub fn die(&mut self) {
+    self.state = LifeState::Dead;
+    self.deaths += 1;
+
+    let delay = *self.base_delay + *self.delay_per_death * (self.deaths - 1) as f32;
+    self.respawn_in = Seconds::new(delay.min(*self.max_delay));
+  }
+}
@@ -3,30 +3,46 @@ use tagged::{Registerable, Schema, Duplicate};
 // might need an is_active bool.
 // todo: switch radius to Meters type
 
+/// Spawn point used by the wave director. Spawns are spread across every
+/// spawner that hasn't reached its `max_enemies`.
 #[derive(Debug, Clone, Serialize, Deserialize, Schema, Registerable, Duplicate)]
 pub struct SpawnComponent {
   #[schema(default = "10.0")]
   pub radius: f32,
-  #[schema(default = "5.0")]
-  pub interval: f32,
-  #[serde(skip)]
-  pub timer: f32,
   #[schema(default = "25")]
   pub max_enemies: usize,
   #[serde(skip)]
   spawn_count: usize,
 }
 
+impl Default for SpawnComponent {
+  fn default() -> Self {
+    Self {
+      radius: 10.0,
+      max_enemies: 25,
+      spawn_count: 0,
+    }
+  }
+}
+
 impl SpawnComponent {
   pub fn spawn_enemy(&mut self) {
     self.spawn_count += 1;
   }
 
   pub fn remove_enemy(&mut self) {
-    self.spawn_count -= 1;
+    self.spawn_count = self.spawn_count.saturating_sub(1);
   }
 
   pub fn spawn_count(&self) -> usize {
     self.spawn_count
   }
+
+  pub fn is_full(&self) -> bool {
+    self.spawn_count >= self.max_enemies
+  }
+
+  pub fn reset(&mut self) {
+    self.spawn_count = 0;
+  }
 }
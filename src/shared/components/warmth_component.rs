@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+use tagged::{Registerable, Schema, Duplicate};
+
+/// Keeps track of how cold a player is. Warmth drains away from the fire and
+/// recovers next to it, once it runs out the player starts taking damage.
+#[derive(Debug, Clone, Serialize, Deserialize, Registerable, Schema, Duplicate)]
+pub struct WarmthComponent {
+  #[schema(default = "100.0")]
+  pub warmth: f32,
+  #[schema(default = "100.0")]
+  pub max_warmth: f32,
+  #[schema(default = "2.0")]
+  pub drain_per_second: f32,
+  #[schema(default = "10.0")]
+  pub recover_per_second: f32,
+  #[schema(default = "5.0")]
+  pub freezing_damage_per_second: f32,
+  #[serde(skip)]
+  pub is_warm: bool,
+}
+
+impl Default for WarmthComponent {
+  fn default() -> Self {
+    Self {
+      warmth: 100.0,
+      max_warmth: 100.0,
+      drain_per_second: 2.0,
+      recover_per_second: 10.0,
+      freezing_damage_per_second: 5.0,
+      is_warm: false,
+    }
+  }
+}
+
+impl WarmthComponent {
+  pub fn is_freezing(&self) -> bool {
+    self.warmth <= 0.0
+  }
+
+  /// Warmth left as a fraction of `max_warmth`, from 0.0 to 1.0.
+  pub fn ratio(&self) -> f32 {
+    if self.max_warmth <= 0.0 {
+      return 0.0;
+    }
+
+    (self.warmth / self.max_warmth).clamp(0.0, 1.0)
+  }
+}
@@ -0,0 +1,84 @@
+use engine::utils::units::Seconds;
+use serde::{Deserialize, Serialize};
+use tagged::{Registerable, Schema, Duplicate};
+
+/// One kind of enemy a wave can be made of. Each spawn spends `cost` out of
+/// the wave budget, and `weight` decides how often it gets picked.
+#[derive(Debug, Clone, Serialize, Deserialize, Schema, Registerable, Duplicate)]
+pub struct WaveEnemy {
+  #[schema(default = "Swampeter")]
+  pub prefab: String,
+  #[schema(default = "1")]
+  pub cost: u32,
+  #[schema(default = "1.0")]
+  pub weight: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Schema, Registerable, Duplicate)]
+pub struct WaveDefinition {
+  #[schema(default = "5")]
+  pub budget: u32,
+  pub composition: Vec<WaveEnemy>,
+  #[schema(default = "{seconds: 2.0}")]
+  pub spawn_interval: Seconds,
+  #[schema(default = "{seconds: 15.0}")]
+  pub break_duration: Seconds,
+}
+
+/// Data table for the wave director, authored in the level editor.
+/// Waves past the end of the table repeat the last one, escalated by
+/// `budget_growth` and `interval_decay` for every extra wave.
+#[derive(Debug, Clone, Serialize, Deserialize, Schema, Registerable, Duplicate)]
+pub struct WaveDirectorComponent {
+  pub waves: Vec<WaveDefinition>,
+  #[schema(default = "{seconds: 10.0}")]
+  pub first_break: Seconds,
+  #[schema(default = "1.25")]
+  pub budget_growth: f32,
+  #[schema(default = "0.9")]
+  pub interval_decay: f32,
+  #[schema(default = "{seconds: 0.5}")]
+  pub min_spawn_interval: Seconds,
+}
+
+impl Default for WaveDirectorComponent {
+  fn default() -> Self {
+    let swampeter = WaveEnemy {
+      prefab: String::from("Swampeter"),
+      cost: 1,
+      weight: 1.0,
+    };
+
+    Self {
+      waves: vec![WaveDefinition {
+        budget: 5,
+        composition: vec![swampeter],
+        spawn_interval: Seconds::new(2.0),
+        break_duration: Seconds::new(15.0),
+      }],
+      first_break: Seconds::new(10.0),
+      budget_growth: 1.25,
+      interval_decay: 0.9,
+      min_spawn_interval: Seconds::new(0.5),
+    }
+  }
+}
+
+impl WaveDirectorComponent {
+  /// Resolves the definition for a wave, counting from 1. Returns `None`
+  /// when the table is empty.
+  pub fn wave(&self, number: u32) -> Option<WaveDefinition> {
+    let last = self.waves.len().checked_sub(1)?;
+    let index = (number.saturating_sub(1) as usize).min(last);
+    let mut wave = self.waves[index].clone();
+
+    let extra = (number as usize).saturating_sub(self.waves.len()) as i32;
+    if extra > 0 {
+      wave.budget = (wave.budget as f32 * self.budget_growth.powi(extra)).round() as u32;
+      let interval = *wave.spawn_interval * self.interval_decay.powi(extra);
+      wave.spawn_interval = Seconds::new(interval.max(*self.min_spawn_interval));
+    }
+
+    Some(wave)
+  }
+}
@@ -0,0 +1,40 @@
+use crate::shared::weapon::{cleric::Cleric, great_ax::GreatAx, hammer::Hammer, tank::Tank, Weapon};
+use serde::{Deserialize, Serialize};
+use tagged::{Registerable, Schema, Duplicate};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Schema, Registerable, PartialEq, Eq, Duplicate)]
+pub enum WeaponKind {
+  GreatAx,
+  Hammer,
+  Tank,
+  Cleric,
+}
+
+/// The weapon a player wields, so that the server knows what they can do.
+#[derive(Debug, Clone, Serialize, Deserialize, Schema, Registerable, Duplicate)]
+pub struct WeaponComponent {
+  pub kind: WeaponKind,
+}
+
+impl Default for WeaponComponent {
+  fn default() -> Self {
+    Self {
+      kind: WeaponKind::GreatAx,
+    }
+  }
+}
+
+impl WeaponComponent {
+  pub fn new(kind: WeaponKind) -> Self {
+    Self { kind }
+  }
+
+  pub fn weapon(&self) -> Box<dyn Weapon> {
+    match self.kind {
+      WeaponKind::GreatAx => Box::new(GreatAx::new()),
+      WeaponKind::Hammer => Box::new(Hammer::new()),
+      WeaponKind::Tank => Box::new(Tank::new()),
+      WeaponKind::Cleric => Box::new(Cleric::new()),
+    }
+  }
+}
@@ -0,0 +1,64 @@
+use engine::{systems::Backpack, Entity};
+
+use crate::shared::{
+  components::match_component::MatchPhase, systems::goal::components::FireState,
+};
+
+/// Gameplay events raised by the server-authoritative systems during a frame.
+/// Anything the clients need to see is mirrored onto replicated components,
+/// these are only meant for other systems running on the same side. Clients
+/// raise the respawn ones themselves, from the `RespawnComponent` they get.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+  FireStateChanged { fire: Entity, state: FireState },
+  WoodDeposited { player: Entity, fire: Entity, amount: u32 },
+  TreeChopped { player: Entity, tree: Entity },
+  MatchLost,
+  MatchPhaseChanged { phase: MatchPhase },
+  WaveStarted { wave: u32 },
+  WaveCleared { wave: u32 },
+  PlayerDied { player: Entity },
+  PlayerRespawned { player: Entity },
+  DamageDealt { attacker: Entity, target: Entity, amount: f32 },
+  Healed { healer: Entity, target: Entity, amount: f32 },
+}
+
+/// Frame-scoped queue of `GameEvent`s, stored in the backpack.
+#[derive(Debug, Default)]
+pub struct GameEvents {
+  events: Vec<GameEvent>,
+}
+
+impl GameEvents {
+  pub fn new() -> Self {
+    Self { events: vec![] }
+  }
+
+  /// Pushes an event into the backpack queue, creating it on first use.
+  pub fn emit(backpack: &mut Backpack, event: GameEvent) {
+    match backpack.get_mut::<GameEvents>() {
+      Some(events) => events.push(event),
+      None => {
+        let mut events = GameEvents::new();
+        events.push(event);
+        backpack.insert(events);
+      }
+    }
+  }
+
+  pub fn push(&mut self, event: GameEvent) {
+    self.events.push(event);
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = &GameEvent> {
+    self.events.iter()
+  }
+
+  pub fn contains(&self, event: &GameEvent) -> bool {
+    self.events.contains(event)
+  }
+
+  pub fn clear(&mut self) {
+    self.events.clear();
+  }
+}
@@ -1,5 +1,3 @@
-#![cfg(target_arch = "wasm32")]
-
 use engine::application::devices::{
   Devices, KeyboardKey, MouseButton, MouseEvent, MouseState, WindowEvent,
 };
@@ -24,6 +22,10 @@ pub struct PlayerInput {
   pub light_attack: bool,
   pub heavy_attack: bool,
   pub dash: bool,
+  pub deposit: bool,
+  pub chop: bool,
+  pub taunt: bool,
+  pub heal: bool,
 }
 
 impl Default for PlayerInput {
@@ -39,6 +41,10 @@ impl Input for PlayerInput {
     self.sprint = false;
     self.debug = false;
     self.dash = false;
+    self.deposit = false;
+    self.chop = false;
+    self.taunt = false;
+    self.heal = false;
   }
 
   fn normalize(&mut self, count: usize) {
@@ -98,6 +104,10 @@ impl Input for PlayerInput {
         KeyboardKey::S | KeyboardKey::Down => self.direction_vector.z = -1.0,
         KeyboardKey::LShift => self.sprint = true,
         KeyboardKey::E => self.dash = true,
+        KeyboardKey::F => self.deposit = true,
+        KeyboardKey::C => self.chop = true,
+        KeyboardKey::T => self.taunt = true,
+        KeyboardKey::H => self.heal = true,
         KeyboardKey::RShift => self.debug = true,
         _ => {}
       }
@@ -153,6 +163,10 @@ impl PlayerInput {
       light_attack: false,
       heavy_attack: false,
       dash: false,
+      deposit: false,
+      chop: false,
+      taunt: false,
+      heal: false,
     }
   }
 }
@@ -0,0 +1,112 @@
+use crate::shared::components::match_component::{MatchComponent, MatchPhase};
+use engine::{systems::Backpack, utils::units::Seconds};
+
+/// Rules that drive the transitions between match phases.
+#[derive(Debug, Clone)]
+pub struct MatchRules {
+  pub min_players: usize,
+  pub countdown: Seconds,
+  pub time_limit: Seconds,
+  pub results_duration: Seconds,
+}
+
+impl Default for MatchRules {
+  fn default() -> Self {
+    Self {
+      min_players: 1,
+      countdown: Seconds::new(5.0),
+      time_limit: Seconds::new(600.0),
+      results_duration: Seconds::new(10.0),
+    }
+  }
+}
+
+/// Server-side match phase, kept in the backpack.
+///
+/// Lobby -> Countdown -> Active -> Won | Lost -> Lobby
+///
+/// The countdown goes back to the lobby if players drop below the minimum,
+/// an active round is won by surviving `time_limit`, and lost as soon as the
+/// fire goes out. Results stay up for `results_duration`, after which the
+/// level gets reset.
+#[derive(Debug, Clone)]
+pub struct MatchState {
+  pub rules: MatchRules,
+  phase: MatchPhase,
+  phase_time: Seconds,
+}
+
+impl MatchState {
+  pub fn new(rules: MatchRules) -> Self {
+    Self {
+      rules,
+      phase: MatchPhase::Lobby,
+      phase_time: Seconds::new(0.0),
+    }
+  }
+
+  /// Whether the round is being played. Without a match state in the
+  /// backpack (clients, tests) gameplay is always considered active.
+  pub fn is_active(backpack: &Backpack) -> bool {
+    match backpack.get::<MatchState>() {
+      Some(state) => state.phase == MatchPhase::Active,
+      None => true,
+    }
+  }
+
+  pub fn phase(&self) -> MatchPhase {
+    self.phase
+  }
+
+  pub fn phase_time(&self) -> Seconds {
+    self.phase_time
+  }
+
+  /// How long the current phase lasts, zero when it has no time limit.
+  pub fn phase_duration(&self) -> Seconds {
+    match self.phase {
+      MatchPhase::Lobby => Seconds::new(0.0),
+      MatchPhase::Countdown => self.rules.countdown,
+      MatchPhase::Active => self.rules.time_limit,
+      MatchPhase::Won | MatchPhase::Lost => self.rules.results_duration,
+    }
+  }
+
+  /// Advances the clock and applies the transition rules. Returns the new
+  /// phase when it changed.
+  pub fn update(&mut self, dt: f32, players: usize, fire_out: bool) -> Option<MatchPhase> {
+    self.phase_time += Seconds::new(dt);
+
+    let next = match self.phase {
+      MatchPhase::Lobby if players >= self.rules.min_players => Some(MatchPhase::Countdown),
+      MatchPhase::Countdown if players < self.rules.min_players => Some(MatchPhase::Lobby),
+      MatchPhase::Countdown if self.phase_time >= self.rules.countdown => Some(MatchPhase::Active),
+      MatchPhase::Active if fire_out => Some(MatchPhase::Lost),
+      MatchPhase::Active if self.phase_time >= self.rules.time_limit => Some(MatchPhase::Won),
+      MatchPhase::Won | MatchPhase::Lost if self.phase_time >= self.rules.results_duration => {
+        Some(MatchPhase::Lobby)
+      }
+      _ => None,
+    };
+
+    if let Some(phase) = next {
+      self.set_phase(phase);
+    }
+
+    next
+  }
+
+  pub fn set_phase(&mut self, phase: MatchPhase) {
+    self.phase = phase;
+    self.phase_time = Seconds::new(0.0);
+  }
+
+  pub fn to_component(&self, players: usize) -> MatchComponent {
+    MatchComponent {
+      phase: self.phase,
+      phase_time: self.phase_time,
+      phase_duration: self.phase_duration(),
+      players,
+    }
+  }
+}
@@ -1,7 +1,15 @@
 pub mod animations;
 pub mod components;
+pub mod events;
 pub mod follow;
 pub mod game_types;
 pub mod input;
+pub mod match_state;
+pub mod player_inputs;
+pub mod players;
+pub mod replication;
+pub mod rng;
+pub mod threat;
 pub mod systems;
+pub mod time_of_day;
 pub mod weapon;
@@ -0,0 +1,44 @@
+use crate::shared::input::PlayerInput;
+use engine::{application::scene::IdComponent, networking::connection::PlayerId};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Latest input received from every player, stored in the backpack. The
+/// server fills it with what each client sends, and the gameplay systems
+/// drive every player with their own.
+///
+/// A player's entity shares its id with the `PlayerId` that owns it, which
+/// is what inputs are looked up by.
+#[derive(Debug, Default)]
+pub struct PlayerInputs {
+  inputs: HashMap<Uuid, PlayerInput>,
+}
+
+impl PlayerInputs {
+  pub fn new() -> Self {
+    Self {
+      inputs: HashMap::new(),
+    }
+  }
+
+  pub fn insert(&mut self, player_id: &PlayerId, input: PlayerInput) {
+    self.inputs.insert(**player_id, input);
+  }
+
+  /// Stores the input of the player owning the entity with this id.
+  pub fn insert_for(&mut self, id: &IdComponent, input: PlayerInput) {
+    self.inputs.insert(***id, input);
+  }
+
+  pub fn remove(&mut self, player_id: &PlayerId) {
+    self.inputs.remove(&**player_id);
+  }
+
+  pub fn get(&self, id: &IdComponent) -> Option<&PlayerInput> {
+    self.inputs.get(&***id)
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.inputs.is_empty()
+  }
+}
@@ -0,0 +1,18 @@
+use crate::shared::components::respawn_component::RespawnComponent;
+use engine::{
+  application::scene::{Scene, TransformComponent},
+  Entity,
+};
+use nalgebra::Vector3;
+
+/// Every player still in the fight, along with where they stand. Players are
+/// whoever can respawn, which the `NetworkController` hands out on join, and
+/// the dead ones are left out until they're back.
+pub fn alive(scene: &mut Scene) -> Vec<(Entity, Vector3<f32>)> {
+  scene
+    .query_mut::<(&TransformComponent, &RespawnComponent)>()
+    .into_iter()
+    .filter(|(_, (_, respawn))| respawn.is_alive())
+    .map(|(player, (transform, _))| (player, transform.translation))
+    .collect()
+}
@@ -0,0 +1,59 @@
+use engine::{
+  application::scene::{IdComponent, PrefabId, Scene},
+  systems::Backpack,
+  Entity,
+};
+use std::collections::HashSet;
+
+/// Entities whose replicated components changed this frame, and that need
+/// to be pushed to every connected client, along with the ids of those that
+/// got despawned. Shared systems only mark them, the server decides how and
+/// when they are sent.
+#[derive(Debug, Default)]
+pub struct ReplicationQueue {
+  entities: HashSet<Entity>,
+  despawned: Vec<PrefabId>,
+}
+
+impl ReplicationQueue {
+  pub fn new() -> Self {
+    Self {
+      entities: HashSet::new(),
+      despawned: vec![],
+    }
+  }
+
+  /// The queue kept in `backpack`, creating it on first use.
+  fn of(backpack: &mut Backpack) -> &mut Self {
+    if backpack.get::<ReplicationQueue>().is_none() {
+      backpack.insert(ReplicationQueue::new());
+    }
+    backpack.get_mut::<ReplicationQueue>().unwrap()
+  }
+
+  /// Marks an entity as dirty, creating the queue on first use.
+  pub fn mark(backpack: &mut Backpack, entity: Entity) {
+    Self::of(backpack).entities.insert(entity);
+  }
+
+  /// Marks an entity as despawned, so that clients remove it too. Has to be
+  /// called right before it leaves the scene, while its id can be looked up.
+  pub fn despawn(scene: &mut Scene, backpack: &mut Backpack, entity: Entity) {
+    let id = match scene.query_one_mut::<&IdComponent>(entity) {
+      Ok(id) => **id,
+      Err(_) => return,
+    };
+
+    let queue = Self::of(backpack);
+    queue.entities.remove(&entity);
+    queue.despawned.push(id);
+  }
+
+  pub fn drain(&mut self) -> impl Iterator<Item = Entity> + '_ {
+    self.entities.drain()
+  }
+
+  pub fn drain_despawned(&mut self) -> impl Iterator<Item = PrefabId> + '_ {
+    self.despawned.drain(..)
+  }
+}
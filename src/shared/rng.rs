@@ -0,0 +1,51 @@
+use engine::systems::Backpack;
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+
+/// Random number generator shared by the gameplay systems, stored in the
+/// backpack. Seeding it makes a simulation replay exactly the same way,
+/// which is what the tests rely on.
+#[derive(Debug, Clone)]
+pub struct GameRng {
+  rng: StdRng,
+}
+
+impl GameRng {
+  pub fn from_seed(seed: u64) -> Self {
+    Self {
+      rng: StdRng::seed_from_u64(seed),
+    }
+  }
+
+  pub fn from_entropy() -> Self {
+    Self {
+      rng: StdRng::from_entropy(),
+    }
+  }
+
+  /// The generator in the backpack, seeded from entropy on first use.
+  pub fn get(backpack: &mut Backpack) -> &mut GameRng {
+    if backpack.get::<GameRng>().is_none() {
+      backpack.insert(GameRng::from_entropy());
+    }
+
+    backpack.get_mut::<GameRng>().unwrap()
+  }
+}
+
+impl RngCore for GameRng {
+  fn next_u32(&mut self) -> u32 {
+    self.rng.next_u32()
+  }
+
+  fn next_u64(&mut self) -> u64 {
+    self.rng.next_u64()
+  }
+
+  fn fill_bytes(&mut self, dest: &mut [u8]) {
+    self.rng.fill_bytes(dest)
+  }
+
+  fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+    self.rng.try_fill_bytes(dest)
+  }
+}
@@ -1,21 +1,18 @@
-#![cfg(target_arch = "wasm32")]
 use crate::shared::{
   components::{
     health_component::HealthComponent, inventory_component::InventoryComponent,
-    movement_component::MovementComponent,
+    movement_component::MovementComponent, respawn_component::RespawnComponent,
   },
-  input::PlayerInput,
+  replication::ReplicationQueue,
 };
 use engine::application::scene::component_registry::Access;
 use engine::{
   application::{
-    components::{AnimationComponent, InputComponent, PhysicsComponent, SelfComponent},
-    input::DefaultInput,
+    components::{AnimationComponent, PhysicsComponent},
     physics3d::{ColliderHandle, CollisionEvent},
     scene::{Scene, TagComponent, TransformComponent},
   },
   systems::{
-    input::{CanvasController, InputsReader},
     physics::{CollisionsReader, PhysicsConfig, PhysicsController},
     Backpack, Initializable, Inventory, System,
   },
@@ -32,19 +29,16 @@ use std::{
 /// This is probably a temporary solution until we can get a proper API working for it.
 
 pub struct CollisionSystem {
-  inputs: InputsReader<PlayerInput>,
   physics: PhysicsController,
   collisions_reader: CollisionsReader,
 }
 
 impl Initializable for CollisionSystem {
   fn initialize(inventory: &Inventory) -> Self {
-    let inputs = inventory.get::<InputsReader<PlayerInput>>().clone();
     let physics = inventory.get::<PhysicsController>().clone();
     let collisions_reader = inventory.get::<CollisionsReader>().clone();
 
     Self {
-      inputs,
       physics,
       collisions_reader,
     }
@@ -63,16 +57,24 @@ impl System for CollisionSystem {
 
   fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
     let collisions = self.collisions_reader.read().collect::<Vec<_>>();
+    let mut changed = vec![];
     for collision_event in collisions {
       match collision_event {
         CollisionEvent::Started(collider1, collider2, _) => {
           let (entity1, entity2, entity1_tag, entity2_tag) =
-            self.get_entity_and_tag(scene, collider1, collider2);
+            match self.get_entity_and_tag(scene, collider1, collider2) {
+              Some(collision) => collision,
+              None => continue,
+            };
 
           if entity1_tag.name == "Wood" && entity2_tag.name == "Wizard" {
-            self.handle_wizard_wood_collision_start(scene, entity2, entity1);
+            if self.handle_wizard_wood_collision_start(scene, backpack, entity2, entity1) {
+              changed.push(entity2);
+            }
           } else if entity2_tag.name == "Wood" && entity1_tag.name == "Wizard" {
-            self.handle_wizard_wood_collision_start(scene, entity1, entity2);
+            if self.handle_wizard_wood_collision_start(scene, backpack, entity1, entity2) {
+              changed.push(entity1);
+            }
           }
           if entity1_tag.name == "Terrain" && entity2_tag.name == "Wizard" {
             self.handle_foxy_terrain_collision_start(scene, entity2);
@@ -99,73 +101,75 @@ impl System for CollisionSystem {
       }
     }
 
+    for entity in changed {
+      ReplicationQueue::mark(backpack, entity);
+    }
+
     let dt = **backpack.get::<Time>().unwrap();
   }
 }
 
 impl CollisionSystem {
+  /// The entities behind both colliders along with their tags, or `None`
+  /// when either of them doesn't belong to a tagged entity.
   fn get_entity_and_tag(
     &self,
-    mut scene: &mut Scene,
+    scene: &mut Scene,
     collider1: ColliderHandle,
     collider2: ColliderHandle,
-  ) -> (Entity, Entity, TagComponent, TagComponent) {
-    log::info!("Getting entity and tag: {:?} {:?}", collider1, collider2);
-    let entity1 = self
-      .physics
-      .get_entity_from_collider_handle(collider1)
-      .unwrap();
-
-    let entity2 = self
-      .physics
-      .get_entity_from_collider_handle(collider2)
-      .unwrap();
-
-    let entity1_tag = scene
-      .query_one_mut::<&TagComponent>(entity1)
-      .unwrap()
-      .clone();
-
-    let entity2_tag = scene
-      .query_one_mut::<&TagComponent>(entity2)
-      .unwrap()
-      .clone();
-
-    (entity1, entity2, entity1_tag, entity2_tag)
+  ) -> Option<(Entity, Entity, TagComponent, TagComponent)> {
+    log::trace!("Getting entity and tag: {:?} {:?}", collider1, collider2);
+    let entity1 = self.physics.get_entity_from_collider_handle(collider1)?;
+    let entity2 = self.physics.get_entity_from_collider_handle(collider2)?;
+
+    let entity1_tag = scene.query_one_mut::<&TagComponent>(entity1).ok()?.clone();
+    let entity2_tag = scene.query_one_mut::<&TagComponent>(entity2).ok()?.clone();
+
+    Some((entity1, entity2, entity1_tag, entity2_tag))
   }
 
   fn handle_foxy_terrain_collision_start(&mut self, scene: &mut Scene, wizard_entity: Entity) {
-    let mut movement: &mut MovementComponent = scene
-      .query_one_mut::<&mut MovementComponent>(wizard_entity)
-      .unwrap();
+    let mut movement: &mut MovementComponent =
+      match scene.query_one_mut::<&mut MovementComponent>(wizard_entity) {
+        Ok(movement) => movement,
+        Err(_) => return,
+      };
   }
 
   fn handle_foxy_terrain_collision_stop(&mut self, scene: &mut Scene, wizard_entity: Entity) {
-    let mut movement: &mut MovementComponent = scene
-      .query_one_mut::<&mut MovementComponent>(wizard_entity)
-      .unwrap();
+    let mut movement: &mut MovementComponent =
+      match scene.query_one_mut::<&mut MovementComponent>(wizard_entity) {
+        Ok(movement) => movement,
+        Err(_) => return,
+      };
   }
 
   fn handle_wizard_wood_collision_start(
     &mut self,
     scene: &mut Scene,
+    backpack: &mut Backpack,
     wizard_entity: Entity,
     wood_entity: Entity,
-  ) {
-    if let Ok(player_inventory) = scene.query_one_mut::<&mut InventoryComponent>(wizard_entity) {
-      if player_inventory.is_full() {
-        return;
+  ) -> bool {
+    if let Ok((player_inventory, respawn)) =
+      scene.query_one_mut::<(&mut InventoryComponent, Option<&RespawnComponent>)>(wizard_entity)
+    {
+      // The dead walk through the wood they fall on
+      if player_inventory.is_full() || respawn.map_or(false, |respawn| !respawn.is_alive()) {
+        return false;
       }
 
       player_inventory.add_wood(1);
 
-      let physics: &mut PhysicsComponent = scene
-        .query_one_mut::<&mut PhysicsComponent>(wood_entity)
-        .unwrap();
-
-      self.physics.despawn(&physics);
+      if let Ok(physics) = scene.query_one_mut::<&mut PhysicsComponent>(wood_entity) {
+        self.physics.despawn(&physics);
+      }
+      ReplicationQueue::despawn(scene, backpack, wood_entity);
       scene.remove_entity(wood_entity);
+      return true;
     }
+
+    false
   }
 
   fn handle_wizard_enemy_collision_start(
@@ -174,9 +178,8 @@ impl CollisionSystem {
     wizard_entity: Entity,
     enemy_entity: Entity,
   ) {
-    let mut health = scene
-      .query_one_mut::<&mut HealthComponent>(wizard_entity)
-      .unwrap();
-    health.pending_damage += 5.0;
+    if let Ok(health) = scene.query_one_mut::<&mut HealthComponent>(wizard_entity) {
+      health.pending_damage += 5.0;
+    }
   }
 }
@@ -1,5 +1,3 @@
-#![cfg(target_arch = "wasm32")]
-
 use crate::shared::animations::attack_transitions::AttackTransitions;
 use crate::shared::components::attack_component::AttackComponent;
 use crate::shared::components::attack_component::AttackType;
@@ -9,28 +7,27 @@ use crate::shared::components::attack_component::HEAVY_ATTACK;
 use crate::shared::components::attack_component::LIGHT_ATTACK;
 use crate::shared::components::attack_component::NO_ATTACK;
 use crate::shared::components::movement_component::MovementComponent;
+use crate::shared::components::respawn_component::RespawnComponent;
 
 use crate::shared::components::health_component::HealthComponent;
 use crate::shared::components::lifetime_component::LifetimeComponent;
 use crate::shared::components::shield_component::ShieldComponent;
 
+use crate::shared::events::{GameEvent, GameEvents};
 use crate::shared::game_types::game_types::PrefabType;
-use crate::shared::input::PlayerInput;
+use crate::shared::player_inputs::PlayerInputs;
+use crate::shared::replication::ReplicationQueue;
 use engine::application::components::StateMachineComponent;
 use engine::application::scene::component_registry::Access;
 use engine::renderer::resources::animation::AnimationId;
 use engine::systems::rendering::DebugController;
 use engine::{
   application::{
-    components::{
-      AnimationComponent, InputComponent, ParticleComponent, PhysicsComponent, SelfComponent,
-    },
-    input::DefaultInput,
+    components::{AnimationComponent, ParticleComponent, PhysicsComponent},
     physics3d::Physics3d,
     scene::{IdComponent, Scene, TagComponent, TransformComponent},
   },
   systems::{
-    input::{CanvasController, InputsReader},
     physics::{PhysicsConfig, PhysicsController},
     Backpack, Initializable, Inventory, System,
   },
@@ -47,23 +44,16 @@ use uuid::uuid;
 
 pub struct CombatSystem {
   damage_inflicted: f32,
-  inputs: InputsReader<PlayerInput>,
   physics: PhysicsController,
-  anim_timer: f32,
-  anim_running: bool,
 }
 
 impl CombatSystem {
   pub fn new(inventory: &Inventory) -> Self {
-    let inputs = inventory.get::<InputsReader<PlayerInput>>().clone();
     let mut physics = inventory.get::<PhysicsController>().clone();
 
     Self {
       damage_inflicted: 0.0,
-      inputs,
       physics,
-      anim_timer: 0.0,
-      anim_running: false,
     }
   }
 
@@ -80,33 +70,54 @@ impl CombatSystem {
   }
 
   fn reset_animation(&mut self, attack: &mut AttackComponent) {
-    self.anim_running = false;
-    self.anim_timer = 0.0;
+    attack.anim_running = false;
+    attack.anim_timer = 0.0;
     attack.attack_type_damage = NO_ATTACK;
     attack.animation_fired = false;
   }
 
+  /// Every player swings on their own input. Attacks only start once the
+  /// previous one is over and the cooldown has run out. Dead players can't
+  /// swing until they're back.
   fn handle_input(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
-    let mut ent = Entity::DANGLING;
     let dt = **backpack.get::<Time>().unwrap();
+    let mut attacks = vec![];
+
+    let inputs = match backpack.get::<PlayerInputs>() {
+      Some(inputs) => inputs,
+      None => return,
+    };
 
-    for (current_entity, (_, attack, movement, physics)) in scene.query_mut::<(
-      &SelfComponent,
+    for (current_entity, (id, attack, movement, physics, respawn)) in scene.query_mut::<(
+      &IdComponent,
       &mut AttackComponent,
       &mut MovementComponent,
       &PhysicsComponent,
+      Option<&RespawnComponent>,
     )>() {
-      if self.anim_running == true {
-        self.anim_timer += dt * 1000.0;
+      let input = match inputs.get(id) {
+        Some(input) => input,
+        None => continue,
+      };
+
+      if let Some(respawn) = respawn && !respawn.is_alive() {
+        self.reset_animation(attack);
+        continue;
       }
 
-      if self.anim_timer > attack.light_anim_end_time && attack.attack_type_damage == LIGHT_ATTACK {
+      if attack.anim_running == true {
+        attack.anim_timer += dt * 1000.0;
+      }
+
+      if attack.anim_timer > attack.light_anim_end_time
+        && attack.attack_type_damage == LIGHT_ATTACK
+      {
         self.reset_animation(attack);
-      } else if self.anim_timer > attack.heavy_anim_end_time
+      } else if attack.anim_timer > attack.heavy_anim_end_time
         && attack.attack_type_damage == HEAVY_ATTACK
       {
         self.reset_animation(attack);
-      } else if self.anim_timer > 1800.0 && attack.attack_type_damage == AIR_ATTACK {
+      } else if attack.anim_timer > 1800.0 && attack.attack_type_damage == AIR_ATTACK {
         self.reset_animation(attack);
       }
 
@@ -118,34 +129,44 @@ impl CombatSystem {
         attack.attacked = false;
       }
 
-      let input = self.inputs.read();
+      if !attack.attacked && !attack.anim_running {
+        let attack_type_damage = if input.heavy_attack {
+          Some(HEAVY_ATTACK)
+        } else if input.light_attack {
+          Some(LIGHT_ATTACK)
+        } else {
+          None
+        };
+
+        if let Some(attack_type_damage) = attack_type_damage {
+          attack.attack_type_damage = attack_type_damage;
+          attack.attacked = true;
+          attack.cooldown_timer = 0.0;
+          attack.anim_running = true;
+          attack.anim_timer = 0.0;
+        }
+      }
 
       attack.air_timer += dt;
       if (attack.attack_type_damage == LIGHT_ATTACK
-        && self.anim_timer > attack.light_anim_start_time
+        && attack.anim_timer > attack.light_anim_start_time
         && attack.animation_fired == false)
         || (attack.attack_type_damage == HEAVY_ATTACK
-          && self.anim_timer > attack.heavy_anim_start_time
+          && attack.anim_timer > attack.heavy_anim_start_time
           && attack.animation_fired == false)
         || (attack.attack_type_damage == AIR_ATTACK && attack.animation_fired == false)
       {
-        ent = current_entity;
-
-        let mut physics = self.physics.clone();
         let max_distance = attack.max_distance;
         let damage = attack.damage;
         let damage_multiplier = attack.attack_type_damage.damage_multiplier;
         attack.animation_fired = true;
-        self.handle_attack(
-          scene,
-          ent,
-          &mut physics,
-          damage * damage_multiplier,
-          max_distance,
-          backpack,
-        );
+        attacks.push((current_entity, damage * damage_multiplier, max_distance));
       }
-      break;
+    }
+
+    for (entity, damage, max_distance) in attacks {
+      let mut physics = self.physics.clone();
+      self.handle_attack(scene, entity, &mut physics, damage, max_distance, backpack);
     }
   }
 
@@ -159,7 +180,9 @@ impl CombatSystem {
     knockback_direction: Vector3<f32>,
     filter: QueryFilter,
     max_distance: f32,
+    backpack: &mut Backpack,
   ) {
+    let attacker = entity;
     let solid = false;
     let mut hit_entities = HashSet::new();
     for ray in rays {
@@ -182,6 +205,14 @@ impl CombatSystem {
           if !hit_entities.contains(&entity) {
             health.pending_damage += damage;
             hit_entities.insert(entity);
+            GameEvents::emit(
+              backpack,
+              GameEvent::DamageDealt {
+                attacker,
+                target: entity,
+                amount: damage,
+              },
+            );
           }
         }
       }
@@ -255,6 +286,7 @@ impl CombatSystem {
         knockback_direction,
         filter,
         max_distance,
+        backpack,
       );
     }
   }
@@ -267,6 +299,7 @@ impl CombatSystem {
   ) {
     let dt = **backpack.get::<Time>().unwrap();
     let mut positions = vec![];
+    let mut damaged = vec![];
 
     for (current_entity, (health, shield, tag, physics, transform, maybe_movement)) in scene
       .query_mut::<(
@@ -289,6 +322,7 @@ impl CombatSystem {
         }
         shield.time_last_damage = 0.0;
         health.pending_damage = 0.0;
+        damaged.push(current_entity);
         // PARTICLE SPAWN START
         let mut spawn_translation = transform.translation;
         spawn_translation.y += 0.5;
@@ -326,19 +360,19 @@ impl CombatSystem {
       }
     }
     positions.clear();
+
+    for entity in damaged {
+      ReplicationQueue::mark(backpack, entity);
+    }
   }
 }
 
 impl Initializable for CombatSystem {
   fn initialize(inventory: &Inventory) -> Self {
-    let inputs = inventory.get::<InputsReader<PlayerInput>>().clone();
     let mut physics = inventory.get::<PhysicsController>().clone();
     Self {
       damage_inflicted: 0.0,
-      inputs,
       physics,
-      anim_timer: 0.0,
-      anim_running: false,
     }
   }
 }
@@ -351,9 +385,8 @@ impl System for CombatSystem {
   }
 
   fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
-    let input = self.inputs.read();
     let mut physics = self.physics.clone();
-    // self.handle_input(scene, backpack);
-    // self.handle_components(scene, backpack, &mut physics);
+    self.handle_input(scene, backpack);
+    self.handle_components(scene, backpack, &mut physics);
   }
 }
@@ -1,23 +1,21 @@
-#![cfg(target_arch = "wasm32")]
 use crate::shared::{
   components::{
     enemy_ai_component::EnemyAiComponent, health_component::HealthComponent,
-    movement_component::MovementComponent, spawn_component::SpawnComponent,
+    movement_component::MovementComponent, respawn_component::RespawnComponent,
+    spawn_component::SpawnComponent,
   },
   game_types::game_types::PrefabType,
-  input::PlayerInput,
+  replication::ReplicationQueue,
 };
 use engine::application::scene::component_registry::Access;
 
 use engine::{
   application::{
-    components::{InputComponent, PhysicsComponent, SelfComponent},
-    input::DefaultInput,
+    components::PhysicsComponent,
     physics3d::CollisionEvent,
     scene::{IdComponent, Scene, TagComponent, TransformComponent},
   },
   systems::{
-    input::{CanvasController, InputsReader},
     physics::{CollisionsReader, PhysicsConfig, PhysicsController},
     Backpack, Initializable, Inventory, System,
   },
@@ -27,15 +25,13 @@ use nalgebra::{Rotation2, Rotation3, UnitQuaternion, Vector2, Vector3};
 use uuid::Uuid;
 
 pub struct DeathSystem {
-  inputs: InputsReader<PlayerInput>,
   physics: PhysicsController,
 }
 
 impl Initializable for DeathSystem {
   fn initialize(inventory: &Inventory) -> Self {
-    let inputs = inventory.get::<InputsReader<PlayerInput>>().clone();
     let physics = inventory.get::<PhysicsController>().clone();
-    Self { inputs, physics }
+    Self { physics }
   }
 }
 
@@ -50,12 +46,18 @@ impl System for DeathSystem {
 
   fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
     let mut dead_entities = vec![];
-    for (entity, (health, tag, physics, transform)) in scene.query_mut::<(
+    for (entity, (health, tag, physics, transform, respawn)) in scene.query_mut::<(
       &mut HealthComponent,
       &TagComponent,
       &mut PhysicsComponent,
       &TransformComponent,
+      Option<&RespawnComponent>,
     )>() {
+      // Players come back through the respawn flow instead
+      if respawn.is_some() {
+        continue;
+      }
+
       if health.health <= 0.0 {
         dead_entities.push((
           entity.clone(),
@@ -75,32 +77,35 @@ impl System for DeathSystem {
             physics.joint.body.id = Uuid::new_v4();
           }
         }
+        scene.create_with_prefab(collectible_entity, collectible_prefab);
+        ReplicationQueue::mark(backpack, collectible_entity);
+      }
 
-        if PrefabType::from(tag.name.as_str()) == PrefabType::Enemy {
-          let ai: &mut EnemyAiComponent = scene
-            .query_one_mut::<&mut EnemyAiComponent>(entity)
-            .unwrap();
+      if PrefabType::from(tag.name.as_str()) == PrefabType::Enemy {
+        let ai: &mut EnemyAiComponent = scene
+          .query_one_mut::<&mut EnemyAiComponent>(entity)
+          .unwrap();
 
-          let spawn = ai.spawned_from();
+        let spawn = ai.spawned_from();
 
-          let mut spawn_entity = match scene.get_entity_mut(spawn) {
-            Some(entity) => Some(entity.clone()),
-            None => None,
-          };
+        let mut spawn_entity = match scene.get_entity_mut(spawn) {
+          Some(entity) => Some(entity.clone()),
+          None => None,
+        };
 
-          if spawn_entity.is_some() {
-            match scene.query_one_mut::<&mut SpawnComponent>(spawn_entity.unwrap()) {
-              Ok(component) => {
-                component.remove_enemy();
-              }
-              Err(_) => (),
-            };
-          }
+        if spawn_entity.is_some() {
+          match scene.query_one_mut::<&mut SpawnComponent>(spawn_entity.unwrap()) {
+            Ok(component) => {
+              component.remove_enemy();
+            }
+            Err(_) => (),
+          };
         }
-        scene.create_with_prefab(collectible_entity, collectible_prefab);
-        scene.remove_entity(entity);
-        self.physics.despawn(&physics);
       }
+
+      ReplicationQueue::despawn(scene, backpack, entity);
+      scene.remove_entity(entity);
+      self.physics.despawn(&physics);
     }
   }
 }
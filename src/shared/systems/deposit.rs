@@ -0,0 +1,136 @@
+use crate::shared::{
+  components::{inventory_component::InventoryComponent, respawn_component::RespawnComponent},
+  events::{GameEvent, GameEvents},
+  player_inputs::PlayerInputs,
+  replication::ReplicationQueue,
+  systems::goal::components::FireComponent,
+};
+use engine::{
+  application::scene::{IdComponent, Scene, TransformComponent},
+  systems::{Backpack, Initializable, Inventory, System},
+  utils::units::Time,
+  Entity,
+};
+use nalgebra::{Point3, Vector3};
+
+struct FireSpot {
+  entity: Entity,
+  translation: Vector3<f32>,
+  radius: f32,
+  auto_deposit: bool,
+  interval: f32,
+  fuel_per_wood: f32,
+  missing_fuel: f32,
+}
+
+/// Feeds the wood carried by players into the nearest fire they stand next to.
+/// Depending on the fire, logs go in on their own or only while the player
+/// asks for it, one log every `deposit_interval`.
+pub struct DepositSystem {}
+
+impl Initializable for DepositSystem {
+  fn initialize(_: &Inventory) -> Self {
+    Self {}
+  }
+}
+
+impl System for DepositSystem {
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    let dt = **backpack.get::<Time>().unwrap();
+
+    let mut fires = vec![];
+    for (entity, (transform, fire)) in scene.query_mut::<(&TransformComponent, &FireComponent)>() {
+      if fire.is_out() {
+        continue;
+      }
+
+      fires.push(FireSpot {
+        entity,
+        translation: transform.translation,
+        radius: *fire.deposit_radius,
+        auto_deposit: fire.auto_deposit,
+        interval: *fire.deposit_interval,
+        fuel_per_wood: fire.fuel_per_wood,
+        missing_fuel: fire.max_fuel - fire.fuel,
+      });
+    }
+
+    let mut deposits: Vec<(Entity, usize)> = vec![];
+
+    let inputs = backpack.get::<PlayerInputs>();
+
+    for (player, (id, transform, inventory, respawn)) in scene.query_mut::<(
+      &IdComponent,
+      &TransformComponent,
+      &mut InventoryComponent,
+      Option<&RespawnComponent>,
+    )>() {
+      let held = inputs
+        .and_then(|inputs| inputs.get(id))
+        .map_or(false, |input| input.deposit);
+      let requested = inventory.deposit_requested || held;
+      inventory.deposit_requested = false;
+
+      if let Some(respawn) = respawn && !respawn.is_alive() {
+        inventory.deposit_timer = 0.0;
+        continue;
+      }
+
+      let nearest = fires
+        .iter()
+        .enumerate()
+        .map(|(index, fire)| {
+          let distance = nalgebra::distance(
+            &Point3::from(transform.translation),
+            &Point3::from(fire.translation),
+          );
+          (index, distance)
+        })
+        .filter(|(index, distance)| *distance <= fires[*index].radius)
+        .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+      let index = match nearest {
+        Some((index, _)) if inventory.get_wood() > 0 => index,
+        _ => {
+          inventory.deposit_timer = 0.0;
+          continue;
+        }
+      };
+
+      let fire = &mut fires[index];
+      if !(fire.auto_deposit || requested) || fire.missing_fuel <= 0.0 {
+        continue;
+      }
+
+      inventory.deposit_timer += dt;
+      if inventory.deposit_timer < fire.interval {
+        continue;
+      }
+
+      inventory.deposit_timer = 0.0;
+      if inventory.deposit_wood() {
+        fire.missing_fuel -= fire.fuel_per_wood;
+        deposits.push((player, index));
+      }
+    }
+
+    for (player, index) in deposits {
+      let fire_entity = fires[index].entity;
+
+      if let Ok(fire) = scene.query_one_mut::<&mut FireComponent>(fire_entity) {
+        fire.add_fuel(fires[index].fuel_per_wood);
+      }
+
+      ReplicationQueue::mark(backpack, player);
+      ReplicationQueue::mark(backpack, fire_entity);
+      GameEvents::emit(
+        backpack,
+        GameEvent::WoodDeposited {
+          player,
+          fire: fire_entity,
+          amount: 1,
+        },
+      );
+    }
+  }
+}
@@ -1,20 +1,20 @@
-#![cfg(target_arch = "wasm32")]
-
 use crate::shared::{
-  components::{enemy_ai_component::EnemyAiComponent, movement_component::MovementComponent},
+  components::{
+    enemy_ai_component::EnemyAiComponent, movement_component::MovementComponent,
+  },
   game_types::game_types::EnemyState,
-  input::PlayerInput,
+  players,
+  rng::GameRng,
+  threat::ThreatTables,
 };
 use engine::application::scene::component_registry::Access;
 use engine::{
   application::{
-    components::{AnimationComponent, InputComponent, PhysicsComponent, SelfComponent},
-    input::DefaultInput,
+    components::{AnimationComponent, PhysicsComponent},
     physics3d::CollisionEvent,
     scene::{Scene, TagComponent, TransformComponent},
   },
   systems::{
-    input::{CanvasController, InputsReader},
     physics::{CollisionsReader, PhysicsConfig, PhysicsController},
     Backpack, Initializable, Inventory, System,
   },
@@ -22,7 +22,8 @@ use engine::{
   Entity,
 };
 use nalgebra::{Rotation2, Rotation3, UnitQuaternion, Vector2, Vector3};
-use rand::{thread_rng, Rng};
+use rand::Rng;
+use std::collections::HashMap;
 
 pub struct EnemyAiSystem {
   physics: PhysicsController,
@@ -47,29 +48,47 @@ impl System for EnemyAiSystem {
     let dt = **backpack.get::<Time>().unwrap();
 
     // Collect targets' (players') positions
-    let mut targets: Vec<Vector3<f32>> = vec![];
-    for (_, (_, transform)) in scene.query_mut::<(&SelfComponent, &TransformComponent)>() {
-      targets.push(transform.translation.clone())
+    let targets: Vec<(Entity, Vector3<f32>)> = players::alive(scene);
+
+    // Pick every enemy's target, the player highest on its threat table
+    // within detection radius, or else the closest one
+    let tables = backpack.get::<ThreatTables>();
+    let mut chosen: HashMap<Entity, (Vector3<f32>, f32)> = HashMap::new();
+    for (enemy, (transform, ai)) in scene.query_mut::<(&TransformComponent, &EnemyAiComponent)>() {
+      let in_range: Vec<(Entity, Vector3<f32>, f32)> = targets
+        .iter()
+        .map(|&(player, target)| (player, target, (transform.translation - target).magnitude()))
+        .filter(|&(_, _, distance)| distance <= *ai.detection_radius)
+        .collect();
+
+      let preferred = tables
+        .and_then(|tables| tables.get(enemy))
+        .and_then(|table| table.target(in_range.iter().map(|&(player, _, _)| player)));
+
+      let target = match preferred {
+        Some(preferred) => in_range.iter().find(|&&(player, _, _)| player == preferred),
+        None => in_range.iter().min_by(|&(_, _, dist_a), &(_, _, dist_b)| {
+          dist_a
+            .partial_cmp(dist_b)
+            .unwrap_or(std::cmp::Ordering::Equal)
+        }),
+      };
+
+      if let Some(&(_, target, distance)) = target {
+        chosen.insert(enemy, (target, distance));
+      }
     }
 
+    let rng = GameRng::get(backpack);
+
     // Process each enemy
-    for (_, (physics, transform, ai, tag)) in scene.query_mut::<(
+    for (enemy, (physics, transform, ai, tag)) in scene.query_mut::<(
       &mut PhysicsComponent,
       &mut TransformComponent,
       &mut EnemyAiComponent,
       &TagComponent,
     )>() {
-      // Find the closest target within detection radius
-      if let Some((closest_target, closest_distance)) = targets
-        .iter()
-        .map(|&target| (target, (transform.translation - target).magnitude()))
-        .filter(|&(_, distance)| distance <= *ai.detection_radius)
-        .min_by(|&(_, dist_a), &(_, dist_b)| {
-          dist_a
-            .partial_cmp(&dist_b)
-            .unwrap_or(std::cmp::Ordering::Equal)
-        })
-      {
+      if let Some(&(closest_target, closest_distance)) = chosen.get(&enemy) {
         // Update enemy state based on distance to target
         if closest_distance <= *ai.attack_range {
           ai.state = EnemyState::Attacking;
@@ -100,7 +119,7 @@ impl System for EnemyAiSystem {
       } else {
         // No player in detection radius, remain idle or perform other actions
         ai.state = EnemyState::Idle;
-        self.handle_pacing(ai, transform, physics, dt);
+        self.handle_pacing(ai, transform, physics, dt, rng);
       }
 
       // Handle cooldown and other timers
@@ -128,11 +147,12 @@ impl EnemyAiSystem {
     transform: &mut TransformComponent,
     physics: &mut PhysicsComponent,
     dt: f32,
+    rng: &mut GameRng,
   ) {
     if ai.pacing_direction.is_none() || *ai.pacing_time_remaining <= 0.0 {
       // Start pacing in a new random direction for a random duration up to 20 seconds
-      ai.pacing_direction = Some(self.pick_random_direction());
-      ai.pacing_time_remaining = Seconds::new(thread_rng().gen_range(0.0..20.0));
+      ai.pacing_direction = Some(self.pick_random_direction(rng));
+      ai.pacing_time_remaining = Seconds::new(rng.gen_range(0.0..20.0));
     } else {
       // Calculate end point for this pacing step
       let pacing_step_distance = ai.pacing_speed * Seconds::new(dt); // Distance to move this frame
@@ -154,8 +174,7 @@ impl EnemyAiSystem {
     }
   }
 
-  fn pick_random_direction(&self) -> Vector3<f32> {
-    let mut rng = thread_rng();
+  fn pick_random_direction(&self, rng: &mut GameRng) -> Vector3<f32> {
     let angle = rng.gen_range(0.0..std::f32::consts::PI * 2.0);
     Vector3::new(angle.cos(), 0.0, angle.sin()) // Assuming movement on a flat plane
   }
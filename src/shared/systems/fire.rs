@@ -0,0 +1,77 @@
+use crate::shared::{
+  events::{GameEvent, GameEvents},
+  match_state::MatchState,
+  replication::ReplicationQueue,
+  systems::goal::components::{FireComponent, FireState},
+  time_of_day::TimeOfDay,
+};
+use engine::{
+  application::scene::Scene,
+  systems::{Backpack, Initializable, Inventory, System},
+  utils::units::Time,
+  Entity,
+};
+
+/// How often, in seconds, the fuel level is pushed to clients while nothing
+/// else about the fire changes.
+const REPLICATION_INTERVAL: f32 = 0.5;
+
+/// Burns the fuel of every fire, and ends the match once one of them goes out.
+pub struct FireSystem {
+  replication_timer: f32,
+}
+
+impl Initializable for FireSystem {
+  fn initialize(_: &Inventory) -> Self {
+    Self {
+      replication_timer: 0.0,
+    }
+  }
+}
+
+impl System for FireSystem {
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    if !MatchState::is_active(backpack) {
+      return;
+    }
+
+    let dt = **backpack.get::<Time>().unwrap();
+
+    self.replication_timer += dt;
+    let replicate_all = self.replication_timer >= REPLICATION_INTERVAL;
+    if replicate_all {
+      self.replication_timer = 0.0;
+    }
+
+    // Fires burn faster through the night
+    let burn_multiplier = TimeOfDay::burn_multiplier(backpack);
+
+    let mut changed: Vec<(Entity, Option<FireState>)> = vec![];
+
+    for (entity, fire) in scene.query_mut::<&mut FireComponent>() {
+      if fire.is_out() {
+        continue;
+      }
+
+      fire.burn(fire.burn_rate * burn_multiplier * dt);
+
+      let state = fire.update_state();
+      if state.is_some() || replicate_all {
+        changed.push((entity, state));
+      }
+    }
+
+    for (entity, state) in changed {
+      ReplicationQueue::mark(backpack, entity);
+
+      if let Some(state) = state {
+        log::info!("[fire] {:?} is now {:?}", entity, state);
+        GameEvents::emit(backpack, GameEvent::FireStateChanged { fire: entity, state });
+
+        if state == FireState::Out {
+          GameEvents::emit(backpack, GameEvent::MatchLost);
+        }
+      }
+    }
+  }
+}
@@ -5,7 +5,7 @@ use std::collections::{HashMap, hash_map::Entry};
 use engine::{
   application::scene::{Scene, TransformComponent, UnpackEntity},
   systems::{Backpack, Initializable, Inventory, System},
-  utils::units::{Radians, Time, Meters},
+  utils::units::{Radians, Time, Meters, Seconds},
   Entity,
 };
 use crate::shared::components::movement_component::MovementComponent;
@@ -16,14 +16,136 @@ use nalgebra::{Point3, Vector3, UnitQuaternion, Unit};
 use tagged::{Registerable, Schema, Duplicate};
 
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Schema, Registerable, PartialEq, Eq, Duplicate)]
+pub enum FireState {
+  Lit,
+  Dim,
+  Out,
+}
+
+impl Default for FireState {
+  fn default() -> Self {
+    FireState::Lit
+  }
+}
+
+/// The fire the players have to keep alive. Fuel burns at `burn_rate` units
+/// per second, the fire goes dim below `dim_threshold` and out at zero.
+/// Players within `deposit_radius` can feed it their wood, each log being
+/// worth `fuel_per_wood` units of fuel. The fire keeps everyone within its
+/// heat radius warm, which shrinks from `max_heat_radius` down to
+/// `min_heat_radius` as it runs out of fuel.
 #[derive(Debug, Clone, Serialize, Deserialize, Schema, Registerable, Duplicate)]
 pub struct FireComponent {
   id: Uuid,
+  #[schema(default = "100.0")]
+  pub fuel: f32,
+  #[schema(default = "100.0")]
+  pub max_fuel: f32,
+  #[schema(default = "1.0")]
+  pub burn_rate: f32,
+  #[schema(default = "25.0")]
+  pub dim_threshold: f32,
+  #[serde(default)]
+  pub state: FireState,
+  #[schema(default = "{meters: 4.0}")]
+  pub deposit_radius: Meters,
+  #[schema(default = "10.0")]
+  pub fuel_per_wood: f32,
+  #[schema(default = "{seconds: 0.5}")]
+  pub deposit_interval: Seconds,
+  #[schema(default = "true")]
+  pub auto_deposit: bool,
+  #[schema(default = "{meters: 3.0}")]
+  pub min_heat_radius: Meters,
+  #[schema(default = "{meters: 12.0}")]
+  pub max_heat_radius: Meters,
+}
+
+impl Default for FireComponent {
+  fn default() -> Self {
+    Self {
+      id: Uuid::new_v4(),
+      fuel: 100.0,
+      max_fuel: 100.0,
+      burn_rate: 1.0,
+      dim_threshold: 25.0,
+      state: FireState::Lit,
+      deposit_radius: Meters::new(4.0),
+      fuel_per_wood: 10.0,
+      deposit_interval: Seconds::new(0.5),
+      auto_deposit: true,
+      min_heat_radius: Meters::new(3.0),
+      max_heat_radius: Meters::new(12.0),
+    }
+  }
+}
+
+impl FireComponent {
+  pub fn burn(&mut self, amount: f32) {
+    self.fuel = (self.fuel - amount).max(0.0);
+  }
+
+  pub fn add_fuel(&mut self, amount: f32) {
+    self.fuel = (self.fuel + amount).min(self.max_fuel);
+  }
+
+  /// Fuel left as a fraction of `max_fuel`, from 0.0 to 1.0.
+  pub fn intensity(&self) -> f32 {
+    if self.max_fuel <= 0.0 {
+      return 0.0;
+    }
+
+    (self.fuel / self.max_fuel).clamp(0.0, 1.0)
+  }
+
+  /// How far the fire's warmth reaches, based on how much fuel is left.
+  pub fn heat_radius(&self) -> Meters {
+    if self.is_out() {
+      return Meters::new(0.0);
+    }
+
+    let (min, max) = (*self.min_heat_radius, *self.max_heat_radius);
+    Meters::new(min + (max - min) * self.intensity())
+  }
+
+  pub fn is_full(&self) -> bool {
+    self.fuel >= self.max_fuel
+  }
+
+  pub fn is_out(&self) -> bool {
+    self.state == FireState::Out
+  }
+
+  /// Recomputes the state from the current fuel. Returns the new state
+  /// when it changed. A fire that went out stays out.
+  pub fn update_state(&mut self) -> Option<FireState> {
+    let next = if self.state == FireState::Out || self.fuel <= 0.0 {
+      FireState::Out
+    } else if self.fuel < self.dim_threshold {
+      FireState::Dim
+    } else {
+      FireState::Lit
+    };
+
+    if next == self.state {
+      return None;
+    }
+
+    self.state = next;
+    Some(next)
+  }
 }
 
+/// Marks a harvestable tree. Players standing within `chop_radius` need to
+/// keep chopping for `chop_time` to get a log out of its `ResourceComponent`.
 #[derive(Debug, Clone, Serialize, Deserialize, Schema, Registerable, Duplicate)]
 pub struct TreeComponent {
   id: Uuid,
+  #[schema(default = "{meters: 2.5}")]
+  pub chop_radius: Meters,
+  #[schema(default = "{seconds: 3.0}")]
+  pub chop_time: Seconds,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Schema, Registerable, Duplicate)]
@@ -5,13 +5,13 @@ use std::collections::{HashMap, hash_map::Entry};
 use engine::{
   application::{
     scene::{Scene, TransformComponent, UnpackEntity},
-    goap::{Sensor, Action, Goal, Planner, Blackboard},
   },
   systems::{Backpack, Initializable, Inventory, System},
-  utils::units::{Radians, Time, Meters},
+  utils::units::{Radians, Seconds, Time, Meters},
   Entity,
 };
 use crate::shared::components::movement_component::MovementComponent;
+use crate::utils::goap::{Sensor, Action, ActionStatus, Goal, GoalState, Blackboard};
 use engine::application::components::{PhysicsComponent, SelfComponent};
 use engine::systems::physics::PhysicsController;
 
@@ -19,24 +19,41 @@ use nalgebra::{Point3, Vector3, UnitQuaternion, Unit};
 use tagged::{Registerable, Schema, Duplicate};
 
 use super::components::FireComponent;
+use super::navigation::translation;
+use super::perception::{self, can_see, Fires, Memory};
+use super::world::SceneWorld;
+
+/// How close to the fire searching for it gets the agent.
+const ARRIVAL_DISTANCE: f32 = 2.0;
 
 #[derive(Debug)]
 struct FireLocation(pub Vector3<f32>, Meters);
 
+/// Looks for fires within `max_distance` and in the agent's line of sight.
+/// Fires glow, so they're spotted whichever way the agent faces unless
+/// `field_of_view` says otherwise, and kept in mind for `memory`.
 #[derive(Debug, Clone, Serialize, Deserialize, Schema, Registerable, Duplicate)]
 pub struct SenseFire {
   max_distance: Meters,
+  #[serde(default = "perception::all_around")]
+  #[schema(default = "{radians: 6.2831855}")]
+  field_of_view: Radians,
+  #[serde(default = "perception::landmark_memory")]
+  #[schema(default = "{seconds: 60.0}")]
+  memory: Seconds,
 }
 
 impl SenseFire {
   pub fn new(max_distance: Meters) -> Self {
     Self {
       max_distance,
+      field_of_view: perception::all_around(),
+      memory: perception::landmark_memory(),
     }
   }
 }
 
-impl Sensor for SenseFire {
+impl Sensor<SceneWorld> for SenseFire {
   fn name(&self) -> &'static str {
     "SenseFire"
   }
@@ -49,34 +66,58 @@ impl Sensor for SenseFire {
     local: &mut Backpack,
     blackboard: &mut Blackboard,
   ) {
-    let entity_transform = match scene.get_components::<&TransformComponent>(entity) {
-      Some(transform) => transform.clone(),
-      None => return,
+    let delta = match backpack.get::<Time>() {
+      Some(time) => **time,
+      None => 0.0,
     };
 
-    let mut fire_distance = None;
+    let origin = match translation(entity, scene) {
+      Some(translation) => translation,
+      None => return,
+    };
 
-    for (entity, (transform, _)) in scene.query_mut::<(
+    let mut fires = vec![];
+    for (fire, (transform, _)) in scene.query_mut::<(
       &TransformComponent,
       &FireComponent,
     )>() {
-      let distance = nalgebra::distance(
-        &Point3::from(entity_transform.translation),
-        &Point3::from(transform.translation),
-      );
+      fires.push((fire, transform.translation));
+    }
 
-      match fire_distance {
-        Some((_, current_distance)) if distance < current_distance => fire_distance = Some((transform.translation, distance)),
-        None => fire_distance = Some((transform.translation, distance)),
-        _ => {}
+    let mut seen = vec![];
+    for (fire, translation) in fires {
+      if can_see(entity, scene, backpack, fire, translation, *self.max_distance, *self.field_of_view) {
+        seen.push((fire, translation));
       }
     }
 
-    match fire_distance {
-      Some((translation, distance)) if distance < *self.max_distance => {
+    let memory = Memory::<Fires>::of(local);
+    memory.age(delta, *self.memory);
+    for (fire, translation) in seen {
+      memory.remember(fire, translation);
+    }
+    let remembered = memory.closest_to(origin);
+
+    // Fires that burned away entirely are forgotten along the way
+    let mut nearest = None;
+    for (fire, translation, distance) in remembered {
+      match scene.get_components::<&FireComponent>(fire) {
+        Some(component) => {
+          nearest = Some((translation, distance, component.clone()));
+          break;
+        },
+        None => Memory::<Fires>::of(local).forget(fire),
+      }
+    }
+
+    match nearest {
+      Some((translation, distance, fire)) => {
         local.insert(FireLocation(translation, Meters::new(distance)));
+        // Close enough to feed it, and how full it is in percent
+        blackboard.insert_bool("NearFire", distance <= *fire.deposit_radius);
+        blackboard.insert_number("FireFuel", (fire.intensity() * 100.0).round() as u32);
       },
-      _ => {
+      None => {
         local.take::<FireLocation>();
       },
     }
@@ -85,7 +126,7 @@ impl Sensor for SenseFire {
 
 #[derive(Debug, Clone, Serialize, Deserialize, Schema, Registerable, Duplicate)]
 pub struct StayWarm {}
-impl Goal for StayWarm {
+impl Goal<SceneWorld> for StayWarm {
   fn name() -> &'static str {
     "StayWarm"
   }
@@ -95,10 +136,10 @@ impl Goal for StayWarm {
     entity: Entity,
     scene: &mut Scene,
     backpack: &mut Backpack,
-  ) -> Blackboard {
-    let mut blackboard = Blackboard::new();
-    blackboard.insert_bool("NearbyFire", true);
-    blackboard
+  ) -> GoalState {
+    let mut goal = GoalState::new();
+    goal.insert_bool("NearbyFire", true);
+    goal
   }
 }
 
@@ -120,13 +161,15 @@ impl SearchForFire {
   }
 }
 
-impl Action for SearchForFire {
+impl Action<SceneWorld> for SearchForFire {
   fn name(&self) -> &'static str {
     "SearchForFire"
   }
 
   fn cost(
     &self,
+    entity: Entity,
+    scene: &mut Scene,
     local: &Backpack,
     blackboard: &Blackboard,
   ) -> i32 {
@@ -143,6 +186,8 @@ impl Action for SearchForFire {
 
   fn check_readyness(
     &mut self,
+    entity: Entity,
+    scene: &mut Scene,
     local: &Backpack,
     blackboard: &Blackboard,
   ) -> bool {
@@ -158,13 +203,17 @@ impl Action for SearchForFire {
     scene: &mut Scene,
     backpack: &mut Backpack,
     local: &mut Backpack,
-  ) {
+  ) -> ActionStatus {
     let (location, distance) = if let Some(FireLocation(location, distance)) = local.get() {
-      (location.clone(), distance)
+      (location.clone(), distance.clone())
     } else {
-      return
+      return ActionStatus::Failure
     };
 
+    if *distance < ARRIVAL_DISTANCE {
+      return ActionStatus::Success;
+    }
+
     if let Some(physics_controller) = backpack.get_mut::<PhysicsController>()
       && let Some((transform, physics, movement)) = scene.get_components::<(
         &TransformComponent,
@@ -186,6 +235,8 @@ impl Action for SearchForFire {
         movement.run_speed,
       );
     }
+
+    ActionStatus::Running
   }
 }
 
@@ -202,13 +253,15 @@ impl Chill {
   }
 }
 
-impl Action for Chill {
+impl Action<SceneWorld> for Chill {
   fn name(&self) -> &'static str {
     "Chill"
   }
 
   fn cost(
     &self,
+    entity: Entity,
+    scene: &mut Scene,
     local: &Backpack,
     blackboard: &Blackboard,
   ) -> i32 {
@@ -217,6 +270,8 @@ impl Action for Chill {
 
   fn check_readyness(
     &mut self,
+    entity: Entity,
+    scene: &mut Scene,
     local: &Backpack,
     blackboard: &Blackboard,
   ) -> bool {
@@ -241,10 +296,12 @@ impl Action for Chill {
     scene: &mut Scene,
     backpack: &mut Backpack,
     local: &mut Backpack,
-  ) {
+  ) -> ActionStatus {
     if let Some(physics_controller) = backpack.get_mut::<PhysicsController>()
       && let Some(physics) = scene.get_components::<&PhysicsComponent>(entity) {
       physics_controller.set_linvel(&physics, Vector3::zeros());
     }
+
+    ActionStatus::Success
   }
 }
@@ -1,18 +1,35 @@
-mod components;
+pub mod components;
 mod player;
 mod fire;
+mod navigation;
+mod perception;
+mod registry;
+mod sabotage;
+mod tender;
+mod world;
 
 use engine::{
+  application::{components::GoalComponent, scene::Scene},
   systems::{Backpack, Initializable, Inventory, System, Registry},
+  utils::units::Time,
+  Entity,
 };
-
-use nalgebra::{Point3, Vector3, UnitQuaternion, Unit};
+use uuid::Uuid;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use crate::shared::systems::goal::{
   components::{FireComponent, TreeComponent, FirewoodComponent},
   fire::{SenseFire, StayWarm, SearchForFire, Chill},
-  player::{SensePlayer, AggroCharacter, Patrol, Attack},
+  player::{SensePlayer, AggroCharacter, Patrol, Attack, Investigate},
+  sabotage::{SenseGuards, SenseThreat, SabotageFire, ApproachFire, DrainFire, Flee},
+  tender::{SenseTree, SenseFirewood, KeepFireFueled, ChopTree, PickUpFirewood, CarryToFire, FeedFire},
+};
+use crate::utils::goap::{Analysis, Planner, PlanningScheduler, PlanningStats};
+
+pub use registry::{
+  action_registry, goal_registry, sensor_registry, DefinitionError, PlannerDefinition, PlannerDefinitions,
 };
+pub use world::SceneWorld;
 
 pub struct GoalRegistry {
 }
@@ -27,86 +44,183 @@ impl Registry for GoalRegistry {
     }
 
     {
-      use engine::application::goap::goal_registry::Access;
+      use goal_registry::Access;
       StayWarm::register();
       AggroCharacter::register();
+      KeepFireFueled::register();
+      SabotageFire::register();
     }
 
     {
-      use engine::application::goap::action_registry::Access;
+      use action_registry::Access;
       SearchForFire::register();
       Chill::register();
       Patrol::register();
       Attack::register();
+      Investigate::register();
+      ChopTree::register();
+      PickUpFirewood::register();
+      CarryToFire::register();
+      FeedFire::register();
+      ApproachFire::register();
+      DrainFire::register();
+      Flee::register();
     }
 
     {
-      use engine::application::goap::sensor_registry::Access;
+      use sensor_registry::Access;
       SenseFire::register();
       SensePlayer::register();
+      SenseTree::register();
+      SenseFirewood::register();
+      SenseGuards::register();
+      SenseThreat::register();
     }
   }
 }
 
-/*
+/// Seconds between two reports of how planning is going.
+const STATS_INTERVAL: f32 = 10.0;
+
+/// Gives every entity with a `GoalComponent` a planner built from the level
+/// definition it points at, and lets them plan and act within the budget of
+/// the `PlanningScheduler` in the backpack. One with the default budget is
+/// put there unless something else did first. The `PlanningStats` of the
+/// last tick are kept in the backpack too, and logged every `STATS_INTERVAL`.
 pub struct GoalSystem {
-  planners: HashMap<(Entity, Uuid), (Planner, Backpack)>,
+  /// Ordered, so that equally urgent agents always plan in the same order
+  /// and seeded runs play out the same.
+  planners: BTreeMap<(Entity, Uuid), (Planner<SceneWorld>, Backpack)>,
+  /// Definitions that couldn't be built, so they're only reported once.
+  broken: HashSet<Uuid>,
+  /// What analyzing each definition found, so it's done and reported once
+  /// rather than for every agent built from it.
+  analyses: HashMap<Uuid, Analysis>,
+  /// Seconds since planning was last reported.
+  since_report: f32,
 }
 
 impl Initializable for GoalSystem {
   fn initialize(inventory: &Inventory) -> Self {
     Self {
-      planners: HashMap::new(),
+      planners: BTreeMap::new(),
+      broken: HashSet::new(),
+      analyses: HashMap::new(),
+      since_report: 0.0,
     }
   }
 }
 
+impl GoalSystem {
+  fn build(
+    &mut self,
+    entity: Entity,
+    planner_id: Uuid,
+    scene: &mut Scene,
+    backpack: &mut Backpack,
+  ) -> Option<(Planner<SceneWorld>, Backpack)> {
+    if self.broken.contains(&planner_id) {
+      return None;
+    }
+
+    let definition = match backpack.get::<PlannerDefinitions>().and_then(|definitions| definitions.get(&planner_id)) {
+      Some(definition) => definition,
+      None => {
+        log::warn!("no planner definition {}", planner_id);
+        self.broken.insert(planner_id);
+        return None;
+      }
+    };
+
+    let mut planner = match definition.instantiate() {
+      Ok(planner) => planner,
+      Err(error) => {
+        log::warn!("can't build planner {}: {}", definition.name, error);
+        self.broken.insert(planner_id);
+        return None;
+      }
+    };
+
+    let mut local = Backpack::new();
+    if self.analyses.contains_key(&planner_id) {
+      return Some((planner, local));
+    }
+
+    let analysis = planner.analyze(entity, scene, &mut local);
+    if !analysis.is_clean() {
+      let goals = definition.goal_names();
+      let actions = definition.action_names();
+      for goal in &analysis.unreachable_goals {
+        log::warn!("planner {} can never reach {}", definition.name, goals[*goal]);
+      }
+      for action in &analysis.dead_actions {
+        log::warn!("planner {} never uses {}", definition.name, actions[*action]);
+      }
+    }
+    self.analyses.insert(planner_id, analysis);
+
+    Some((planner, local))
+  }
 
-impl GoalSystem {}
+  fn report(&mut self, stats: &PlanningStats, backpack: &Backpack) {
+    self.since_report += backpack.get::<Time>().map_or(0.0, |time| **time);
+    if self.since_report < STATS_INTERVAL {
+      return;
+    }
+    self.since_report = 0.0;
+
+    log::debug!(
+      "{} agents planning, {} expansions in {:?} last tick ({} deferred), {} in {:?} overall",
+      stats.planned,
+      stats.expansions,
+      stats.time,
+      stats.deferred,
+      stats.total_expansions,
+      stats.total_time,
+    );
+  }
+}
 
 impl System for GoalSystem {
-  fn provide(&mut self, inventory: &Inventory) {
-    FireComponent::register();
+  fn attach(&mut self, _: &mut Scene, backpack: &mut Backpack) {
+    if backpack.get::<PlanningScheduler<SceneWorld>>().is_none() {
+      backpack.insert(PlanningScheduler::<SceneWorld>::default());
+    }
   }
 
   fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
-    #[cfg(target_arch = "wasm32")]
-    {
-      let delta = backpack.get::<Time>().unwrap();
+    let mut agents = vec![];
 
-      let mut planners = vec![];
+    for (entity, goal) in scene.query_mut::<&GoalComponent>() {
+      agents.push((entity, goal.id, goal.planner_id));
+    }
 
-      for (entity, goal) in scene.query_mut::<&GoalComponent>() {
-        planners.push((entity.clone(), goal.id))
-      }
+    self
+      .planners
+      .retain(|(entity, goal_id), _| agents.iter().any(|(other, id, _)| entity == other && goal_id == id));
 
-      for (entity, goal_id) in planners.drain(..) {
-        match self.planners.entry((entity, goal_id)) {
-          Entry::Occupied(mut entry) => {
-            let planner = entry.into_mut();
-            planner.0.plan(entity, scene, backpack, &mut planner.1);
-          }
-          Entry::Vacant(vacant) => {
-            let mut planner = Planner::new();
-            planner.insert_goal(StayWarm::new());
-            planner.insert_action(SearchForFire::new());
-            planner.insert_action(Chill::new(Meters::new(2.0)));
-
-            planner.insert_goal(AggroCharacter::new());
-            planner.insert_action(Patrol::new());
-            planner.insert_action(Attack::new(Meters::new(5.0)));
-
-            planner.insert_sensor(SenseFire::new(Meters::new(100.0)));
-            planner.insert_sensor(SensePlayer::new(Meters::new(10.0)));
-
-            let mut local = Backpack::new();
-
-            planner.plan(entity, scene, backpack, &mut local);
-            vacant.insert((planner, local));
-          },
-        };
+    for (entity, goal_id, planner_id) in agents.drain(..) {
+      if !self.planners.contains_key(&(entity, goal_id)) {
+        if let Some(planner) = self.build(entity, planner_id, scene, backpack) {
+          self.planners.insert((entity, goal_id), planner);
+        }
       }
     }
+
+    // Out of the backpack while the planners get to use it
+    let mut scheduler = backpack.take::<PlanningScheduler<SceneWorld>>().unwrap_or_default();
+    scheduler.update(
+      self
+        .planners
+        .iter_mut()
+        .map(|((entity, _), (planner, local))| (*entity, planner, local)),
+      scene,
+      backpack,
+    );
+
+    let stats = scheduler.stats().clone();
+    backpack.insert(scheduler);
+    self.report(&stats, backpack);
+    backpack.insert(stats);
   }
 }
-*/
@@ -0,0 +1,107 @@
+use engine::{
+  application::scene::{Scene, TransformComponent},
+  systems::Backpack,
+  Entity,
+};
+use crate::shared::components::movement_component::MovementComponent;
+use crate::shared::{players, threat::ThreatTables};
+use engine::application::components::PhysicsComponent;
+use engine::systems::physics::PhysicsController;
+
+use nalgebra::{Point3, Vector3};
+
+use super::components::FireComponent;
+
+/// Every this many meters away adds one to the cost of walking somewhere.
+const METERS_PER_COST: f32 = 10.0;
+
+pub(super) fn translation(entity: Entity, scene: &mut Scene) -> Option<Vector3<f32>> {
+  scene
+    .get_components::<&TransformComponent>(entity)
+    .map(|transform| transform.translation)
+}
+
+pub(super) fn distance(from: Vector3<f32>, to: Vector3<f32>) -> f32 {
+  nalgebra::distance(&Point3::from(from), &Point3::from(to))
+}
+
+pub(super) fn walking_cost(distance: f32) -> i32 {
+  (distance / METERS_PER_COST).ceil() as i32
+}
+
+/// The fire closest to `translation` that's still burning, along with its
+/// translation.
+pub(super) fn nearest_fire(scene: &mut Scene, translation: Vector3<f32>) -> Option<(Entity, Vector3<f32>, FireComponent)> {
+  let mut nearest: Option<(Entity, Vector3<f32>, FireComponent)> = None;
+
+  for (entity, (transform, fire)) in scene.query_mut::<(&TransformComponent, &FireComponent)>() {
+    if fire.is_out() {
+      continue;
+    }
+
+    let closer = match &nearest {
+      Some((_, current, _)) => distance(translation, transform.translation) < distance(translation, *current),
+      None => true,
+    };
+    if closer {
+      nearest = Some((entity, transform.translation, fire.clone()));
+    }
+  }
+
+  nearest
+}
+
+pub(super) fn move_towards(entity: Entity, scene: &mut Scene, backpack: &mut Backpack, target: Vector3<f32>) {
+  if let Some(physics_controller) = backpack.get_mut::<PhysicsController>() {
+    if let Some((transform, physics, movement)) = scene.get_components::<(
+      &TransformComponent,
+      &PhysicsComponent,
+      &MovementComponent,
+    )>(entity) {
+      physics_controller.move_towards(
+        &physics,
+        transform.translation,
+        target,
+        movement.run_speed,
+      );
+    }
+  }
+}
+
+pub(super) fn stop(entity: Entity, scene: &mut Scene, backpack: &mut Backpack) {
+  if let Some(physics_controller) = backpack.get_mut::<PhysicsController>() {
+    if let Some(physics) = scene.get_components::<&PhysicsComponent>(entity) {
+      physics_controller.set_linvel(&physics, Vector3::zeros());
+    }
+  }
+}
+
+/// Living players within `radius` of `center`, the closest first.
+pub(super) fn players_around(scene: &mut Scene, center: Vector3<f32>, radius: f32) -> Vec<(Entity, Vector3<f32>, f32)> {
+  let mut players = players::alive(scene)
+    .into_iter()
+    .map(|(player, translation)| (player, translation, distance(center, translation)))
+    .filter(|(_, _, distance)| *distance <= radius)
+    .collect::<Vec<_>>();
+
+  players.sort_by(|a, b| a.2.partial_cmp(&b.2).unwrap_or(std::cmp::Ordering::Equal));
+  players
+}
+
+/// Which of `players`, sorted closest first, `entity` should go after: the
+/// one highest on its threat table, or the closest one when nobody is.
+pub(super) fn pick_target(
+  entity: Entity,
+  backpack: &Backpack,
+  players: Vec<(Entity, Vector3<f32>, f32)>,
+) -> Option<(Entity, Vector3<f32>, f32)> {
+  let preferred = backpack
+    .get::<ThreatTables>()
+    .and_then(|tables| tables.get(entity))
+    .and_then(|table| table.target(players.iter().map(|(player, _, _)| *player)));
+
+  match preferred {
+    Some(preferred) => players.into_iter().find(|(player, _, _)| *player == preferred),
+    None => players.into_iter().next(),
+  }
+}
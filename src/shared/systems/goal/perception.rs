@@ -0,0 +1,214 @@
+use engine::{
+  application::{
+    components::PhysicsComponent,
+    scene::{Scene, TransformComponent},
+  },
+  systems::{physics::PhysicsController, Backpack},
+  utils::units::{Meters, Radians, Seconds},
+  Entity,
+};
+use crate::shared::components::{attack_component::AttackComponent, inventory_component::InventoryComponent};
+
+use nalgebra::Vector3;
+use rapier3d::prelude::{QueryFilter, Ray};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use super::navigation::{distance, players_around};
+
+/// Anything this close gets noticed, whichever way the agent faces.
+const AWARENESS_RADIUS: f32 = 2.0;
+/// Eyes and ears sit this high above an entity's translation.
+const HEAD_HEIGHT: f32 = 0.5;
+
+pub(super) fn default_field_of_view() -> Radians {
+  Radians::new(120f32.to_radians())
+}
+
+pub(super) fn all_around() -> Radians {
+  Radians::new(std::f32::consts::TAU)
+}
+
+pub(super) fn default_hearing_radius() -> Meters {
+  Meters::new(15.0)
+}
+
+pub(super) fn default_memory() -> Seconds {
+  Seconds::new(10.0)
+}
+
+/// Fires don't go anywhere, so they're kept in mind for longer.
+pub(super) fn landmark_memory() -> Seconds {
+  Seconds::new(60.0)
+}
+
+/// Whether `target` falls inside the vision cone of `entity`, which is
+/// `field_of_view` wide and centered on where it faces.
+pub(super) fn in_view(
+  entity: Entity,
+  scene: &mut Scene,
+  target: Vector3<f32>,
+  field_of_view: f32,
+) -> bool {
+  let transform = match scene.get_components::<&TransformComponent>(entity) {
+    Some(transform) => transform.clone(),
+    None => return false,
+  };
+
+  let towards = target - transform.translation;
+  if towards.norm() <= AWARENESS_RADIUS {
+    return true;
+  }
+
+  let facing = transform.get_euler_direction().into_inner();
+  facing.angle(&towards) <= field_of_view / 2.0
+}
+
+/// Whether nothing stands between `entity` and `target`, casting a ray
+/// through the physics world. Without one there's nothing to block the view.
+pub(super) fn line_of_sight(
+  entity: Entity,
+  scene: &mut Scene,
+  backpack: &mut Backpack,
+  target: Entity,
+  to: Vector3<f32>,
+) -> bool {
+  let physics_controller = match backpack.get_mut::<PhysicsController>() {
+    Some(physics_controller) => physics_controller,
+    None => return true,
+  };
+
+  let (eyes, filter) = match scene.get_components::<(&TransformComponent, &PhysicsComponent)>(entity) {
+    Some((transform, physics)) => {
+      let filter = match physics_controller.get_rigid_body(&physics.joint.body.id) {
+        Some(body) => QueryFilter::default().exclude_rigid_body(body),
+        None => QueryFilter::default(),
+      };
+      (transform.translation + Vector3::y() * HEAD_HEIGHT, filter)
+    },
+    None => return true,
+  };
+
+  let towards = to + Vector3::y() * HEAD_HEIGHT - eyes;
+  let length = towards.norm();
+  if length <= f32::EPSILON {
+    return true;
+  }
+
+  let ray = Ray::new(eyes.into(), towards / length);
+  // The ray stops at the target, so only what's in between can block it
+  match physics_controller.raycast(&ray, length, true, filter) {
+    Some((hit, _, _)) => hit == target,
+    None => true,
+  }
+}
+
+/// Whether `entity` can see `target` standing at `translation`: within
+/// `range`, inside its vision cone and in its line of sight.
+pub(super) fn can_see(
+  entity: Entity,
+  scene: &mut Scene,
+  backpack: &mut Backpack,
+  target: Entity,
+  translation: Vector3<f32>,
+  range: f32,
+  field_of_view: f32,
+) -> bool {
+  let origin = match scene.get_components::<&TransformComponent>(entity) {
+    Some(transform) => transform.translation,
+    None => return false,
+  };
+
+  distance(origin, translation) <= range
+    && in_view(entity, scene, translation, field_of_view)
+    && line_of_sight(entity, scene, backpack, target, translation)
+}
+
+/// Players making noise within `radius` of `center`, chopping wood or
+/// swinging at something, along with where they stand.
+pub(super) fn noises_around(scene: &mut Scene, center: Vector3<f32>, radius: f32) -> Vec<(Entity, Vector3<f32>)> {
+  let mut noisy = vec![];
+
+  for (player, inventory) in scene.query_mut::<&InventoryComponent>() {
+    if inventory.chop_timer > 0.0 {
+      noisy.push(player);
+    }
+  }
+  for (player, attack) in scene.query_mut::<&AttackComponent>() {
+    if attack.anim_running && !noisy.contains(&player) {
+      noisy.push(player);
+    }
+  }
+
+  players_around(scene, center, radius)
+    .into_iter()
+    .filter(|(player, _, _)| noisy.contains(player))
+    .map(|(player, translation, _)| (player, translation))
+    .collect()
+}
+
+/// Marks the `Memory` of players.
+#[derive(Debug)]
+pub(super) enum Players {}
+
+/// Marks the `Memory` of fires.
+#[derive(Debug)]
+pub(super) enum Fires {}
+
+/// Where an agent last saw or heard things of a kind, kept in its local
+/// backpack and forgotten once they're older than the sensor's memory span.
+#[derive(Debug)]
+pub(super) struct Memory<T> {
+  last_known: HashMap<Entity, (Vector3<f32>, f32)>,
+  kind: PhantomData<fn() -> T>,
+}
+
+impl<T: 'static> Memory<T> {
+  pub fn new() -> Self {
+    Self {
+      last_known: HashMap::new(),
+      kind: PhantomData,
+    }
+  }
+
+  /// The memory kept in `local`, starting an empty one if there's none yet.
+  pub fn of(local: &mut Backpack) -> &mut Self {
+    if local.get::<Self>().is_none() {
+      local.insert(Self::new());
+    }
+    local.get_mut::<Self>().unwrap()
+  }
+
+  pub fn remember(&mut self, target: Entity, translation: Vector3<f32>) {
+    self.last_known.insert(target, (translation, 0.0));
+  }
+
+  pub fn forget(&mut self, target: Entity) {
+    self.last_known.remove(&target);
+  }
+
+  /// Ages every memory by `delta` seconds, dropping those older than `span`.
+  pub fn age(&mut self, delta: f32, span: f32) {
+    for (_, age) in self.last_known.values_mut() {
+      *age += delta;
+    }
+    self.last_known.retain(|_, (_, age)| *age <= span);
+  }
+
+  /// Everything remembered, along with where it was last known to be.
+  pub fn iter(&self) -> impl Iterator<Item = (Entity, Vector3<f32>)> + '_ {
+    self.last_known.iter().map(|(target, (translation, _))| (*target, *translation))
+  }
+
+  /// Everything remembered, the closest to `origin` first, along with how
+  /// far it is.
+  pub fn closest_to(&self, origin: Vector3<f32>) -> Vec<(Entity, Vector3<f32>, f32)> {
+    let mut remembered = self
+      .iter()
+      .map(|(target, translation)| (target, translation, distance(origin, translation)))
+      .collect::<Vec<_>>();
+
+    remembered.sort_by(|a, b| a.2.partial_cmp(&b.2).unwrap_or(std::cmp::Ordering::Equal));
+    remembered
+  }
+}
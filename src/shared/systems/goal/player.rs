@@ -5,36 +5,63 @@ use std::collections::{HashMap, hash_map::Entry};
 use engine::{
   application::{
     scene::{Scene, TransformComponent, UnpackEntity},
-    goap::{Sensor, Action, Goal, Planner, Blackboard},
   },
   systems::{Backpack, Initializable, Inventory, System},
-  utils::units::{Radians, Time, Meters},
+  utils::units::{Radians, Seconds, Time, Meters},
   Entity,
 };
 use crate::shared::components::movement_component::MovementComponent;
-use engine::application::components::{PhysicsComponent, SelfComponent};
+use crate::utils::goap::{Sensor, Action, ActionStatus, Goal, GoalState, Effects, Operator, Blackboard};
+use super::navigation::{distance, move_towards, pick_target, players_around, stop, translation, walking_cost};
+use super::perception::{self, can_see, noises_around, Memory, Players};
+use super::world::SceneWorld;
+use engine::application::components::PhysicsComponent;
 use engine::systems::physics::PhysicsController;
 
-use nalgebra::{Point3, Vector3, UnitQuaternion, Unit};
+use nalgebra::{Vector3, UnitQuaternion, Unit};
 use tagged::{Registerable, Schema, Duplicate};
 
+/// How close to the player patrolling gets the agent.
+const ARRIVAL_DISTANCE: f32 = 2.0;
+
+/// The player the agent goes after, where they stand and how far they are.
+#[derive(Debug)]
+struct PlayerLocation(pub Entity, pub Vector3<f32>, Meters);
+
+/// Where the agent last saw or heard a player it can't perceive anymore.
 #[derive(Debug)]
-struct PlayerLocation(pub Vector3<f32>, Meters);
+struct LastKnownPosition(pub Entity, pub Vector3<f32>);
 
+/// Looks for players within `max_distance` that are in the agent's vision
+/// cone and line of sight, and listens for the noisy ones within
+/// `hearing_radius`. Players it loses track of stay in its memory for
+/// `memory`, so it can go look for them.
 #[derive(Debug, Clone, Serialize, Deserialize, Schema, Registerable, Duplicate)]
 pub struct SensePlayer {
   max_distance: Meters,
+  #[serde(default = "perception::default_field_of_view")]
+  #[schema(default = "{radians: 2.0943952}")]
+  field_of_view: Radians,
+  #[serde(default = "perception::default_hearing_radius")]
+  #[schema(default = "{meters: 15.0}")]
+  hearing_radius: Meters,
+  #[serde(default = "perception::default_memory")]
+  #[schema(default = "{seconds: 10.0}")]
+  memory: Seconds,
 }
 
 impl SensePlayer {
   pub fn new(max_distance: Meters) -> Self {
     Self {
       max_distance,
+      field_of_view: perception::default_field_of_view(),
+      hearing_radius: perception::default_hearing_radius(),
+      memory: perception::default_memory(),
     }
   }
 }
 
-impl Sensor for SensePlayer {
+impl Sensor<SceneWorld> for SensePlayer {
   fn name(&self) -> &'static str {
     "SensePlayer"
   }
@@ -48,46 +75,76 @@ impl Sensor for SensePlayer {
     blackboard: &mut Blackboard,
   ) {
     //log::debug!("Sensing Player");
-    let entity_transform = match scene.get_components::<&TransformComponent>(entity) {
-      Some(transform) => transform.clone(),
-      None => return,
+    let delta = match backpack.get::<Time>() {
+      Some(time) => **time,
+      None => 0.0,
     };
 
-    let mut player_distance = None;
-
-    for (entity, (transform, _)) in scene.query_mut::<(
-      &TransformComponent,
-      &SelfComponent,
-    )>() {
-      let distance = nalgebra::distance(
-        &Point3::from(entity_transform.translation),
-        &Point3::from(transform.translation),
-      );
+    let origin = match translation(entity, scene) {
+      Some(translation) => translation,
+      None => return,
+    };
 
-      match player_distance {
-        Some((_, current_distance)) if distance < current_distance => player_distance = Some((transform.translation, distance)),
-        None => player_distance = Some((transform.translation, distance)),
-        _ => {}
+    let mut seen = vec![];
+    for (player, translation, distance) in players_around(scene, origin, *self.max_distance) {
+      let visible = player != entity
+        && can_see(entity, scene, backpack, player, translation, *self.max_distance, *self.field_of_view);
+      if visible {
+        seen.push((player, translation, distance));
       }
-
-      //log::info!("{:} player_location? {:?} -- {:?}", distance, &transform, player_distance);
     }
+    let heard = noises_around(scene, origin, *self.hearing_radius);
 
-    match player_distance {
-      Some((translation, distance)) if distance < *self.max_distance => {
-        local.insert(PlayerLocation(translation, Meters::new(distance)));
+    let memory = Memory::<Players>::of(local);
+    memory.age(delta, *self.memory);
+    for (player, translation, _) in &seen {
+      memory.remember(*player, *translation);
+    }
+    for (player, translation) in heard {
+      if player != entity {
+        memory.remember(player, translation);
+      }
+    }
+    let remembered = memory.closest_to(origin);
+
+    match pick_target(entity, backpack, seen) {
+      Some((player, translation, distance)) => {
+        local.insert(PlayerLocation(player, translation, Meters::new(distance)));
+        local.take::<LastKnownPosition>();
+        blackboard.insert_bool("SeesPlayer", true);
+        blackboard.insert_bool("SuspectsPlayer", false);
       },
-      _ => {
+      None => {
         local.take::<PlayerLocation>();
+        blackboard.insert_bool("SeesPlayer", false);
+
+        match pick_target(entity, backpack, remembered) {
+          Some((player, translation, _)) => {
+            local.insert(LastKnownPosition(player, translation));
+            blackboard.insert_bool("SuspectsPlayer", true);
+          },
+          None => {
+            local.take::<LastKnownPosition>();
+            blackboard.insert_bool("SuspectsPlayer", false);
+          },
+        }
       },
     }
   }
+
+  fn invalidates(
+    &self,
+    previous: &Blackboard,
+    current: &Blackboard,
+  ) -> bool {
+    previous.get_bool("SeesPlayer") != Some(&true) && current.get_bool("SeesPlayer") == Some(&true)
+  }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Schema, Registerable, Duplicate)]
 pub struct AggroCharacter {}
 
-impl Goal for AggroCharacter {
+impl Goal<SceneWorld> for AggroCharacter {
   fn name() -> &'static str {
     "AggroCharacter"
   }
@@ -97,10 +154,10 @@ impl Goal for AggroCharacter {
     entity: Entity,
     scene: &mut Scene,
     local: &mut Backpack,
-  ) -> Blackboard {
-    let mut blackboard = Blackboard::new();
-    blackboard.insert_bool("NearbyPlayer", true);
-    blackboard
+  ) -> GoalState {
+    let mut goal = GoalState::new();
+    goal.insert_bool("NearbyPlayer", true);
+    goal
   }
 }
 
@@ -121,13 +178,15 @@ impl Patrol {
   }
 }
 
-impl Action for Patrol {
+impl Action<SceneWorld> for Patrol {
   fn name(&self) -> &'static str {
     "Patrol"
   }
 
   fn cost(
     &self,
+    entity: Entity,
+    scene: &mut Scene,
     local: &Backpack,
     blackboard: &Blackboard,
   ) -> i32 {
@@ -136,6 +195,8 @@ impl Action for Patrol {
 
   fn check_readyness(
     &mut self,
+    entity: Entity,
+    scene: &mut Scene,
     local: &Backpack,
     blackboard: &Blackboard,
   ) -> bool {
@@ -160,13 +221,17 @@ impl Action for Patrol {
     scene: &mut Scene,
     backpack: &mut Backpack,
     local: &mut Backpack,
-  ) {
-    let (location, _) = if let Some(PlayerLocation(location, distance)) = local.get() {
-      (location.clone(), distance)
+  ) -> ActionStatus {
+    let (location, distance) = if let Some(PlayerLocation(_, location, distance)) = local.get() {
+      (location.clone(), distance.clone())
     } else {
-      return
+      return ActionStatus::Failure
     };
 
+    if *distance < ARRIVAL_DISTANCE {
+      return ActionStatus::Success;
+    }
+
     if let Some(physics_controller) = backpack.get_mut::<PhysicsController>()
       && let Some((transform, physics, movement)) = scene.get_components::<(
         &TransformComponent,
@@ -189,6 +254,8 @@ impl Action for Patrol {
         movement.run_speed,
       );
     }
+
+    ActionStatus::Running
   }
 }
 
@@ -205,13 +272,15 @@ impl Attack {
   }
 }
 
-impl Action for Attack {
+impl Action<SceneWorld> for Attack {
   fn name(&self) -> &'static str {
     "Attack"
   }
 
   fn cost(
     &self,
+    entity: Entity,
+    scene: &mut Scene,
     local: &Backpack,
     blackboard: &Blackboard,
   ) -> i32 {
@@ -220,12 +289,14 @@ impl Action for Attack {
 
   fn check_readyness(
     &mut self,
+    entity: Entity,
+    scene: &mut Scene,
     local: &Backpack,
     blackboard: &Blackboard,
   ) -> bool {
     //log::debug!("attack");
     match (local.get::<PlayerLocation>(), blackboard.get_bool("KnowPlayerLocation")) {
-      (Some(PlayerLocation(_, distance)), _) if *distance < self.max_distance => true,
+      (Some(PlayerLocation(_, _, distance)), _) if *distance < self.max_distance => true,
       (_, Some(true)) => true,
       _ => false,
     }
@@ -245,10 +316,86 @@ impl Action for Attack {
     scene: &mut Scene,
     backpack: &mut Backpack,
     local: &mut Backpack,
-  ) {
+  ) -> ActionStatus {
     if let Some(physics_controller) = backpack.get_mut::<PhysicsController>()
       && let Some(physics) = scene.get_components::<&PhysicsComponent>(entity) {
       physics_controller.set_linvel(&physics, Vector3::zeros());
     }
+
+    ActionStatus::Success
+  }
+}
+
+/// Walks up to where a player was last seen or heard. Nobody being there
+/// anymore once it arrives means the lead went cold, and the agent forgets
+/// about them.
+#[derive(Debug, Clone, Serialize, Deserialize, Schema, Registerable, Duplicate)]
+pub struct Investigate {}
+
+impl Investigate {
+  pub fn new() -> Self {
+    Self {}
+  }
+}
+
+impl Action<SceneWorld> for Investigate {
+  fn name(&self) -> &'static str {
+    "Investigate"
+  }
+
+  fn cost(
+    &self,
+    entity: Entity,
+    scene: &mut Scene,
+    local: &Backpack,
+    blackboard: &Blackboard,
+  ) -> i32 {
+    let walk = match (translation(entity, scene), local.get::<LastKnownPosition>()) {
+      (Some(origin), Some(LastKnownPosition(_, location))) => walking_cost(distance(origin, *location)),
+      _ => 0,
+    };
+    2 + walk
+  }
+
+  fn preconditions(&self) -> Option<GoalState> {
+    let mut preconditions = GoalState::new();
+    preconditions.insert_bool("SuspectsPlayer", true);
+    preconditions.insert("SeesPlayer", Operator::NotEqual, true);
+    Some(preconditions)
+  }
+
+  fn effects(&self) -> Option<Effects> {
+    let mut effects = Effects::new();
+    // Optimistic, finding them is what the search is for
+    effects.insert_bool("KnowPlayerLocation", true);
+    Some(effects)
+  }
+
+  fn execute(
+    &mut self,
+    entity: Entity,
+    scene: &mut Scene,
+    backpack: &mut Backpack,
+    local: &mut Backpack,
+  ) -> ActionStatus {
+    let (player, location) = match local.get::<LastKnownPosition>() {
+      Some(LastKnownPosition(player, location)) => (*player, *location),
+      None => return ActionStatus::Failure,
+    };
+
+    let origin = match translation(entity, scene) {
+      Some(translation) => translation,
+      None => return ActionStatus::Failure,
+    };
+
+    if distance(origin, location) > ARRIVAL_DISTANCE {
+      move_towards(entity, scene, backpack, location);
+      return ActionStatus::Running;
+    }
+
+    stop(entity, scene, backpack);
+    Memory::<Players>::of(local).forget(player);
+    local.take::<LastKnownPosition>();
+    ActionStatus::Failure
   }
 }
@@ -0,0 +1,198 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as Params;
+use uuid::Uuid;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::RwLock;
+
+use crate::utils::goap::{Action, Goal, Planner, Sensor};
+
+use super::world::SceneWorld;
+
+type Constructor<T> = fn(Params) -> Result<Box<T>, serde_json::Error>;
+
+static GOALS: RwLock<BTreeMap<&'static str, Constructor<dyn Goal<SceneWorld>>>> = RwLock::new(BTreeMap::new());
+static ACTIONS: RwLock<BTreeMap<&'static str, Constructor<dyn Action<SceneWorld>>>> = RwLock::new(BTreeMap::new());
+static SENSORS: RwLock<BTreeMap<&'static str, Constructor<dyn Sensor<SceneWorld>>>> = RwLock::new(BTreeMap::new());
+
+/// Name a type is registered under, its path stripped.
+fn short_name<T>() -> &'static str {
+  let name = std::any::type_name::<T>();
+  name.rsplit("::").next().unwrap_or(name)
+}
+
+fn construct_goal<T: Goal<SceneWorld> + DeserializeOwned + 'static>(
+  params: Params,
+) -> Result<Box<dyn Goal<SceneWorld>>, serde_json::Error> {
+  Ok(Box::new(serde_json::from_value::<T>(params)?))
+}
+
+fn construct_action<T: Action<SceneWorld> + DeserializeOwned + 'static>(
+  params: Params,
+) -> Result<Box<dyn Action<SceneWorld>>, serde_json::Error> {
+  Ok(Box::new(serde_json::from_value::<T>(params)?))
+}
+
+fn construct_sensor<T: Sensor<SceneWorld> + DeserializeOwned + 'static>(
+  params: Params,
+) -> Result<Box<dyn Sensor<SceneWorld>>, serde_json::Error> {
+  Ok(Box::new(serde_json::from_value::<T>(params)?))
+}
+
+pub mod goal_registry {
+  use super::*;
+
+  pub trait Access {
+    fn register();
+  }
+
+  impl<T: Goal<SceneWorld> + DeserializeOwned + 'static> Access for T {
+    fn register() {
+      GOALS.write().unwrap().insert(T::name(), construct_goal::<T>);
+    }
+  }
+}
+
+pub mod action_registry {
+  use super::*;
+
+  pub trait Access {
+    fn register();
+  }
+
+  impl<T: Action<SceneWorld> + DeserializeOwned + 'static> Access for T {
+    fn register() {
+      ACTIONS.write().unwrap().insert(short_name::<T>(), construct_action::<T>);
+    }
+  }
+}
+
+pub mod sensor_registry {
+  use super::*;
+
+  pub trait Access {
+    fn register();
+  }
+
+  impl<T: Sensor<SceneWorld> + DeserializeOwned + 'static> Access for T {
+    fn register() {
+      SENSORS.write().unwrap().insert(short_name::<T>(), construct_sensor::<T>);
+    }
+  }
+}
+
+#[derive(Debug)]
+pub enum DefinitionError {
+  /// Nothing was registered under the name.
+  Unknown { kind: &'static str, name: String },
+  /// The parameters didn't fit what was registered.
+  Params { name: String, error: serde_json::Error },
+}
+
+impl fmt::Display for DefinitionError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      DefinitionError::Unknown { kind, name } => write!(f, "no {} registered as {}", kind, name),
+      DefinitionError::Params { name, error } => write!(f, "bad parameters for {}: {}", name, error),
+    }
+  }
+}
+
+/// A planner as the editor saves it, goals, actions and sensors listed by
+/// the name they were registered under along with their parameters:
+/// `{"Chill": {"max_distance": {"meters": 3}}}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannerDefinition {
+  pub id: Uuid,
+  pub name: String,
+  #[serde(default)]
+  pub goals: Vec<BTreeMap<String, Params>>,
+  #[serde(default)]
+  pub actions: Vec<BTreeMap<String, Params>>,
+  #[serde(default)]
+  pub sensors: Vec<BTreeMap<String, Params>>,
+  /// Nodes a single search can expand before giving up, the planner's own
+  /// default when left out.
+  #[serde(default)]
+  pub iteration_limit: Option<usize>,
+}
+
+impl PlannerDefinition {
+  /// Reads the definition out of any asset that serializes like one, such
+  /// as the planners of a level.
+  pub fn from_asset(asset: &impl Serialize) -> Result<Self, serde_json::Error> {
+    serde_json::from_value(serde_json::to_value(asset)?)
+  }
+
+  /// Names of the goals, in the order the planner gets them.
+  pub fn goal_names(&self) -> Vec<&str> {
+    self.goals.iter().flat_map(|entry| entry.keys()).map(|name| name.as_str()).collect()
+  }
+
+  /// Names of the actions, in the order the planner gets them.
+  pub fn action_names(&self) -> Vec<&str> {
+    self.actions.iter().flat_map(|entry| entry.keys()).map(|name| name.as_str()).collect()
+  }
+
+  /// Builds a fresh planner out of the registered goals, actions and
+  /// sensors. Every agent gets its own, as they keep state between ticks.
+  pub fn instantiate(&self) -> Result<Planner<SceneWorld>, DefinitionError> {
+    let mut planner = Planner::new();
+    if let Some(iteration_limit) = self.iteration_limit {
+      planner.set_iteration_limit(iteration_limit);
+    }
+
+    for (name, params) in self.goals.iter().flatten() {
+      planner.insert_boxed_goal(construct(&GOALS, "goal", name, params)?);
+    }
+    for (name, params) in self.actions.iter().flatten() {
+      planner.insert_boxed_action(construct(&ACTIONS, "action", name, params)?);
+    }
+    for (name, params) in self.sensors.iter().flatten() {
+      planner.insert_boxed_sensor(construct(&SENSORS, "sensor", name, params)?);
+    }
+
+    Ok(planner)
+  }
+}
+
+fn construct<T: ?Sized>(
+  registry: &RwLock<BTreeMap<&'static str, Constructor<T>>>,
+  kind: &'static str,
+  name: &str,
+  params: &Params,
+) -> Result<Box<T>, DefinitionError> {
+  let constructor = registry
+    .read()
+    .unwrap()
+    .get(name)
+    .copied()
+    .ok_or_else(|| DefinitionError::Unknown { kind, name: name.to_string() })?;
+
+  constructor(params.clone()).map_err(|error| DefinitionError::Params { name: name.to_string(), error })
+}
+
+/// Planner definitions of the loaded level, by id.
+#[derive(Debug, Clone, Default)]
+pub struct PlannerDefinitions {
+  definitions: HashMap<Uuid, PlannerDefinition>,
+}
+
+impl PlannerDefinitions {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn insert(&mut self, definition: PlannerDefinition) {
+    self.definitions.insert(definition.id, definition);
+  }
+
+  pub fn get(&self, id: &Uuid) -> Option<&PlannerDefinition> {
+    self.definitions.get(id)
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = &PlannerDefinition> {
+    self.definitions.values()
+  }
+}
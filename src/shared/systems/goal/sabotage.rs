@@ -0,0 +1,380 @@
+use serde::{Deserialize, Serialize};
+
+use engine::{
+  application::scene::Scene,
+  systems::Backpack,
+  utils::units::{Meters, Time},
+  Entity,
+};
+use crate::shared::replication::ReplicationQueue;
+use crate::utils::goap::{Sensor, Action, ActionStatus, Goal, GoalState, Effects, Operator, Blackboard};
+
+use nalgebra::Vector3;
+use tagged::{Registerable, Schema, Duplicate};
+
+use super::components::FireComponent;
+use super::navigation::{distance, move_towards, nearest_fire, players_around, stop, translation, walking_cost};
+use super::world::SceneWorld;
+
+/// Counts the players standing guard around the closest fire.
+#[derive(Debug, Clone, Serialize, Deserialize, Schema, Registerable, Duplicate)]
+pub struct SenseGuards {
+  #[schema(default = "{meters: 10.0}")]
+  guard_radius: Meters,
+}
+
+impl SenseGuards {
+  pub fn new(guard_radius: Meters) -> Self {
+    Self {
+      guard_radius,
+    }
+  }
+}
+
+impl Sensor<SceneWorld> for SenseGuards {
+  fn name(&self) -> &'static str {
+    "SenseGuards"
+  }
+
+  fn sense(
+    &mut self,
+    entity: Entity,
+    scene: &mut Scene,
+    backpack: &mut Backpack,
+    local: &mut Backpack,
+    blackboard: &mut Blackboard,
+  ) {
+    let origin = match translation(entity, scene) {
+      Some(translation) => translation,
+      None => return,
+    };
+
+    if let Some((_, fire, _)) = nearest_fire(scene, origin) {
+      let guards = players_around(scene, fire, *self.guard_radius).len();
+      blackboard.insert_number("FireGuards", guards as u32);
+    }
+  }
+}
+
+/// Whether a player got close enough to fight. Getting engaged throws the
+/// current plan away, so the agent can flee right away.
+#[derive(Debug, Clone, Serialize, Deserialize, Schema, Registerable, Duplicate)]
+pub struct SenseThreat {
+  #[schema(default = "{meters: 6.0}")]
+  engage_distance: Meters,
+}
+
+impl SenseThreat {
+  pub fn new(engage_distance: Meters) -> Self {
+    Self {
+      engage_distance,
+    }
+  }
+}
+
+impl Sensor<SceneWorld> for SenseThreat {
+  fn name(&self) -> &'static str {
+    "SenseThreat"
+  }
+
+  fn sense(
+    &mut self,
+    entity: Entity,
+    scene: &mut Scene,
+    backpack: &mut Backpack,
+    local: &mut Backpack,
+    blackboard: &mut Blackboard,
+  ) {
+    let origin = match translation(entity, scene) {
+      Some(translation) => translation,
+      None => return,
+    };
+
+    let engaged = !players_around(scene, origin, *self.engage_distance).is_empty();
+    blackboard.insert_bool("Engaged", engaged);
+  }
+
+  fn invalidates(
+    &self,
+    previous: &Blackboard,
+    current: &Blackboard,
+  ) -> bool {
+    previous.get_bool("Engaged") != Some(&true) && current.get_bool("Engaged") == Some(&true)
+  }
+}
+
+/// Puts the closest fire out. Worth the most when nobody guards it, every
+/// guard around the fire dividing how insistent it is.
+#[derive(Debug, Clone, Serialize, Deserialize, Schema, Registerable, Duplicate)]
+pub struct SabotageFire {
+  #[schema(default = "1.5")]
+  insistence: f32,
+}
+
+impl SabotageFire {
+  pub fn new(insistence: f32) -> Self {
+    Self {
+      insistence,
+    }
+  }
+}
+
+impl Goal<SceneWorld> for SabotageFire {
+  fn name() -> &'static str {
+    "SabotageFire"
+  }
+
+  fn get_goal(
+    &self,
+    entity: Entity,
+    scene: &mut Scene,
+    local: &mut Backpack,
+  ) -> GoalState {
+    let mut goal = GoalState::new();
+    goal.insert("FireFuel", Operator::Equal, 0u32);
+    goal
+  }
+
+  fn insistence(
+    &self,
+    entity: Entity,
+    scene: &mut Scene,
+    local: &Backpack,
+    blackboard: &Blackboard,
+  ) -> f32 {
+    let guards = blackboard.get_number("FireGuards").copied().unwrap_or(0);
+    self.insistence / (1 + guards) as f32
+  }
+}
+
+/// Walks up to the closest fire, as long as nobody's in the way.
+#[derive(Debug, Clone, Serialize, Deserialize, Schema, Registerable, Duplicate)]
+pub struct ApproachFire {}
+
+impl ApproachFire {
+  pub fn new() -> Self {
+    Self {}
+  }
+}
+
+impl Action<SceneWorld> for ApproachFire {
+  fn name(&self) -> &'static str {
+    "ApproachFire"
+  }
+
+  fn cost(
+    &self,
+    entity: Entity,
+    scene: &mut Scene,
+    local: &Backpack,
+    blackboard: &Blackboard,
+  ) -> i32 {
+    let walk = translation(entity, scene)
+      .and_then(|origin| nearest_fire(scene, origin).map(|(_, fire, _)| distance(origin, fire)))
+      .map(walking_cost)
+      .unwrap_or(0);
+    1 + walk
+  }
+
+  fn preconditions(&self) -> Option<GoalState> {
+    let mut preconditions = GoalState::new();
+    preconditions.insert("Engaged", Operator::NotEqual, true);
+    Some(preconditions)
+  }
+
+  fn effects(&self) -> Option<Effects> {
+    let mut effects = Effects::new();
+    effects.insert_bool("NearFire", true);
+    Some(effects)
+  }
+
+  fn execute(
+    &mut self,
+    entity: Entity,
+    scene: &mut Scene,
+    backpack: &mut Backpack,
+    local: &mut Backpack,
+  ) -> ActionStatus {
+    let origin = match translation(entity, scene) {
+      Some(translation) => translation,
+      None => return ActionStatus::Failure,
+    };
+
+    let (location, radius) = match nearest_fire(scene, origin) {
+      Some((_, location, fire)) => (location, *fire.deposit_radius),
+      None => return ActionStatus::Failure,
+    };
+
+    if distance(origin, location) > radius {
+      move_towards(entity, scene, backpack, location);
+      return ActionStatus::Running;
+    }
+
+    stop(entity, scene, backpack);
+    ActionStatus::Success
+  }
+}
+
+/// Drains the fuel of the fire the agent stands next to, `drain_rate` units
+/// per second, until it goes out.
+#[derive(Debug, Clone, Serialize, Deserialize, Schema, Registerable, Duplicate)]
+pub struct DrainFire {
+  #[schema(default = "5.0")]
+  drain_rate: f32,
+}
+
+impl DrainFire {
+  pub fn new(drain_rate: f32) -> Self {
+    Self {
+      drain_rate,
+    }
+  }
+}
+
+impl Action<SceneWorld> for DrainFire {
+  fn name(&self) -> &'static str {
+    "DrainFire"
+  }
+
+  fn cost(
+    &self,
+    entity: Entity,
+    scene: &mut Scene,
+    local: &Backpack,
+    blackboard: &Blackboard,
+  ) -> i32 {
+    2
+  }
+
+  fn preconditions(&self) -> Option<GoalState> {
+    let mut preconditions = GoalState::new();
+    preconditions.insert_bool("NearFire", true);
+    preconditions.insert("Engaged", Operator::NotEqual, true);
+    Some(preconditions)
+  }
+
+  fn effects(&self) -> Option<Effects> {
+    let mut effects = Effects::new();
+    effects.set("FireFuel", 0u32);
+    Some(effects)
+  }
+
+  fn execute(
+    &mut self,
+    entity: Entity,
+    scene: &mut Scene,
+    backpack: &mut Backpack,
+    local: &mut Backpack,
+  ) -> ActionStatus {
+    let delta = match backpack.get::<Time>() {
+      Some(time) => **time,
+      None => 0.0,
+    };
+
+    let origin = match translation(entity, scene) {
+      Some(translation) => translation,
+      None => return ActionStatus::Failure,
+    };
+
+    let (fire, location, radius) = match nearest_fire(scene, origin) {
+      Some((fire, location, component)) => (fire, location, *component.deposit_radius),
+      None => return ActionStatus::Failure,
+    };
+
+    if distance(origin, location) > radius {
+      return ActionStatus::Failure;
+    }
+
+    let drained = match scene.query_one_mut::<&mut FireComponent>(fire) {
+      Ok(component) => {
+        component.burn(self.drain_rate * delta);
+        component.fuel <= 0.0
+      },
+      Err(_) => return ActionStatus::Failure,
+    };
+
+    ReplicationQueue::mark(backpack, fire);
+
+    if drained {
+      ActionStatus::Success
+    } else {
+      ActionStatus::Running
+    }
+  }
+}
+
+/// Runs away from the closest player until it's `safe_distance` away.
+#[derive(Debug, Clone, Serialize, Deserialize, Schema, Registerable, Duplicate)]
+pub struct Flee {
+  #[schema(default = "{meters: 12.0}")]
+  safe_distance: Meters,
+}
+
+impl Flee {
+  pub fn new(safe_distance: Meters) -> Self {
+    Self {
+      safe_distance,
+    }
+  }
+}
+
+impl Action<SceneWorld> for Flee {
+  fn name(&self) -> &'static str {
+    "Flee"
+  }
+
+  fn cost(
+    &self,
+    entity: Entity,
+    scene: &mut Scene,
+    local: &Backpack,
+    blackboard: &Blackboard,
+  ) -> i32 {
+    2
+  }
+
+  fn preconditions(&self) -> Option<GoalState> {
+    let mut preconditions = GoalState::new();
+    preconditions.insert_bool("Engaged", true);
+    Some(preconditions)
+  }
+
+  fn effects(&self) -> Option<Effects> {
+    let mut effects = Effects::new();
+    effects.insert_bool("Engaged", false);
+    effects.insert_bool("NearFire", false);
+    Some(effects)
+  }
+
+  fn execute(
+    &mut self,
+    entity: Entity,
+    scene: &mut Scene,
+    backpack: &mut Backpack,
+    local: &mut Backpack,
+  ) -> ActionStatus {
+    let origin = match translation(entity, scene) {
+      Some(translation) => translation,
+      None => return ActionStatus::Failure,
+    };
+
+    let chaser = match players_around(scene, origin, *self.safe_distance).first() {
+      Some((_, chaser, _)) => *chaser,
+      None => {
+        stop(entity, scene, backpack);
+        return ActionStatus::Success;
+      },
+    };
+
+    let away = origin - chaser;
+    // Any way will do when standing right on top of each other
+    let direction = if away.norm() > f32::EPSILON {
+      away.normalize()
+    } else {
+      Vector3::x()
+    };
+    move_towards(entity, scene, backpack, origin + direction * *self.safe_distance);
+
+    ActionStatus::Running
+  }
+}
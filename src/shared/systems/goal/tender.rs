@@ -0,0 +1,569 @@
+use serde::{Deserialize, Serialize};
+
+use engine::{
+  application::scene::{Scene, TransformComponent},
+  systems::Backpack,
+  utils::units::Meters,
+  Entity,
+};
+use crate::shared::components::{
+  inventory_component::InventoryComponent, resource_component::ResourceComponent,
+};
+use crate::shared::replication::ReplicationQueue;
+use crate::utils::goap::{Sensor, Action, ActionStatus, Goal, GoalState, Effects, Operator, Blackboard};
+use engine::application::components::PhysicsComponent;
+use engine::systems::physics::PhysicsController;
+
+use nalgebra::Vector3;
+use tagged::{Registerable, Schema, Duplicate};
+
+use super::components::{FireComponent, FirewoodComponent, TreeComponent};
+use super::navigation::{distance, move_towards, nearest_fire, stop, translation, walking_cost};
+use super::world::SceneWorld;
+
+/// How close the tender has to get to a log to pick it up.
+const PICKUP_DISTANCE: f32 = 1.0;
+
+#[derive(Debug)]
+struct TreeLocation(Entity, Vector3<f32>, Meters);
+
+#[derive(Debug)]
+struct FirewoodLocation(Entity, Vector3<f32>, Meters);
+
+/// Looks for the closest tree that still has wood on it.
+#[derive(Debug, Clone, Serialize, Deserialize, Schema, Registerable, Duplicate)]
+pub struct SenseTree {
+  max_distance: Meters,
+}
+
+impl SenseTree {
+  pub fn new(max_distance: Meters) -> Self {
+    Self {
+      max_distance,
+    }
+  }
+}
+
+impl Sensor<SceneWorld> for SenseTree {
+  fn name(&self) -> &'static str {
+    "SenseTree"
+  }
+
+  fn sense(
+    &mut self,
+    entity: Entity,
+    scene: &mut Scene,
+    backpack: &mut Backpack,
+    local: &mut Backpack,
+    blackboard: &mut Blackboard,
+  ) {
+    let origin = match translation(entity, scene) {
+      Some(translation) => translation,
+      None => return,
+    };
+
+    let mut nearest: Option<(Entity, Vector3<f32>, f32)> = None;
+
+    for (tree, (transform, _, resource)) in scene.query_mut::<(
+      &TransformComponent,
+      &TreeComponent,
+      &ResourceComponent,
+    )>() {
+      if resource.is_depleted() {
+        continue;
+      }
+
+      let distance = distance(origin, transform.translation);
+      match nearest {
+        Some((_, _, current)) if distance >= current => {}
+        _ => nearest = Some((tree, transform.translation, distance)),
+      }
+    }
+
+    match nearest {
+      Some((tree, translation, distance)) if distance < *self.max_distance => {
+        local.insert(TreeLocation(tree, translation, Meters::new(distance)));
+        blackboard.insert_bool("SeesTree", true);
+      },
+      _ => {
+        local.take::<TreeLocation>();
+      },
+    }
+  }
+}
+
+/// Looks for the closest log lying around, logs being whatever has a
+/// `FirewoodComponent`, and keeps track of the ones the agent carries in its
+/// `InventoryComponent`.
+#[derive(Debug, Clone, Serialize, Deserialize, Schema, Registerable, Duplicate)]
+pub struct SenseFirewood {
+  max_distance: Meters,
+}
+
+impl SenseFirewood {
+  pub fn new(max_distance: Meters) -> Self {
+    Self {
+      max_distance,
+    }
+  }
+}
+
+impl Sensor<SceneWorld> for SenseFirewood {
+  fn name(&self) -> &'static str {
+    "SenseFirewood"
+  }
+
+  fn sense(
+    &mut self,
+    entity: Entity,
+    scene: &mut Scene,
+    backpack: &mut Backpack,
+    local: &mut Backpack,
+    blackboard: &mut Blackboard,
+  ) {
+    if let Ok(inventory) = scene.query_one_mut::<&InventoryComponent>(entity) {
+      blackboard.insert_number("CarriedWood", inventory.get_wood());
+      blackboard.insert_bool("InventoryFull", inventory.is_full());
+    }
+
+    let origin = match translation(entity, scene) {
+      Some(translation) => translation,
+      None => return,
+    };
+
+    let mut nearest: Option<(Entity, Vector3<f32>, f32)> = None;
+
+    for (log, (transform, _)) in scene.query_mut::<(&TransformComponent, &FirewoodComponent)>() {
+      let distance = distance(origin, transform.translation);
+      match nearest {
+        Some((_, _, current)) if distance >= current => {}
+        _ => nearest = Some((log, transform.translation, distance)),
+      }
+    }
+
+    match nearest {
+      Some((log, translation, distance)) if distance < *self.max_distance => {
+        local.insert(FirewoodLocation(log, translation, Meters::new(distance)));
+        blackboard.insert_bool("SeesFirewood", true);
+      },
+      _ => {
+        local.take::<FirewoodLocation>();
+      },
+    }
+  }
+}
+
+/// Keeps the closest fire above `threshold` of its fuel, read off the
+/// `FireFuel` that `SenseFire` reports. Gets more insistent the emptier the
+/// fire is.
+#[derive(Debug, Clone, Serialize, Deserialize, Schema, Registerable, Duplicate)]
+pub struct KeepFireFueled {
+  #[schema(default = "0.75")]
+  threshold: f32,
+}
+
+impl KeepFireFueled {
+  pub fn new(threshold: f32) -> Self {
+    Self {
+      threshold,
+    }
+  }
+}
+
+impl Goal<SceneWorld> for KeepFireFueled {
+  fn name() -> &'static str {
+    "KeepFireFueled"
+  }
+
+  fn get_goal(
+    &self,
+    entity: Entity,
+    scene: &mut Scene,
+    local: &mut Backpack,
+  ) -> GoalState {
+    let mut goal = GoalState::new();
+    goal.insert("FireFuel", Operator::GreaterOrEqual, (self.threshold * 100.0).ceil() as u32);
+    goal
+  }
+
+  fn insistence(
+    &self,
+    entity: Entity,
+    scene: &mut Scene,
+    local: &Backpack,
+    blackboard: &Blackboard,
+  ) -> f32 {
+    match blackboard.get_number("FireFuel") {
+      Some(fuel) => 2.0 * (1.0 - *fuel as f32 / 100.0),
+      None => 0.0,
+    }
+  }
+}
+
+/// Walks up to the closest tree and chops it until a log falls off. The
+/// `HarvestSystem` does the chopping, as it would for a player.
+#[derive(Debug, Clone, Serialize, Deserialize, Schema, Registerable, Duplicate)]
+pub struct ChopTree {
+  /// Tree being chopped, and the wood it had when chopping started.
+  #[serde(skip)]
+  chopping: Option<(Entity, u32)>,
+}
+
+impl ChopTree {
+  pub fn new() -> Self {
+    Self {
+      chopping: None,
+    }
+  }
+}
+
+impl Action<SceneWorld> for ChopTree {
+  fn name(&self) -> &'static str {
+    "ChopTree"
+  }
+
+  fn cost(
+    &self,
+    entity: Entity,
+    scene: &mut Scene,
+    local: &Backpack,
+    blackboard: &Blackboard,
+  ) -> i32 {
+    let walk = match local.get::<TreeLocation>() {
+      Some(TreeLocation(_, _, distance)) => walking_cost(**distance),
+      None => 0,
+    };
+    4 + walk
+  }
+
+  fn preconditions(&self) -> Option<GoalState> {
+    let mut preconditions = GoalState::new();
+    preconditions.insert_bool("SeesTree", true);
+    preconditions.insert("InventoryFull", Operator::NotEqual, true);
+    Some(preconditions)
+  }
+
+  fn effects(&self) -> Option<Effects> {
+    let mut effects = Effects::new();
+    effects.insert_bool("SeesFirewood", true);
+    // Trees grow away from the fire
+    effects.insert_bool("NearFire", false);
+    Some(effects)
+  }
+
+  fn timeout(&self) -> Option<f32> {
+    Some(20.0)
+  }
+
+  fn execute(
+    &mut self,
+    entity: Entity,
+    scene: &mut Scene,
+    backpack: &mut Backpack,
+    local: &mut Backpack,
+  ) -> ActionStatus {
+    let (tree, location, distance) = match local.get::<TreeLocation>() {
+      Some(TreeLocation(tree, location, distance)) => (*tree, *location, **distance),
+      None => {
+        self.chopping = None;
+        return ActionStatus::Failure;
+      },
+    };
+
+    let reach = match scene.query_one_mut::<&TreeComponent>(tree) {
+      Ok(component) => *component.chop_radius,
+      Err(_) => return ActionStatus::Failure,
+    };
+
+    if distance > reach {
+      move_towards(entity, scene, backpack, location);
+      return ActionStatus::Running;
+    }
+
+    stop(entity, scene, backpack);
+
+    let available = match scene.query_one_mut::<&ResourceComponent>(tree) {
+      Ok(resource) => resource.available,
+      Err(_) => return ActionStatus::Failure,
+    };
+
+    match self.chopping {
+      Some((chopped, started)) if chopped == tree && available < started => {
+        self.chopping = None;
+        return ActionStatus::Success;
+      },
+      Some((chopped, _)) if chopped == tree => {},
+      _ => self.chopping = Some((tree, available)),
+    }
+
+    if let Ok(inventory) = scene.query_one_mut::<&mut InventoryComponent>(entity) {
+      inventory.chop_requested = true;
+    }
+
+    ActionStatus::Running
+  }
+
+  fn reset(&mut self) {
+    self.chopping = None;
+  }
+}
+
+/// Walks up to the closest log and puts it in the inventory.
+#[derive(Debug, Clone, Serialize, Deserialize, Schema, Registerable, Duplicate)]
+pub struct PickUpFirewood {}
+
+impl PickUpFirewood {
+  pub fn new() -> Self {
+    Self {}
+  }
+}
+
+impl Action<SceneWorld> for PickUpFirewood {
+  fn name(&self) -> &'static str {
+    "PickUpFirewood"
+  }
+
+  fn cost(
+    &self,
+    entity: Entity,
+    scene: &mut Scene,
+    local: &Backpack,
+    blackboard: &Blackboard,
+  ) -> i32 {
+    let walk = match local.get::<FirewoodLocation>() {
+      Some(FirewoodLocation(_, _, distance)) => walking_cost(**distance),
+      None => 0,
+    };
+    1 + walk
+  }
+
+  fn preconditions(&self) -> Option<GoalState> {
+    let mut preconditions = GoalState::new();
+    preconditions.insert_bool("SeesFirewood", true);
+    preconditions.insert("InventoryFull", Operator::NotEqual, true);
+    Some(preconditions)
+  }
+
+  fn effects(&self) -> Option<Effects> {
+    let mut effects = Effects::new();
+    effects.add("CarriedWood", 1);
+    // The sensor reports the next log, if there's any left
+    effects.insert_bool("SeesFirewood", false);
+    Some(effects)
+  }
+
+  fn execute(
+    &mut self,
+    entity: Entity,
+    scene: &mut Scene,
+    backpack: &mut Backpack,
+    local: &mut Backpack,
+  ) -> ActionStatus {
+    let (log, location, distance) = match local.get::<FirewoodLocation>() {
+      Some(FirewoodLocation(log, location, distance)) => (*log, *location, **distance),
+      None => return ActionStatus::Failure,
+    };
+
+    if distance > PICKUP_DISTANCE {
+      move_towards(entity, scene, backpack, location);
+      return ActionStatus::Running;
+    }
+
+    stop(entity, scene, backpack);
+
+    match scene.query_one_mut::<&mut InventoryComponent>(entity) {
+      Ok(inventory) if !inventory.is_full() => inventory.add_wood(1),
+      _ => return ActionStatus::Failure,
+    }
+
+    if let Some(physics_controller) = backpack.get_mut::<PhysicsController>() {
+      if let Some(physics) = scene.get_components::<&PhysicsComponent>(log) {
+        physics_controller.despawn(&physics);
+      }
+    }
+    ReplicationQueue::despawn(scene, backpack, log);
+    scene.remove_entity(log);
+    local.take::<FirewoodLocation>();
+
+    ReplicationQueue::mark(backpack, entity);
+    ActionStatus::Success
+  }
+}
+
+/// Brings the wood over to the closest fire, close enough to feed it.
+#[derive(Debug, Clone, Serialize, Deserialize, Schema, Registerable, Duplicate)]
+pub struct CarryToFire {}
+
+impl CarryToFire {
+  pub fn new() -> Self {
+    Self {}
+  }
+}
+
+impl Action<SceneWorld> for CarryToFire {
+  fn name(&self) -> &'static str {
+    "CarryToFire"
+  }
+
+  fn cost(
+    &self,
+    entity: Entity,
+    scene: &mut Scene,
+    local: &Backpack,
+    blackboard: &Blackboard,
+  ) -> i32 {
+    let walk = translation(entity, scene)
+      .and_then(|origin| nearest_fire(scene, origin).map(|(_, fire, _)| distance(origin, fire)))
+      .map(walking_cost)
+      .unwrap_or(0);
+    1 + walk
+  }
+
+  fn preconditions(&self) -> Option<GoalState> {
+    let mut preconditions = GoalState::new();
+    preconditions.insert("CarriedWood", Operator::GreaterOrEqual, 1u32);
+    Some(preconditions)
+  }
+
+  fn effects(&self) -> Option<Effects> {
+    let mut effects = Effects::new();
+    effects.insert_bool("NearFire", true);
+    Some(effects)
+  }
+
+  fn execute(
+    &mut self,
+    entity: Entity,
+    scene: &mut Scene,
+    backpack: &mut Backpack,
+    local: &mut Backpack,
+  ) -> ActionStatus {
+    let origin = match translation(entity, scene) {
+      Some(translation) => translation,
+      None => return ActionStatus::Failure,
+    };
+
+    let (location, radius) = match nearest_fire(scene, origin) {
+      Some((_, location, fire)) => (location, *fire.deposit_radius),
+      None => return ActionStatus::Failure,
+    };
+
+    if distance(origin, location) > radius {
+      move_towards(entity, scene, backpack, location);
+      return ActionStatus::Running;
+    }
+
+    stop(entity, scene, backpack);
+    ActionStatus::Success
+  }
+}
+
+/// Share of the fuel of `fire` a log is worth, in the percent `SenseFire`
+/// reports `FireFuel` in. Never less than one, so that feeding always gets
+/// the planner somewhere.
+fn fuel_per_wood(fire: &FireComponent) -> u32 {
+  if fire.max_fuel <= 0.0 {
+    return 1;
+  }
+  (fire.fuel_per_wood / fire.max_fuel * 100.0).round().max(1.0) as u32
+}
+
+fn default_fuel_per_wood() -> u32 {
+  fuel_per_wood(&FireComponent::default())
+}
+
+/// Feeds a log to the fire the agent stands next to. The `DepositSystem`
+/// takes it out of the inventory, as it would for a player.
+#[derive(Debug, Clone, Serialize, Deserialize, Schema, Registerable, Duplicate)]
+pub struct FeedFire {
+  /// Logs the agent had deposited when feeding started.
+  #[serde(skip)]
+  deposited: Option<u32>,
+  /// Fuel a log is expected to add, as `fuel_per_wood` computes it. Starts
+  /// off as a default fire's, then follows the last fire the agent fed.
+  #[serde(skip, default = "default_fuel_per_wood")]
+  fuel_per_wood: u32,
+}
+
+impl FeedFire {
+  pub fn new() -> Self {
+    Self {
+      deposited: None,
+      fuel_per_wood: default_fuel_per_wood(),
+    }
+  }
+}
+
+impl Action<SceneWorld> for FeedFire {
+  fn name(&self) -> &'static str {
+    "FeedFire"
+  }
+
+  fn cost(
+    &self,
+    entity: Entity,
+    scene: &mut Scene,
+    local: &Backpack,
+    blackboard: &Blackboard,
+  ) -> i32 {
+    1
+  }
+
+  fn preconditions(&self) -> Option<GoalState> {
+    let mut preconditions = GoalState::new();
+    preconditions.insert_bool("NearFire", true);
+    preconditions.insert("CarriedWood", Operator::GreaterOrEqual, 1u32);
+    Some(preconditions)
+  }
+
+  fn effects(&self) -> Option<Effects> {
+    let mut effects = Effects::new();
+    effects.subtract("CarriedWood", 1);
+    // Burning isn't accounted for, the fire gets sensed again once the log
+    // is in
+    effects.add("FireFuel", self.fuel_per_wood);
+    Some(effects)
+  }
+
+  fn timeout(&self) -> Option<f32> {
+    Some(5.0)
+  }
+
+  fn execute(
+    &mut self,
+    entity: Entity,
+    scene: &mut Scene,
+    backpack: &mut Backpack,
+    local: &mut Backpack,
+  ) -> ActionStatus {
+    let fire = translation(entity, scene).and_then(|origin| nearest_fire(scene, origin));
+    if let Some((_, _, fire)) = fire {
+      self.fuel_per_wood = fuel_per_wood(&fire);
+    }
+
+    let inventory = match scene.query_one_mut::<&mut InventoryComponent>(entity) {
+      Ok(inventory) => inventory,
+      Err(_) => return ActionStatus::Failure,
+    };
+
+    match self.deposited {
+      Some(started) if inventory.wood_deposited > started => {
+        self.deposited = None;
+        ActionStatus::Success
+      },
+      Some(_) if inventory.get_wood() == 0 => {
+        self.deposited = None;
+        ActionStatus::Failure
+      },
+      _ => {
+        self.deposited.get_or_insert(inventory.wood_deposited);
+        inventory.deposit_requested = true;
+        ActionStatus::Running
+      },
+    }
+  }
+
+  fn reset(&mut self) {
+    self.deposited = None;
+  }
+}
@@ -0,0 +1,22 @@
+use engine::{application::scene::Scene, systems::Backpack, utils::units::Time, Entity};
+
+use crate::utils::goap::World;
+
+/// Agents are entities of the engine's scene, and keep what they know in a
+/// backpack of their own.
+#[derive(Debug)]
+pub struct SceneWorld;
+
+impl World for SceneWorld {
+  type Agent = Entity;
+  type Scene = Scene;
+  type Backpack = Backpack;
+  type Local = Backpack;
+
+  fn delta(backpack: &Backpack) -> f32 {
+    match backpack.get::<Time>() {
+      Some(time) => **time,
+      None => 0.0,
+    }
+  }
+}
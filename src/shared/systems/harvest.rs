@@ -0,0 +1,195 @@
+use crate::shared::{
+  components::{
+    inventory_component::InventoryComponent, resource_component::ResourceComponent,
+    respawn_component::RespawnComponent,
+  },
+  events::{GameEvent, GameEvents},
+  player_inputs::PlayerInputs,
+  replication::ReplicationQueue,
+  systems::goal::components::TreeComponent,
+};
+use engine::application::scene::component_registry::Access;
+use engine::{
+  application::{
+    components::PhysicsComponent,
+    scene::{IdComponent, Scene, TransformComponent},
+  },
+  systems::{Backpack, Initializable, Inventory, System},
+  utils::units::{Seconds, Time},
+  Entity,
+};
+use nalgebra::{Point3, Vector3};
+use uuid::Uuid;
+
+struct TreeSpot {
+  entity: Entity,
+  translation: Vector3<f32>,
+  radius: f32,
+  chop_time: f32,
+  available: u32,
+}
+
+/// Lets players chop wood out of trees, and regrows the trees over time.
+/// Every completed chop takes one unit out of the tree's `ResourceComponent`
+/// and drops a `Wood` prefab next to it for the player to pick up.
+pub struct HarvestSystem {}
+
+impl Initializable for HarvestSystem {
+  fn initialize(_: &Inventory) -> Self {
+    Self {}
+  }
+}
+
+impl System for HarvestSystem {
+  fn provide(&mut self, _: &Inventory) {
+    ResourceComponent::register();
+  }
+
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    let dt = **backpack.get::<Time>().unwrap();
+
+    self.regrow(scene, backpack, dt);
+
+    let mut trees = vec![];
+    for (entity, (transform, tree, resource)) in
+      scene.query_mut::<(&TransformComponent, &TreeComponent, &ResourceComponent)>()
+    {
+      trees.push(TreeSpot {
+        entity,
+        translation: transform.translation,
+        radius: *tree.chop_radius,
+        chop_time: *tree.chop_time,
+        available: resource.available,
+      });
+    }
+
+    let mut chops: Vec<(Entity, Vector3<f32>, usize)> = vec![];
+
+    let inputs = backpack.get::<PlayerInputs>();
+
+    for (player, (id, transform, inventory, respawn)) in scene.query_mut::<(
+      &IdComponent,
+      &TransformComponent,
+      &mut InventoryComponent,
+      Option<&RespawnComponent>,
+    )>() {
+      let held = inputs
+        .and_then(|inputs| inputs.get(id))
+        .map_or(false, |input| input.chop);
+      let requested = inventory.chop_requested || held;
+      inventory.chop_requested = false;
+
+      // The dead wait for their respawn, whatever they hold
+      if let Some(respawn) = respawn && !respawn.is_alive() {
+        inventory.chop_timer = 0.0;
+        continue;
+      }
+
+      let nearest = trees
+        .iter()
+        .enumerate()
+        .filter(|(_, tree)| tree.available > 0)
+        .map(|(index, tree)| {
+          let distance = nalgebra::distance(
+            &Point3::from(transform.translation),
+            &Point3::from(tree.translation),
+          );
+          (index, distance)
+        })
+        .filter(|(index, distance)| *distance <= trees[*index].radius)
+        .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+      let index = match nearest {
+        Some((index, _)) if requested => index,
+        _ => {
+          inventory.chop_timer = 0.0;
+          continue;
+        }
+      };
+
+      inventory.chop_timer += dt;
+      if inventory.chop_timer < trees[index].chop_time {
+        continue;
+      }
+
+      inventory.chop_timer = 0.0;
+      trees[index].available -= 1;
+      chops.push((player, transform.translation, index));
+    }
+
+    for (player, player_translation, index) in chops {
+      let tree = &trees[index];
+
+      let gathered = match scene.query_one_mut::<&mut ResourceComponent>(tree.entity) {
+        Ok(resource) => resource.gather_wood(),
+        Err(_) => false,
+      };
+
+      if !gathered {
+        continue;
+      }
+
+      if let Some(wood) = self.drop_wood(scene, tree.translation, player_translation) {
+        ReplicationQueue::mark(backpack, wood);
+      }
+
+      ReplicationQueue::mark(backpack, tree.entity);
+      GameEvents::emit(
+        backpack,
+        GameEvent::TreeChopped {
+          player,
+          tree: tree.entity,
+        },
+      );
+    }
+  }
+}
+
+impl HarvestSystem {
+  fn regrow(&mut self, scene: &mut Scene, backpack: &mut Backpack, dt: f32) {
+    let mut regrown = vec![];
+
+    for (entity, (_, resource)) in scene.query_mut::<(&TreeComponent, &mut ResourceComponent)>() {
+      if resource.is_full() {
+        resource.regen_timer = Seconds::new(0.0);
+        continue;
+      }
+
+      resource.regen_timer += Seconds::new(dt);
+      if resource.regen_timer >= resource.regen_interval {
+        resource.regen_timer = Seconds::new(0.0);
+        resource.restore_wood();
+        regrown.push(entity);
+      }
+    }
+
+    for entity in regrown {
+      ReplicationQueue::mark(backpack, entity);
+    }
+  }
+
+  fn drop_wood(
+    &mut self,
+    scene: &mut Scene,
+    tree: Vector3<f32>,
+    player: Vector3<f32>,
+  ) -> Option<Entity> {
+    let mut wood_prefab = scene.get_prefab("Wood").cloned()?;
+    // Drop the log halfway between the tree and the player, so it can't
+    // end up inside the trunk's collider
+    let mut translation = tree + (player - tree) * 0.5;
+    translation.y += 0.5;
+
+    let wood_entity = scene.create_raw_entity("Wood");
+    wood_prefab.id = IdComponent::new();
+    wood_prefab.transform.translation = translation;
+    for component in wood_prefab.components.iter_mut() {
+      if let Some(mut physics) = component.as_any_mut().downcast_mut::<PhysicsComponent>() {
+        physics.joint.id = Uuid::new_v4();
+        physics.joint.body.id = Uuid::new_v4();
+      }
+    }
+    scene.create_with_prefab(wood_entity, wood_prefab);
+    Some(wood_entity)
+  }
+}
@@ -0,0 +1,112 @@
+use crate::shared::{
+  components::{
+    health_component::HealthComponent, respawn_component::RespawnComponent,
+    weapon_component::WeaponComponent,
+  },
+  events::{GameEvent, GameEvents},
+  player_inputs::PlayerInputs,
+  replication::ReplicationQueue,
+};
+use engine::{
+  application::scene::{IdComponent, Scene, TransformComponent},
+  systems::{Backpack, Initializable, Inventory, System},
+  utils::units::Time,
+  Entity,
+};
+use nalgebra::{Point3, Vector3};
+use std::collections::HashMap;
+
+/// How close allies have to be to a healer to get healed.
+const HEAL_RADIUS: f32 = 6.0;
+/// Seconds before a healer can heal again.
+const HEAL_COOLDOWN: f32 = 8.0;
+
+/// Lets players wielding a support weapon heal everyone around them,
+/// themselves included, raising `Healed` for every player that got health
+/// back.
+pub struct HealSystem {
+  cooldowns: HashMap<Entity, f32>,
+}
+
+impl Initializable for HealSystem {
+  fn initialize(_: &Inventory) -> Self {
+    Self {
+      cooldowns: HashMap::new(),
+    }
+  }
+}
+
+impl System for HealSystem {
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    let dt = **backpack.get::<Time>().unwrap();
+
+    for cooldown in self.cooldowns.values_mut() {
+      *cooldown -= dt;
+    }
+    self.cooldowns.retain(|_, cooldown| *cooldown > 0.0);
+
+    let heals = match backpack.get::<PlayerInputs>() {
+      Some(inputs) => self.heals(scene, inputs),
+      None => return,
+    };
+
+    let mut healed = vec![];
+    for (healer, center, amount) in heals {
+      for (player, (transform, health, respawn)) in
+        scene.query_mut::<(&TransformComponent, &mut HealthComponent, &RespawnComponent)>()
+      {
+        let distance = nalgebra::distance(
+          &Point3::from(center),
+          &Point3::from(transform.translation),
+        );
+        if distance > HEAL_RADIUS || !respawn.is_alive() {
+          continue;
+        }
+
+        let restored = amount.min(health.max_health - health.health);
+        if restored > 0.0 {
+          health.health += restored;
+          healed.push((healer, player, restored));
+        }
+      }
+    }
+
+    for (healer, target, amount) in healed {
+      ReplicationQueue::mark(backpack, target);
+      GameEvents::emit(
+        backpack,
+        GameEvent::Healed {
+          healer,
+          target,
+          amount,
+        },
+      );
+    }
+  }
+}
+
+impl HealSystem {
+  /// Players healing this frame, along with where they stand and how much
+  /// health they give back.
+  fn heals(&mut self, scene: &mut Scene, inputs: &PlayerInputs) -> Vec<(Entity, Vector3<f32>, f32)> {
+    let mut heals = vec![];
+    for (player, (id, transform, weapon, respawn)) in scene.query_mut::<(
+      &IdComponent,
+      &TransformComponent,
+      &WeaponComponent,
+      &RespawnComponent,
+    )>() {
+      let healing = inputs.get(id).map_or(false, |input| input.heal);
+      if !healing || !respawn.is_alive() || self.cooldowns.contains_key(&player) {
+        continue;
+      }
+
+      if let Some(amount) = weapon.weapon().heal() {
+        self.cooldowns.insert(player, HEAL_COOLDOWN);
+        heals.push((player, transform.translation, amount));
+      }
+    }
+
+    heals
+  }
+}
@@ -1,4 +1,3 @@
-#![cfg(target_arch = "wasm32")]
 /*
 This file contains code that controls various item drops.
 Note: This is fairly clunky, we really need to get a better
@@ -11,19 +10,13 @@ use crate::shared::{
     magnetic_pickup_component::MagneticPickupComponent, movement_component::MovementComponent, resource_component::ResourceComponent, inventory_component::InventoryComponent,
   },
   game_types::game_types::EnemyState,
-  input::PlayerInput,
-};
-use engine::application::components::{
-  AnimationComponent, InputComponent, PhysicsComponent, SelfComponent,
+  player_inputs::PlayerInputs,
 };
+use engine::application::components::{AnimationComponent, PhysicsComponent};
 use engine::application::scene::component_registry::Access;
 use engine::application::scene::TagComponent;
-use engine::application::{
-  input::DefaultInput,
-  scene::{IdComponent, Scene, TransformComponent, UnpackEntity},
-};
+use engine::application::scene::{IdComponent, Scene, TransformComponent, UnpackEntity};
 use engine::systems::{
-  input::{CanvasController, InputsReader},
   physics::{PhysicsConfig, PhysicsController},
   Backpack, Initializable, Inventory, System,
 };
@@ -37,15 +30,13 @@ use std::char::MAX;
 use uuid::Uuid;
 
 pub struct ItemDropSystem {
-  inputs: InputsReader<PlayerInput>,
   physics: PhysicsController,
 }
 
 impl Initializable for ItemDropSystem {
   fn initialize(inventory: &Inventory) -> Self {
-    let inputs = inventory.get::<InputsReader<PlayerInput>>().clone();
     let physics = inventory.get::<PhysicsController>().clone();
-    Self { inputs, physics }
+    Self { physics }
   }
 }
 
@@ -59,9 +50,13 @@ impl System for ItemDropSystem {
   fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
     let mut to_drop = Vec::new();
 
-    for (_, (transform, _)) in &mut scene.query::<(&TransformComponent, &SelfComponent)>() {
-      let input = self.inputs.read();
-      if input.debug {
+    let inputs = match backpack.get::<PlayerInputs>() {
+      Some(inputs) => inputs,
+      None => return,
+    };
+
+    for (_, (transform, id)) in &mut scene.query::<(&TransformComponent, &IdComponent)>() {
+      if let Some(input) = inputs.get(id) && input.debug {
         to_drop.push(transform.translation.clone());
       }
     }
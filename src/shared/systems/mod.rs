@@ -1,9 +1,16 @@
 pub mod collisions;
 pub mod combat;
 pub mod death;
+pub mod deposit;
+pub mod enemy_ai;
+pub mod fire;
 pub mod goal;
+pub mod harvest;
+pub mod heal;
 pub mod lifetime;
 pub mod player_movement;
 pub mod sky;
+pub mod threat;
 pub mod spawn;
+pub mod warmth;
 pub mod item_drop;
@@ -1,46 +1,43 @@
-#![cfg(target_arch = "wasm32")]
-use crate::shared::{components::movement_component::MovementComponent, input::PlayerInput};
+use crate::shared::{
+  components::{
+    movement_component::MovementComponent, respawn_component::RespawnComponent,
+    top_down_camera_component::TopDownCameraComponent,
+  },
+  input::PlayerInput,
+  player_inputs::PlayerInputs,
+};
 use engine::application::scene::{component_registry::Access, IdComponent, TagComponent};
 use engine::{
   application::{
-    components::{AnimationComponent, InputComponent, PhysicsComponent, SelfComponent},
-    input::DefaultInput,
-    physics3d::CollisionEvent,
+    components::{CameraComponent, InputComponent, PhysicsComponent},
     scene::{Scene, TransformComponent},
   },
-  systems::{
-    input::{CanvasController, InputsReader},
-    physics::{CollisionsReader, PhysicsConfig, PhysicsController},
-    rendering::{CameraConfig, DebugController},
-    Backpack, Initializable, Inventory, System,
-  },
-  utils::units::{Kph, Seconds, Time},
-  Entity,
+  systems::{physics::PhysicsController, Backpack, Initializable, Inventory, System},
+  utils::units::Time,
 };
 use nalgebra::{
-  Isometry3, Point3, Rotation2, Rotation3, Unit, UnitQuaternion, Vector2, Vector3, Vector4,
+  Isometry3, Matrix4, Perspective3, Point3, Unit, UnitQuaternion, Vector3, Vector4,
 };
 use parry3d::{query::RayCast, shape::HalfSpace};
 use rapier3d::prelude::{QueryFilter, Ray};
 /// This file contains code related to moving and orienting the player's physical position
 /// in the game. For camera positioning, see src/client/camera.rs
+///
+/// It runs on the server, every player is driven by their own input from `PlayerInputs`.
 
 pub struct PlayerMovementSystem {
-  inputs: InputsReader<PlayerInput>,
   physics: PhysicsController,
   running_time: f32,
 }
 
 impl Initializable for PlayerMovementSystem {
   fn initialize(inventory: &Inventory) -> Self {
-    let inputs = inventory.get::<InputsReader<PlayerInput>>().clone();
     let physics = inventory.get::<PhysicsController>().clone();
     // if let Some(mut config) = inventory.get::<PhysicsConfig>() {
     //   config.gravity = Vector3::new(0.0, -9.8, 0.0);
     // }
 
     Self {
-      inputs,
       physics,
       running_time: 0.0,
     }
@@ -49,10 +46,8 @@ impl Initializable for PlayerMovementSystem {
 
 impl System for PlayerMovementSystem {
   fn attach(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
-
-    for (_, (_, physics)) in scene.query_mut::<(&SelfComponent, &PhysicsComponent)>() {
-      // self.physics.set_linear_damping(&physics, 0.6);
-      // self.physics.set_angular_damping(&physics, 0.6);
+    if backpack.get::<PlayerInputs>().is_none() {
+      backpack.insert(PlayerInputs::new());
     }
   }
 
@@ -69,16 +64,39 @@ impl System for PlayerMovementSystem {
 
 impl PlayerMovementSystem {
   fn handle_input(&mut self, scene: &mut Scene, dt: f32, backpack: &mut Backpack) {
-    for (entity, (physics, transform, movement, input_component, _)) in scene.query_mut::<(
-      &mut PhysicsComponent,
-      &mut TransformComponent,
-      &mut MovementComponent,
-      &mut InputComponent,
-      &mut SelfComponent,
-    )>() {
-      let camera = backpack.get_mut::<CameraConfig>().unwrap();
-      let input = self.inputs.read();
-      let (start, end) = self.mouse_to_ray(camera, &input);
+    let inputs = match backpack.get::<PlayerInputs>() {
+      Some(inputs) => inputs,
+      None => return,
+    };
+
+    for (entity, (id, physics, transform, movement, input_component, camera, top_down, respawn)) in
+      scene.query_mut::<(
+        &IdComponent,
+        &mut PhysicsComponent,
+        &mut TransformComponent,
+        &mut MovementComponent,
+        &mut InputComponent,
+        &CameraComponent,
+        &TopDownCameraComponent,
+        Option<&RespawnComponent>,
+      )>()
+    {
+      let input = match inputs.get(id) {
+        Some(input) => input,
+        None => continue,
+      };
+
+      // Dead players wait for their respawn where they fell
+      if let Some(respawn) = respawn && !respawn.is_alive() {
+        movement.target_point = None;
+        self.physics.set_linvel(&physics, Vector3::zeros());
+        continue;
+      }
+
+      let (start, end) = match self.mouse_to_ray(transform, camera, top_down, input) {
+        Some(ray) => ray,
+        None => continue,
+      };
 
       /*
       let debug_controller = backpack.get_mut::<DebugController>().unwrap();
@@ -196,17 +214,45 @@ impl PlayerMovementSystem {
     }
   }
 
+  /// Casts the mouse into the world. The server has no camera of its own, so
+  /// the player's top down camera is rebuilt the same way the client's
+  /// `CameraSystem` places it.
   fn mouse_to_ray(
-    &mut self,
-    camera: &CameraConfig,
+    &self,
+    transform: &TransformComponent,
+    camera: &CameraComponent,
+    top_down: &TopDownCameraComponent,
     input: &PlayerInput,
-  ) -> (Point3<f32>, Point3<f32>) {
-    let projection_matrix = camera.get_projection().to_homogeneous(); // Get the projection matrix
-    let view_matrix = camera.get_view(); // Get the view matrix
-    let inverse = (projection_matrix * view_matrix).try_inverse().unwrap(); // Get the inverse of the view-projection matrix
+  ) -> Option<(Point3<f32>, Point3<f32>)> {
+    let (fovy, znear, zfar) = match camera {
+      CameraComponent::Perspective {
+        fovy, znear, zfar, ..
+      } => (**fovy, *znear, *zfar),
+      _ => return None,
+    };
+
+    let (width, height) = (input.canvas.0 as f32, input.canvas.1 as f32);
+    if width <= 0.0 || height <= 0.0 {
+      return None;
+    }
+
+    let eye = Point3::new(
+      transform.translation.x,
+      *top_down.camera_height,
+      transform.translation.z - *top_down.camera_back_offset,
+    );
+    let target = Point3::from(transform.translation);
+
+    let projection_matrix = Perspective3::new(width / height, fovy, znear, zfar).to_homogeneous(); // Get the projection matrix
+    let view_matrix = Matrix4::look_at_rh(&eye, &target, &Vector3::y()); // Get the view matrix
+    let inverse = (projection_matrix * view_matrix).try_inverse()?; // Get the inverse of the view-projection matrix
 
-    let (mouse_x, mouse_y) = (input.mouse_position.x as u32, input.mouse_position.y as u32); // Destructure the mouse position coordinates
-    let pixel = camera.canvas_to_screenspace(mouse_x, mouse_y); // Convert the mouse position to screenspace
+    // Convert the mouse position to screenspace
+    let pixel = Vector3::new(
+      (input.mouse_position.x * input.pixel_ratio / width) * 2.0 - 1.0,
+      1.0 - (input.mouse_position.y * input.pixel_ratio / height) * 2.0,
+      0.0,
+    );
 
     let view_start = {
       // Calculate the start of the ray
@@ -225,6 +271,6 @@ impl PlayerMovementSystem {
     let start = Point3::new(view_start.x, view_start.y, view_start.z);
     let end = Point3::new(view_end.x, view_end.y, view_end.z);
 
-    (start, end)
+    Some((start, end))
   }
 }
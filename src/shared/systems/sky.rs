@@ -1,39 +1,108 @@
+use crate::shared::{
+  components::day_night_component::DayNightComponent, replication::ReplicationQueue,
+  time_of_day::TimeOfDay,
+};
+use engine::application::scene::component_registry::Access;
 use engine::{
-  application::{components::SkyLightComponent, scene::Scene},
+  application::{
+    components::SkyLightComponent,
+    scene::{Prefab, Scene},
+  },
   systems::{Backpack, Initializable, Inventory, System},
-  utils::units::Radians,
+  utils::units::{Radians, Time},
+  Entity,
 };
+use std::f32::consts::{FRAC_PI_2, PI};
+
+/// How often, in seconds, the clock gets pushed to clients.
+const SYNC_INTERVAL: f32 = 5.0;
 
+/// Runs the day/night clock and moves the sun along with it. The clock is
+/// the replicated `DayNightComponent`, nothing moves until there's one.
 pub struct SkySystem {
-  timing: f32,
+  sync_timer: f32,
 }
 
 impl Initializable for SkySystem {
   fn initialize(_: &Inventory) -> Self {
-    Self { timing: 0.0 }
+    Self { sync_timer: 0.0 }
   }
 }
 
 impl System for SkySystem {
+  fn provide(&mut self, _: &Inventory) {
+    DayNightComponent::register();
+  }
+
   fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
-    /*
-    for (_, sky) in scene.query_mut::<&mut SkyLightComponent>() {
-      match sky {
-        SkyLightComponent::Dynamic {
-          id: _,
-          intensity: _,
-          turbidity: _,
-          azimuth: _,
-          inclination,
-        } => {
-          // *azimuth = Radians::new(self.timing.cos() * 180.0);
-          *inclination = Radians::new(self.timing.cos() * 90.0);
-        }
-        _ => {}
+    let dt = **backpack.get::<Time>().unwrap();
+
+    let mut clock: Option<(Entity, TimeOfDay)> = None;
+    for (entity, day_night) in scene.query_mut::<&mut DayNightComponent>() {
+      Self::advance(day_night, dt);
+      clock = Some((entity, TimeOfDay::from_component(day_night)));
+      break;
+    }
+
+    let (clock, time_of_day) = match clock {
+      Some(clock) => clock,
+      None => return,
+    };
+
+    let mut skies = vec![];
+    for (entity, sky) in scene.query_mut::<&mut SkyLightComponent>() {
+      if let SkyLightComponent::Dynamic {
+        azimuth,
+        inclination,
+        ..
+      } = sky
+      {
+        *azimuth = Radians::new(time_of_day.time * 2.0 * PI);
+        *inclination = Radians::new(time_of_day.sun_elevation() * FRAC_PI_2);
+        skies.push(entity);
+      }
+    }
+
+    self.sync_timer += dt;
+    if self.sync_timer >= SYNC_INTERVAL {
+      self.sync_timer = 0.0;
+
+      ReplicationQueue::mark(backpack, clock);
+      for entity in skies {
+        ReplicationQueue::mark(backpack, entity);
       }
     }
-    */
 
-    self.timing += 0.0005;
+    backpack.insert(time_of_day);
+  }
+}
+
+impl SkySystem {
+  /// Spawns a clock with the default day length when the level doesn't come
+  /// with one, and returns its prefab so it can be brought back on a reset.
+  pub fn spawn_clock(scene: &mut Scene) -> Option<Prefab> {
+    if scene.query_mut::<&DayNightComponent>().into_iter().next().is_some() {
+      return None;
+    }
+
+    let entity = scene.create_raw_entity("Day Night");
+    let mut prefab = match Prefab::pack(scene, entity) {
+      Ok(prefab) => prefab,
+      Err(_) => {
+        let _ = scene.despawn(entity);
+        return None;
+      }
+    };
+    prefab.components.push(Box::new(DayNightComponent::default()));
+    scene.create_with_prefab(entity, prefab.clone());
+    Some(prefab)
+  }
+
+  fn advance(day_night: &mut DayNightComponent, dt: f32) {
+    if *day_night.day_length <= 0.0 {
+      return;
+    }
+
+    day_night.time_of_day = (day_night.time_of_day + dt / *day_night.day_length).rem_euclid(1.0);
   }
 }
@@ -1,108 +1,262 @@
-#![cfg(target_arch = "wasm32")]
 use crate::shared::{
-  components::{enemy_ai_component::EnemyAiComponent, spawn_component::SpawnComponent},
-  input::PlayerInput,
+  components::{
+    enemy_ai_component::EnemyAiComponent, spawn_component::SpawnComponent,
+    wave_director_component::{WaveDefinition, WaveDirectorComponent},
+  },
+  events::{GameEvent, GameEvents},
+  match_state::MatchState,
+  replication::ReplicationQueue,
+  rng::GameRng,
+  time_of_day::TimeOfDay,
 };
 use engine::application::scene::component_registry::Access;
 
 use engine::{
   application::{
-    components::{AnimationComponent, InputComponent, PhysicsComponent, SelfComponent},
-    input::DefaultInput,
-    physics3d::CollisionEvent,
-    scene::{IdComponent, Scene, TransformComponent},
-  },
-  systems::{
-    input::{CanvasController, InputsReader},
-    physics::{CollisionsReader, PhysicsConfig, PhysicsController},
-    Backpack, Initializable, Inventory, System,
+    components::PhysicsComponent,
+    scene::{IdComponent, PrefabId, Scene, TransformComponent},
   },
+  systems::{Backpack, Initializable, Inventory, System},
   utils::units::Time,
   Entity,
 };
 use nalgebra::Vector3;
-use rand::{distributions::Uniform, Rng};
-use uuid::Uuid;
+use rand::{distributions::WeightedIndex, prelude::Distribution, Rng};
+use std::collections::HashSet;
+use uuid::Builder;
+
+#[derive(Debug, Clone, Copy)]
+enum WavePhase {
+  Break { remaining: f32 },
+  Spawning { budget: u32, timer: f32 },
+  Clearing,
+}
 
+/// Paces the enemy spawns in waves, following the `WaveDirectorComponent`
+/// table found in the level. Every wave gets a budget to spend on enemies,
+/// once it's spent and all of them are dead the wave is cleared, and the
+/// next one starts after a break.
 pub struct SpawnSystem {
-  physics: PhysicsController,
+  wave: u32,
+  phase: WavePhase,
+  definition: Option<WaveDefinition>,
+  alive: HashSet<Entity>,
+  next_spawner: usize,
 }
 
 impl Initializable for SpawnSystem {
-  fn initialize(inventory: &Inventory) -> Self {
-    let physics = inventory.get::<PhysicsController>().clone();
-    Self { physics }
+  fn initialize(_: &Inventory) -> Self {
+    Self {
+      wave: 0,
+      phase: WavePhase::Break { remaining: 0.0 },
+      definition: None,
+      alive: HashSet::new(),
+      next_spawner: 0,
+    }
   }
 }
 
 impl System for SpawnSystem {
-  fn attach(&mut self, _: &mut Scene, _: &mut Backpack) {
-    return;
-  }
-
   fn provide(&mut self, inventory: &Inventory) {
     SpawnComponent::register();
+    WaveDirectorComponent::register();
   }
 
   fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
-    let dt = **backpack.get::<Time>().unwrap();
-    let mut rng = rand::thread_rng();
-    let mut swampeter_prefab;
-    if let Some(prefab) = scene.get_prefab("Swampeter").cloned() {
-      swampeter_prefab = prefab;
-    } else {
+    let director = self.director(scene);
+
+    if !MatchState::is_active(backpack) {
+      if self.wave > 0 {
+        self.reset(&director);
+      }
       return;
     }
 
-    let mut new_transform;
+    if self.wave == 0 {
+      self.reset(&director);
+      self.wave = 1;
+    }
 
-    // First pass: Collect the entities and their new positions
-    let mut new_entities = Vec::new();
-    for (entity, (spawn, transform, id)) in
-      scene.query_mut::<(&mut SpawnComponent, &TransformComponent, &IdComponent)>()
-    {
-      spawn.timer += dt;
+    let dt = **backpack.get::<Time>().unwrap();
+    self.alive.retain(|entity| scene.query_one_mut::<&IdComponent>(*entity).is_ok());
 
-      if spawn.timer >= spawn.interval && spawn.spawn_count() < spawn.max_enemies {
-        let angle = rng.gen_range(0.0..std::f32::consts::PI * 2.0);
-        let distance = rng.gen_range(2.0..spawn.radius);
-        let offset_x = distance * angle.cos();
-        let offset_z = distance * angle.sin();
+    match self.phase {
+      WavePhase::Break { remaining } => {
+        let remaining = remaining - dt;
+        if remaining > 0.0 {
+          self.phase = WavePhase::Break { remaining };
+          return;
+        }
 
-        let mut new_prefab = swampeter_prefab.clone();
+        self.definition = director.wave(self.wave);
+        let budget = match &self.definition {
+          Some(definition) => definition.budget,
+          None => return,
+        };
 
-        new_transform = transform.clone();
-        new_transform.translation.x += offset_x;
-        new_transform.translation.z += offset_z;
-        new_transform.scale = Vector3::new(1.0, 1.0, 1.0);
-        new_transform.rotation = Vector3::new(0.0, 0.0, 0.0);
+        log::info!("[waves] wave {} started with a budget of {}", self.wave, budget);
+        self.phase = WavePhase::Spawning { budget, timer: 0.0 };
+        GameEvents::emit(backpack, GameEvent::WaveStarted { wave: self.wave });
+      }
+      WavePhase::Spawning { budget, timer } => {
+        let definition = match &self.definition {
+          Some(definition) => definition.clone(),
+          None => return,
+        };
 
-        new_prefab.id = IdComponent::new();
-        new_prefab.transform = new_transform;
+        // Enemies come in faster at night
+        let timer = timer + dt * TimeOfDay::spawn_multiplier(backpack);
+        if timer < *definition.spawn_interval {
+          self.phase = WavePhase::Spawning { budget, timer };
+          return;
+        }
 
-        for component in new_prefab.components.iter_mut() {
-          if let Some(mut ai) = component.as_any_mut().downcast_mut::<EnemyAiComponent>() {
-            ai.set_spawned_from(**id)
-          }
+        self.phase = match self.spawn(scene, backpack, &definition, budget) {
+          Some(cost) if cost >= budget => WavePhase::Clearing,
+          Some(cost) => WavePhase::Spawning {
+            budget: budget - cost,
+            timer: 0.0,
+          },
+          // Nothing affordable left in the budget
+          None if self.affordable(&definition, budget).is_empty() => WavePhase::Clearing,
+          // Every spawner is full, try again on the next interval
+          None => WavePhase::Spawning { budget, timer: 0.0 },
+        };
+      }
+      WavePhase::Clearing => {
+        if !self.alive.is_empty() {
+          return;
         }
 
-        spawn.spawn_enemy();
-        new_entities.push((new_prefab, new_transform));
-        spawn.timer = 0.0;
+        log::info!("[waves] wave {} cleared", self.wave);
+        GameEvents::emit(backpack, GameEvent::WaveCleared { wave: self.wave });
+
+        let remaining = match &self.definition {
+          Some(definition) => *definition.break_duration,
+          None => 0.0,
+        };
+        self.wave += 1;
+        self.phase = WavePhase::Break { remaining };
       }
     }
+  }
+}
 
-    // Second pass: Create new entities with the collected information
-    for (mut new_prefab, new_transform) in new_entities {
-      for component in new_prefab.components.iter_mut() {
-        if let Some(mut physics) = component.as_any_mut().downcast_mut::<PhysicsComponent>() {
-          physics.joint.id = Uuid::new_v4();
-          physics.joint.body.id = Uuid::new_v4();
-        }
+impl SpawnSystem {
+  fn director(&mut self, scene: &mut Scene) -> WaveDirectorComponent {
+    scene
+      .query_mut::<&WaveDirectorComponent>()
+      .into_iter()
+      .map(|(_, director)| director.clone())
+      .next()
+      .unwrap_or_default()
+  }
+
+  fn reset(&mut self, director: &WaveDirectorComponent) {
+    self.wave = 0;
+    self.phase = WavePhase::Break {
+      remaining: *director.first_break,
+    };
+    self.definition = None;
+    self.alive.clear();
+  }
+
+  fn affordable(&self, definition: &WaveDefinition, budget: u32) -> Vec<usize> {
+    definition
+      .composition
+      .iter()
+      .enumerate()
+      .filter(|(_, enemy)| enemy.cost <= budget && enemy.weight > 0.0)
+      .map(|(index, _)| index)
+      .collect()
+  }
+
+  /// Spawns a single enemy from the wave composition on the next spawner in
+  /// line, returning how much of the budget it cost.
+  fn spawn(
+    &mut self,
+    scene: &mut Scene,
+    backpack: &mut Backpack,
+    definition: &WaveDefinition,
+    budget: u32,
+  ) -> Option<u32> {
+    let candidates = self.affordable(definition, budget);
+    let weights = candidates
+      .iter()
+      .map(|index| definition.composition[*index].weight)
+      .collect::<Vec<_>>();
+    let weights = WeightedIndex::new(&weights).ok()?;
+
+    // Everything random is rolled up front, ids included, so that a seeded
+    // `GameRng` spawns the same enemies in the same places every time
+    let (picked, angle, spread, ids) = {
+      let rng = GameRng::get(backpack);
+      (
+        weights.sample(rng),
+        rng.gen_range(0.0..std::f32::consts::PI * 2.0),
+        rng.gen_range(0.0..1.0),
+        [rng.gen::<[u8; 16]>(), rng.gen::<[u8; 16]>(), rng.gen::<[u8; 16]>()],
+      )
+    };
+    let enemy = &definition.composition[candidates[picked]];
+
+    let mut prefab = match scene.get_prefab(&enemy.prefab).cloned() {
+      Some(prefab) => prefab,
+      None => {
+        // Skip over it, so a typo in the table can't stall the wave
+        log::warn!("[waves] missing enemy prefab {:?}", &enemy.prefab);
+        return Some(enemy.cost.max(1));
+      }
+    };
+
+    let mut spawners = vec![];
+    for (entity, (spawn, transform, id)) in
+      scene.query_mut::<(&SpawnComponent, &TransformComponent, &IdComponent)>()
+    {
+      if !spawn.is_full() {
+        spawners.push((entity, transform.clone(), **id, spawn.radius));
+      }
+    }
+
+    if spawners.is_empty() {
+      return None;
+    }
+
+    self.next_spawner = (self.next_spawner + 1) % spawners.len();
+    let (spawner, transform, spawner_id, radius) = spawners.swap_remove(self.next_spawner);
+
+    let distance = 2.0 + spread * (radius.max(2.1) - 2.0);
+
+    let mut new_transform = transform;
+    new_transform.translation.x += distance * angle.cos();
+    new_transform.translation.z += distance * angle.sin();
+    new_transform.scale = Vector3::new(1.0, 1.0, 1.0);
+    new_transform.rotation = Vector3::new(0.0, 0.0, 0.0);
+
+    let [id, joint_id, body_id] = ids.map(|bytes| Builder::from_random_bytes(bytes).into_uuid());
+
+    prefab.id = IdComponent::with_id(PrefabId::with_id(id));
+    prefab.transform = new_transform;
+
+    for component in prefab.components.iter_mut() {
+      if let Some(mut ai) = component.as_any_mut().downcast_mut::<EnemyAiComponent>() {
+        ai.set_spawned_from(spawner_id)
+      }
+      if let Some(mut physics) = component.as_any_mut().downcast_mut::<PhysicsComponent>() {
+        physics.joint.id = joint_id;
+        physics.joint.body.id = body_id;
       }
+    }
 
-      let swampeter_entity = scene.create_raw_entity("Swampeter");
-      scene.create_with_prefab(swampeter_entity, new_prefab);
+    if let Ok(spawn) = scene.query_one_mut::<&mut SpawnComponent>(spawner) {
+      spawn.spawn_enemy();
     }
+
+    let entity = scene.create_raw_entity(&enemy.prefab);
+    scene.create_with_prefab(entity, prefab);
+    self.alive.insert(entity);
+    ReplicationQueue::mark(backpack, entity);
+
+    Some(enemy.cost.max(1))
   }
 }
@@ -0,0 +1,204 @@
+use crate::shared::{
+  components::{
+    enemy_ai_component::EnemyAiComponent, respawn_component::RespawnComponent,
+    weapon_component::WeaponComponent,
+  },
+  events::{GameEvent, GameEvents},
+  player_inputs::PlayerInputs,
+  players,
+  threat::ThreatTables,
+};
+use engine::application::scene::component_registry::Access;
+use engine::{
+  application::{
+    components::GoalComponent,
+    scene::{IdComponent, Scene, TransformComponent},
+  },
+  systems::{Backpack, Initializable, Inventory, System},
+  utils::units::Time,
+  Entity,
+};
+use nalgebra::{Point3, Vector3};
+use std::collections::HashMap;
+
+/// Threat a point of damage is worth.
+const DAMAGE_THREAT: f32 = 1.0;
+/// Threat a point of healing is worth, to every enemy fighting the healed.
+const HEALING_THREAT: f32 = 0.5;
+/// Threat per second a player right next to an enemy builds up, fading out
+/// to nothing at `PROXIMITY_RADIUS`.
+const PROXIMITY_THREAT: f32 = 2.0;
+const PROXIMITY_RADIUS: f32 = 10.0;
+/// Share of their threat enemies let go of every second.
+const DECAY_RATE: f32 = 0.1;
+/// How close enemies have to be to a tank to get taunted.
+const TAUNT_RADIUS: f32 = 8.0;
+/// Seconds before a tank can taunt again.
+const TAUNT_COOLDOWN: f32 = 12.0;
+
+/// Keeps the threat table of every enemy up to date. Players build threat
+/// up by hurting enemies, healing the players they fight, or just standing
+/// close, and it fades away over time. Dead players drop off the tables until
+/// they're back, enemies are whatever runs an AI.
+///
+/// Has to run after everything raising `DamageDealt` or `Healed`.
+pub struct ThreatSystem {
+  cooldowns: HashMap<Entity, f32>,
+}
+
+impl Initializable for ThreatSystem {
+  fn initialize(_: &Inventory) -> Self {
+    Self {
+      cooldowns: HashMap::new(),
+    }
+  }
+}
+
+impl System for ThreatSystem {
+  fn provide(&mut self, _: &Inventory) {
+    WeaponComponent::register();
+  }
+
+  fn attach(&mut self, _: &mut Scene, backpack: &mut Backpack) {
+    backpack.insert(ThreatTables::new());
+  }
+
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    let dt = **backpack.get::<Time>().unwrap();
+
+    let players = players::alive(scene);
+
+    let mut enemies = vec![];
+    for (enemy, (transform, _)) in scene.query_mut::<(&TransformComponent, &EnemyAiComponent)>() {
+      enemies.push((enemy, transform.translation));
+    }
+    for (enemy, (transform, _, respawn)) in
+      scene.query_mut::<(&TransformComponent, &GoalComponent, Option<&RespawnComponent>)>()
+    {
+      let known = enemies.iter().any(|(known, _)| *known == enemy);
+      if !known && respawn.is_none() {
+        enemies.push((enemy, transform.translation));
+      }
+    }
+
+    let taunts = self.taunts(scene, backpack, &players, dt);
+
+    let events = match backpack.get::<GameEvents>() {
+      Some(events) => events.iter().cloned().collect::<Vec<_>>(),
+      None => vec![],
+    };
+
+    let tables = match backpack.get_mut::<ThreatTables>() {
+      Some(tables) => tables,
+      None => return,
+    };
+
+    tables.retain(|enemy| enemies.iter().any(|(known, _)| *known == enemy));
+
+    for (enemy, enemy_translation) in &enemies {
+      let table = tables.entry(*enemy);
+
+      let gone = table
+        .iter()
+        .map(|(player, _)| *player)
+        .filter(|player| !players.iter().any(|(known, _)| known == player))
+        .collect::<Vec<_>>();
+      for player in gone {
+        table.forget(player);
+      }
+
+      table.decay(DECAY_RATE, dt);
+
+      for (player, player_translation) in &players {
+        let distance = nalgebra::distance(
+          &Point3::from(*enemy_translation),
+          &Point3::from(*player_translation),
+        );
+        if distance < PROXIMITY_RADIUS {
+          table.add(*player, PROXIMITY_THREAT * (1.0 - distance / PROXIMITY_RADIUS) * dt);
+        }
+      }
+    }
+
+    for event in events {
+      match event {
+        GameEvent::DamageDealt {
+          attacker,
+          target,
+          amount,
+        } => {
+          let from_player = players.iter().any(|(player, _)| *player == attacker);
+          let to_enemy = enemies.iter().any(|(enemy, _)| *enemy == target);
+          if from_player && to_enemy {
+            tables.entry(target).add(attacker, amount * DAMAGE_THREAT);
+          }
+        }
+        GameEvent::Healed {
+          healer,
+          target,
+          amount,
+        } => {
+          for (_, table) in tables.iter_mut() {
+            if table.get(target) > 0.0 {
+              table.add(healer, amount * HEALING_THREAT);
+            }
+          }
+        }
+        _ => {}
+      }
+    }
+
+    for (tank, tank_translation, duration) in taunts {
+      for (enemy, enemy_translation) in &enemies {
+        let distance = nalgebra::distance(
+          &Point3::from(*enemy_translation),
+          &Point3::from(tank_translation),
+        );
+        if distance <= TAUNT_RADIUS {
+          tables.entry(*enemy).taunt(tank, duration);
+        }
+      }
+    }
+  }
+}
+
+impl ThreatSystem {
+  /// Players taunting this frame, along with where they stand and how long
+  /// their taunt lasts.
+  fn taunts(
+    &mut self,
+    scene: &mut Scene,
+    backpack: &mut Backpack,
+    players: &[(Entity, Vector3<f32>)],
+    dt: f32,
+  ) -> Vec<(Entity, Vector3<f32>, f32)> {
+    for cooldown in self.cooldowns.values_mut() {
+      *cooldown -= dt;
+    }
+    self.cooldowns.retain(|_, cooldown| *cooldown > 0.0);
+
+    let inputs = match backpack.get::<PlayerInputs>() {
+      Some(inputs) => inputs,
+      None => return vec![],
+    };
+
+    let mut taunts = vec![];
+    for (player, (id, weapon)) in scene.query_mut::<(&IdComponent, &WeaponComponent)>() {
+      let taunting = inputs.get(id).map_or(false, |input| input.taunt);
+      if !taunting || self.cooldowns.contains_key(&player) {
+        continue;
+      }
+
+      let translation = players
+        .iter()
+        .find(|(known, _)| *known == player)
+        .map(|(_, translation)| *translation);
+      if let (Some(translation), Some(duration)) = (translation, weapon.weapon().taunt()) {
+        self.cooldowns.insert(player, TAUNT_COOLDOWN);
+        taunts.push((player, translation, *duration));
+      }
+    }
+
+    taunts
+  }
+}
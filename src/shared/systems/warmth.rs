@@ -0,0 +1,102 @@
+use crate::shared::{
+  components::{
+    health_component::HealthComponent, respawn_component::RespawnComponent,
+    warmth_component::WarmthComponent,
+  },
+  match_state::MatchState,
+  replication::ReplicationQueue,
+  systems::goal::components::FireComponent,
+};
+use engine::application::scene::component_registry::Access;
+use engine::{
+  application::scene::{Scene, TransformComponent},
+  systems::{Backpack, Initializable, Inventory, System},
+  utils::units::Time,
+  Entity,
+};
+use nalgebra::{Point3, Vector3};
+
+/// How often, in seconds, warmth gets pushed to clients.
+const REPLICATION_INTERVAL: f32 = 0.5;
+
+/// Warms up players standing within the heat radius of a fire, and freezes
+/// everyone else. Frozen players lose health until they get back to a fire.
+pub struct WarmthSystem {
+  replication_timer: f32,
+}
+
+impl Initializable for WarmthSystem {
+  fn initialize(_: &Inventory) -> Self {
+    Self {
+      replication_timer: 0.0,
+    }
+  }
+}
+
+impl System for WarmthSystem {
+  fn provide(&mut self, _: &Inventory) {
+    WarmthComponent::register();
+  }
+
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    if !MatchState::is_active(backpack) {
+      return;
+    }
+
+    let dt = **backpack.get::<Time>().unwrap();
+
+    let mut fires: Vec<(Vector3<f32>, f32)> = vec![];
+    for (_, (transform, fire)) in scene.query_mut::<(&TransformComponent, &FireComponent)>() {
+      fires.push((transform.translation, *fire.heat_radius()));
+    }
+
+    let mut changed: Vec<Entity> = vec![];
+
+    for (entity, (transform, warmth, health, respawn)) in scene.query_mut::<(
+      &TransformComponent,
+      &mut WarmthComponent,
+      &mut HealthComponent,
+      Option<&RespawnComponent>,
+    )>() {
+      // The dead don't freeze, their warmth comes back with the respawn
+      if let Some(respawn) = respawn && !respawn.is_alive() {
+        continue;
+      }
+
+      let is_warm = fires.iter().any(|(translation, radius)| {
+        nalgebra::distance(
+          &Point3::from(transform.translation),
+          &Point3::from(*translation),
+        ) <= *radius
+      });
+
+      if is_warm {
+        warmth.warmth = (warmth.warmth + warmth.recover_per_second * dt).min(warmth.max_warmth);
+      } else {
+        warmth.warmth = (warmth.warmth - warmth.drain_per_second * dt).max(0.0);
+      }
+
+      if warmth.is_freezing() {
+        health.pending_damage += warmth.freezing_damage_per_second * dt;
+      }
+
+      if is_warm != warmth.is_warm {
+        warmth.is_warm = is_warm;
+        changed.push(entity);
+      }
+    }
+
+    self.replication_timer += dt;
+    if self.replication_timer >= REPLICATION_INTERVAL {
+      self.replication_timer = 0.0;
+
+      for (entity, _) in scene.query_mut::<&WarmthComponent>() {
+        changed.push(entity);
+      }
+    }
+
+    for entity in changed {
+      ReplicationQueue::mark(backpack, entity);
+    }
+  }
+}
@@ -0,0 +1,125 @@
+use engine::Entity;
+use std::collections::HashMap;
+
+/// Threat below this is as good as none, and gets forgotten.
+const FORGET_BELOW: f32 = 0.01;
+
+/// How much every player bothers one enemy. Enemies go after whoever has
+/// the most threat, unless a tank taunted them into going after the tank.
+#[derive(Debug, Clone, Default)]
+pub struct ThreatTable {
+  threat: HashMap<Entity, f32>,
+  taunt: Option<(Entity, f32)>,
+}
+
+impl ThreatTable {
+  pub fn new() -> Self {
+    Self {
+      threat: HashMap::new(),
+      taunt: None,
+    }
+  }
+
+  pub fn add(&mut self, player: Entity, amount: f32) {
+    *self.threat.entry(player).or_insert(0.0) += amount;
+  }
+
+  pub fn get(&self, player: Entity) -> f32 {
+    self.threat.get(&player).copied().unwrap_or(0.0)
+  }
+
+  /// Locks the enemy on `player` for `duration` seconds. The player also
+  /// gets as much threat as the top of the table, so the enemy doesn't
+  /// turn away as soon as the taunt wears off.
+  pub fn taunt(&mut self, player: Entity, duration: f32) {
+    let top = self.threat.values().copied().fold(0.0, f32::max);
+    let threat = self.threat.entry(player).or_insert(0.0);
+    *threat = threat.max(top);
+    self.taunt = Some((player, duration));
+  }
+
+  pub fn taunted_by(&self) -> Option<Entity> {
+    self.taunt.map(|(player, _)| player)
+  }
+
+  /// Lets go of `rate` of the threat every second, and runs the taunt down.
+  pub fn decay(&mut self, rate: f32, delta: f32) {
+    let kept = (-rate * delta).exp();
+    for threat in self.threat.values_mut() {
+      *threat *= kept;
+    }
+    self.threat.retain(|_, threat| *threat >= FORGET_BELOW);
+
+    self.taunt = match self.taunt {
+      Some((player, remaining)) if remaining > delta => Some((player, remaining - delta)),
+      _ => None,
+    };
+  }
+
+  pub fn forget(&mut self, player: Entity) {
+    self.threat.remove(&player);
+    if self.taunted_by() == Some(player) {
+      self.taunt = None;
+    }
+  }
+
+  /// The player among `candidates` the enemy should go after: the one who
+  /// taunted it, or else the one with the most threat. `None` when nobody
+  /// in there has any threat.
+  pub fn target(&self, candidates: impl IntoIterator<Item = Entity>) -> Option<Entity> {
+    let mut target: Option<(Entity, f32)> = None;
+
+    for candidate in candidates {
+      if self.taunted_by() == Some(candidate) {
+        return Some(candidate);
+      }
+
+      let threat = self.get(candidate);
+      let higher = match target {
+        Some((_, current)) => threat > current,
+        None => threat > 0.0,
+      };
+      if higher {
+        target = Some((candidate, threat));
+      }
+    }
+
+    target.map(|(player, _)| player)
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = (&Entity, &f32)> {
+    self.threat.iter()
+  }
+}
+
+/// The threat table of every enemy, stored in the backpack. Kept up to date
+/// by the `ThreatSystem`, so that any AI can look up who to go after.
+#[derive(Debug, Default)]
+pub struct ThreatTables {
+  tables: HashMap<Entity, ThreatTable>,
+}
+
+impl ThreatTables {
+  pub fn new() -> Self {
+    Self {
+      tables: HashMap::new(),
+    }
+  }
+
+  pub fn get(&self, enemy: Entity) -> Option<&ThreatTable> {
+    self.tables.get(&enemy)
+  }
+
+  /// The table of `enemy`, starting an empty one if it has none yet.
+  pub fn entry(&mut self, enemy: Entity) -> &mut ThreatTable {
+    self.tables.entry(enemy).or_insert_with(ThreatTable::new)
+  }
+
+  pub fn retain(&mut self, mut keep: impl FnMut(Entity) -> bool) {
+    self.tables.retain(|enemy, _| keep(*enemy));
+  }
+
+  pub fn iter_mut(&mut self) -> impl Iterator<Item = (&Entity, &mut ThreatTable)> {
+    self.tables.iter_mut()
+  }
+}
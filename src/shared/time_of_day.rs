@@ -0,0 +1,55 @@
+use crate::shared::components::day_night_component::DayNightComponent;
+use engine::systems::Backpack;
+use std::f32::consts::PI;
+
+/// Current time of day, published in the backpack by the `SkySystem` so that
+/// gameplay systems can react to it.
+#[derive(Debug, Clone)]
+pub struct TimeOfDay {
+  /// From 0.0 to 1.0, where 0.0 is midnight and 0.5 is noon.
+  pub time: f32,
+  pub night_burn_multiplier: f32,
+  pub night_spawn_multiplier: f32,
+}
+
+impl TimeOfDay {
+  pub fn from_component(day_night: &DayNightComponent) -> Self {
+    Self {
+      time: day_night.time_of_day,
+      night_burn_multiplier: day_night.night_burn_multiplier,
+      night_spawn_multiplier: day_night.night_spawn_multiplier,
+    }
+  }
+
+  /// Height of the sun, from -1.0 at midnight to 1.0 at noon.
+  pub fn sun_elevation(&self) -> f32 {
+    (2.0 * PI * (self.time - 0.25)).sin()
+  }
+
+  pub fn is_night(&self) -> bool {
+    self.sun_elevation() < 0.0
+  }
+
+  /// How deep into the night we are, 0.0 during the day and 1.0 at midnight.
+  pub fn night_factor(&self) -> f32 {
+    (-self.sun_elevation()).max(0.0)
+  }
+
+  pub fn hour(&self) -> f32 {
+    self.time * 24.0
+  }
+
+  pub fn burn_multiplier(backpack: &Backpack) -> f32 {
+    match backpack.get::<TimeOfDay>() {
+      Some(time) => 1.0 + (time.night_burn_multiplier - 1.0) * time.night_factor(),
+      None => 1.0,
+    }
+  }
+
+  pub fn spawn_multiplier(backpack: &Backpack) -> f32 {
+    match backpack.get::<TimeOfDay>() {
+      Some(time) => 1.0 + (time.night_spawn_multiplier - 1.0) * time.night_factor(),
+      None => 1.0,
+    }
+  }
+}
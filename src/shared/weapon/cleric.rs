@@ -10,6 +10,10 @@ impl Weapon for Cleric {
   fn health(&self) -> f32 {
     0.0
   }
+
+  fn heal(&self) -> Option<f32> {
+    Some(20.0)
+  }
 }
 
 impl Cleric {
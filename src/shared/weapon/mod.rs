@@ -3,6 +3,8 @@ pub mod great_ax;
 pub mod hammer;
 pub mod tank;
 
+use engine::utils::units::Seconds;
+
 pub trait Weapon: Sync + Send {
   fn shield(&self) -> f32 {
     0.0
@@ -17,4 +19,16 @@ pub trait Weapon: Sync + Send {
   fn secondary_ability(&self) {}
 
   fn ultimate_ability(&self) {}
+
+  /// How long enemies around stay locked on the wielder after a taunt.
+  /// Only weapons made for drawing aggro can taunt.
+  fn taunt(&self) -> Option<Seconds> {
+    None
+  }
+
+  /// How much health the wielder gives back to every ally around them.
+  /// Only weapons made for support can heal.
+  fn heal(&self) -> Option<f32> {
+    None
+  }
 }
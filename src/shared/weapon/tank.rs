@@ -1,4 +1,5 @@
 use crate::shared::weapon::Weapon;
+use engine::utils::units::Seconds;
 
 pub struct Tank {}
 
@@ -10,6 +11,10 @@ impl Weapon for Tank {
   fn health(&self) -> f32 {
     2.0
   }
+
+  fn taunt(&self) -> Option<Seconds> {
+    Some(Seconds::new(4.0))
+  }
 }
 
 impl Tank {
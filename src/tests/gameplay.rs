@@ -0,0 +1,1191 @@
+use super::simulation::{Simulation, TICK};
+use crate::server::{
+  level::LevelSnapshot, lifecycle::MatchSystem, replication::ConnectedPlayers,
+  respawn::RespawnSystem, spawn_points::SpawnPoints,
+};
+use crate::shared::{
+  components::{
+    attack_component::AttackComponent, day_night_component::DayNightComponent,
+    enemy_ai_component::EnemyAiComponent,
+    health_component::HealthComponent, inventory_component::InventoryComponent,
+    match_component::MatchPhase,
+    resource_component::ResourceComponent, respawn_component::RespawnComponent,
+    shield_component::ShieldComponent,
+    spawn_component::SpawnComponent, warmth_component::WarmthComponent,
+    wave_director_component::WaveDirectorComponent,
+    weapon_component::{WeaponComponent, WeaponKind},
+  },
+  events::{GameEvent, GameEvents},
+  input::PlayerInput,
+  match_state::{MatchRules, MatchState},
+  systems::{
+    collisions::CollisionSystem,
+    combat::CombatSystem,
+    death::DeathSystem,
+    deposit::DepositSystem,
+    fire::FireSystem,
+    harvest::HarvestSystem,
+    heal::HealSystem,
+    goal::{
+      components::{FireComponent, FireState, TreeComponent},
+      GoalRegistry, GoalSystem, PlannerDefinition, PlannerDefinitions, SceneWorld,
+    },
+    sky::SkySystem,
+    spawn::SpawnSystem,
+    threat::ThreatSystem,
+    warmth::WarmthSystem,
+  },
+  threat::ThreatTables,
+};
+use crate::utils::goap::{
+  Planner, PlanningScheduler, PlanningStats, DEFAULT_BUDGET, DEFAULT_ITERATION_LIMIT,
+};
+use engine::{
+  application::{
+    components::GoalComponent,
+    scene::{IdComponent, TagComponent, TransformComponent},
+  },
+  networking::connection::PlayerId,
+  systems::{Backpack, Registry},
+  utils::units::{Seconds, Time},
+  Entity,
+};
+use nalgebra::Vector3;
+
+fn fire(sim: &mut Simulation, fire: FireComponent) -> Entity {
+  let mut prefab = sim.prefab("Fire", Vector3::zeros());
+  prefab.components.push(Box::new(fire));
+  sim.create(prefab)
+}
+
+fn player_with(sim: &mut Simulation, translation: Vector3<f32>, wood: u32) -> Entity {
+  let mut prefab = sim.player(translation);
+  prefab.components.push(Box::new(HealthComponent {
+    health: 100.0,
+    max_health: 100.0,
+    pending_damage: 0.0,
+  }));
+
+  let mut inventory = InventoryComponent::default();
+  inventory.add_wood(wood);
+  prefab.components.push(Box::new(inventory));
+
+  sim.create(prefab)
+}
+
+#[test]
+fn level_loads_without_a_renderer() {
+  let mut sim = Simulation::new(1).with_level("arena.lvl");
+
+  assert!(sim.count::<ResourceComponent>() > 0);
+  assert!(sim.count::<TreeComponent>() > 0);
+  assert!(sim.count::<SpawnComponent>() > 0);
+  assert_eq!(sim.count::<WaveDirectorComponent>(), 1);
+  assert!(!sim.tagged("Wood").is_empty());
+}
+
+#[test]
+fn levels_without_a_clock_get_the_default_one() {
+  let mut sim = Simulation::new(1)
+    .with_level("arena.lvl")
+    .with_system::<SkySystem>();
+  let start = sim.all::<DayNightComponent>();
+  assert_eq!(start.len(), 1);
+
+  sim.step_seconds(60.0);
+  let (_, clock) = sim.all::<DayNightComponent>().pop().unwrap();
+  assert!((clock.time_of_day - start[0].1.time_of_day - 0.1).abs() < 0.01);
+}
+
+#[test]
+fn level_planners_build_from_registered_names() {
+  GoalRegistry::register();
+  let mut sim = Simulation::new(1).with_level("arena.lvl");
+
+  let definitions = sim.backpack_mut().get::<PlannerDefinitions>().unwrap();
+  assert!(definitions.iter().any(|definition| definition.name == "Fire Monster"));
+  for definition in definitions.iter() {
+    assert!(definition.instantiate().is_ok(), "{} didn't build", definition.name);
+  }
+}
+
+#[test]
+fn planner_definitions_can_set_their_iteration_limit() {
+  let limited: PlannerDefinition = serde_json::from_value(serde_json::json!({
+    "id": "0d9b2f6e-5c1a-4e7b-8f3d-2a6c9e1b4d70",
+    "name": "Limited",
+    "iteration_limit": 200
+  }))
+  .unwrap();
+  assert_eq!(limited.instantiate().unwrap().iteration_limit(), 200);
+
+  let unlimited: PlannerDefinition = serde_json::from_value(serde_json::json!({
+    "id": "5e8a1c3f-7b2d-4f9e-a6c0-3d1b8e5f2a94",
+    "name": "Default"
+  }))
+  .unwrap();
+  assert_eq!(unlimited.instantiate().unwrap().iteration_limit(), DEFAULT_ITERATION_LIMIT);
+}
+
+#[test]
+fn the_planning_budget_comes_from_the_backpack() {
+  let mut sim = Simulation::new(1).with_system::<GoalSystem>();
+  sim.step(1);
+  let scheduler = sim.backpack_mut().get::<PlanningScheduler<SceneWorld>>().unwrap();
+  assert_eq!(scheduler.budget(), DEFAULT_BUDGET);
+
+  let mut sim = Simulation::new(1);
+  sim.backpack_mut().insert(PlanningScheduler::<SceneWorld>::new(7));
+  let mut sim = sim.with_system::<GoalSystem>();
+  sim.step(1);
+  let scheduler = sim.backpack_mut().get::<PlanningScheduler<SceneWorld>>().unwrap();
+  assert_eq!(scheduler.budget(), 7);
+}
+
+#[test]
+fn fire_keeper_uses_every_action() {
+  GoalRegistry::register();
+  let mut sim = Simulation::new(1).with_level("arena.lvl");
+  let keeper = sim.spawn_player(Vector3::zeros());
+
+  let definition = sim
+    .backpack_mut()
+    .get::<PlannerDefinitions>()
+    .and_then(|definitions| definitions.iter().find(|definition| definition.name == "Fire Keeper"))
+    .cloned()
+    .unwrap();
+  let mut planner = definition.instantiate().unwrap();
+
+  let analysis = planner.analyze(keeper, sim.scene_mut(), &mut Backpack::new());
+  assert!(analysis.is_clean(), "{:?}", analysis);
+}
+
+fn tree(sim: &mut Simulation, translation: Vector3<f32>) -> Entity {
+  let tree: TreeComponent = serde_json::from_value(serde_json::json!({
+    "id": "3f0c1f4e-8d53-4d0e-a54c-0a9a4f3f1d21",
+    "chop_radius": {"meters": 2.5},
+    "chop_time": {"seconds": 1.0}
+  }))
+  .unwrap();
+  let resource: ResourceComponent = serde_json::from_value(serde_json::json!({
+    "available": 5,
+    "max": 5,
+    "regen_amount": 1,
+    "regen_interval": {"seconds": 30.0}
+  }))
+  .unwrap();
+
+  let mut prefab = sim.prefab("Tree", translation);
+  prefab.components.push(Box::new(tree));
+  prefab.components.push(Box::new(resource));
+  sim.create(prefab)
+}
+
+fn chops(sim: &Simulation, tree: Entity) -> usize {
+  sim
+    .events()
+    .iter()
+    .filter(|event| matches!(event, GameEvent::TreeChopped { tree: chopped, .. } if *chopped == tree))
+    .count()
+}
+
+fn set_resource(sim: &mut Simulation, tree: Entity, available: u32, regen_amount: u32) {
+  let resource = sim.scene_mut().query_one_mut::<&mut ResourceComponent>(tree).unwrap();
+  resource.available = available;
+  resource.regen_amount = regen_amount;
+}
+
+#[test]
+fn chopping_takes_the_trees_chop_time() {
+  let mut sim = Simulation::new(1).with_system::<HarvestSystem>();
+  let tree = tree(&mut sim, Vector3::zeros());
+  let player = player_with(&mut sim, Vector3::new(1.0, 0.0, 0.0), 0);
+
+  let mut chop = PlayerInput::default();
+  chop.chop = true;
+  sim.script(player, 0..30, chop.clone());
+  sim.step(30);
+  let timer = sim.get::<InventoryComponent>(player).unwrap().chop_timer;
+  assert!((timer - 0.5).abs() < 0.01, "{}", timer);
+  assert_eq!(sim.get::<ResourceComponent>(tree).unwrap().available, 5);
+
+  // Letting go starts the chop over
+  sim.step(1);
+  assert_eq!(sim.get::<InventoryComponent>(player).unwrap().chop_timer, 0.0);
+
+  sim.script(player, 31..101, chop);
+  sim.step(50);
+  assert_eq!(sim.get::<ResourceComponent>(tree).unwrap().available, 5);
+  assert_eq!(chops(&sim, tree), 0);
+
+  sim.step(20);
+  assert_eq!(sim.get::<ResourceComponent>(tree).unwrap().available, 4);
+  assert_eq!(chops(&sim, tree), 1);
+}
+
+#[test]
+fn chopped_wood_drops_between_the_tree_and_the_player() {
+  let mut sim = Simulation::new(1)
+    .with_level("arena.lvl")
+    .with_system::<HarvestSystem>();
+  // Far from the trees and logs of the level
+  let camp = Vector3::new(5000.0, 0.0, 5000.0);
+  let tree = tree(&mut sim, camp);
+  let player = player_with(&mut sim, camp + Vector3::new(2.0, 0.0, 0.0), 0);
+  let logs = sim.tagged("Wood");
+
+  let mut chop = PlayerInput::default();
+  chop.chop = true;
+  sim.script(player, 0..90, chop);
+  sim.step(90);
+
+  let dropped = sim
+    .tagged("Wood")
+    .into_iter()
+    .filter(|wood| !logs.contains(wood))
+    .collect::<Vec<_>>();
+  assert_eq!(dropped.len(), 1);
+  let translation = sim.translation(dropped[0]).unwrap();
+  assert!((translation - (camp + Vector3::new(1.0, 0.5, 0.0))).norm() < 0.01);
+  assert_eq!(sim.get::<ResourceComponent>(tree).unwrap().available, 4);
+}
+
+#[test]
+fn depleted_trees_yield_nothing() {
+  let mut sim = Simulation::new(1).with_system::<HarvestSystem>();
+  let tree = tree(&mut sim, Vector3::zeros());
+  set_resource(&mut sim, tree, 0, 1);
+  let player = player_with(&mut sim, Vector3::new(1.0, 0.0, 0.0), 0);
+
+  let mut chop = PlayerInput::default();
+  chop.chop = true;
+  sim.script(player, 0..300, chop);
+  sim.step(300);
+
+  assert_eq!(sim.get::<ResourceComponent>(tree).unwrap().available, 0);
+  assert_eq!(sim.get::<InventoryComponent>(player).unwrap().chop_timer, 0.0);
+  assert_eq!(chops(&sim, tree), 0);
+}
+
+#[test]
+fn trees_regrow_up_to_their_max() {
+  let mut sim = Simulation::new(1).with_system::<HarvestSystem>();
+  let tree = tree(&mut sim, Vector3::zeros());
+  set_resource(&mut sim, tree, 3, 1);
+
+  sim.step_seconds(29.0);
+  assert_eq!(sim.get::<ResourceComponent>(tree).unwrap().available, 3);
+  sim.step_seconds(2.0);
+  assert_eq!(sim.get::<ResourceComponent>(tree).unwrap().available, 4);
+  sim.step_seconds(30.0);
+  assert_eq!(sim.get::<ResourceComponent>(tree).unwrap().available, 5);
+  sim.step_seconds(120.0);
+  assert_eq!(sim.get::<ResourceComponent>(tree).unwrap().available, 5);
+
+  // Growing back more than was taken still stops at the max
+  set_resource(&mut sim, tree, 4, 3);
+  sim.step_seconds(31.0);
+  assert_eq!(sim.get::<ResourceComponent>(tree).unwrap().available, 5);
+}
+
+#[test]
+fn fire_keepers_chop_carry_and_feed_the_fire() {
+  GoalRegistry::register();
+  let mut sim = Simulation::new(1)
+    .with_level("arena.lvl")
+    .with_system::<GoalSystem>()
+    .with_system::<HarvestSystem>()
+    .with_system::<DepositSystem>();
+  // Far from the trees, logs and fires of the level
+  let camp = Vector3::new(5000.0, 0.0, 5000.0);
+
+  let mut component = FireComponent::default();
+  component.fuel = 70.0;
+  component.burn_rate = 0.0;
+  component.auto_deposit = false;
+  let fire = fire(&mut sim, component);
+  sim.scene_mut().query_one_mut::<&mut TransformComponent>(fire).unwrap().translation =
+    camp + Vector3::new(-1.5, 0.0, 0.0);
+  let tree = tree(&mut sim, camp + Vector3::new(1.0, 0.0, 0.0));
+
+  let goal: GoalComponent = serde_json::from_value(serde_json::json!({
+    "id": "b6f1d7a2-2c4e-4b8e-9f0a-6d3c5e7a9b14",
+    "planner_id": "e50e42a8-123f-4c44-866e-1743ea868ce6"
+  }))
+  .unwrap();
+  let mut prefab = sim.prefab("Fire Keeper", camp);
+  prefab.components.push(Box::new(InventoryComponent::default()));
+  prefab.components.push(Box::new(goal));
+  let keeper = sim.create(prefab);
+
+  sim.step_seconds(5.0);
+
+  assert_eq!(sim.get::<ResourceComponent>(tree).unwrap().available, 4);
+  assert!(sim.events().iter().any(|event| match event {
+    GameEvent::TreeChopped { player, tree: chopped } => *player == keeper && *chopped == tree,
+    _ => false,
+  }));
+  assert!(sim.events().iter().any(|event| match event {
+    GameEvent::WoodDeposited { player, fire: fed, .. } => *player == keeper && *fed == fire,
+    _ => false,
+  }));
+  assert_eq!(sim.get::<InventoryComponent>(keeper).unwrap().get_wood(), 0);
+  assert!(sim.get::<FireComponent>(fire).unwrap().fuel >= 75.0);
+  assert!(sim.backpack_mut().get::<PlanningStats>().unwrap().total_expansions > 0);
+}
+
+#[test]
+fn fire_burns_out_and_loses_the_match() {
+  let mut sim = Simulation::new(1).with_system::<FireSystem>();
+  let mut component = FireComponent::default();
+  component.fuel = 10.0;
+  component.burn_rate = 1.0;
+  let entity = fire(&mut sim, component);
+
+  sim.step_seconds(5.0);
+  let burning = sim.get::<FireComponent>(entity).unwrap();
+  assert!((burning.fuel - 5.0).abs() < 0.1);
+  assert_eq!(burning.state, FireState::Dim);
+
+  sim.step_seconds(6.0);
+  assert!(sim.get::<FireComponent>(entity).unwrap().is_out());
+  assert!(sim.events().contains(&GameEvent::MatchLost));
+}
+
+#[test]
+fn players_feed_the_fire_while_holding_deposit() {
+  let mut sim = Simulation::new(1)
+    .with_system::<FireSystem>()
+    .with_system::<DepositSystem>();
+  let mut component = FireComponent::default();
+  component.fuel = 50.0;
+  component.burn_rate = 0.0;
+  component.auto_deposit = false;
+  let fire = fire(&mut sim, component);
+  let player = player_with(&mut sim, Vector3::new(1.0, 0.0, 0.0), 3);
+
+  // Nothing goes in until the player asks for it
+  sim.step_seconds(2.0);
+  assert_eq!(sim.get::<InventoryComponent>(player).unwrap().get_wood(), 3);
+
+  let mut deposit = PlayerInput::default();
+  deposit.deposit = true;
+  let from = sim.tick();
+  sim.script(player, from..from + 90, deposit);
+  sim.step_seconds(2.0);
+
+  let inventory = sim.get::<InventoryComponent>(player).unwrap();
+  let fuel = sim.get::<FireComponent>(fire).unwrap().fuel;
+  assert_eq!(inventory.get_wood(), 1);
+  assert!((fuel - 70.0).abs() < 0.1);
+}
+
+#[test]
+fn players_freeze_away_from_the_fire() {
+  let mut sim = Simulation::new(1).with_system::<WarmthSystem>();
+  fire(&mut sim, FireComponent::default());
+  let warm = player_with(&mut sim, Vector3::new(2.0, 0.0, 0.0), 0);
+  let cold = player_with(&mut sim, Vector3::new(50.0, 0.0, 0.0), 0);
+
+  sim.step_seconds(60.0);
+
+  assert!(!sim.get::<WarmthComponent>(warm).unwrap().is_freezing());
+  assert!(sim.get::<WarmthComponent>(cold).unwrap().is_freezing());
+  assert_eq!(sim.get::<HealthComponent>(warm).unwrap().pending_damage, 0.0);
+  assert!(sim.get::<HealthComponent>(cold).unwrap().pending_damage > 0.0);
+}
+
+fn waves(seed: u64) -> Vec<Vector3<f32>> {
+  let mut sim = Simulation::new(seed).with_system::<SpawnSystem>();
+
+  let enemy = sim.prefab("Swampeter", Vector3::zeros());
+  sim.scene_mut().store_prefab("Swampeter", enemy);
+
+  let mut spawner = sim.prefab("Spawner", Vector3::new(20.0, 0.0, 0.0));
+  spawner.components.push(Box::new(SpawnComponent::default()));
+  sim.create(spawner);
+
+  sim.step_seconds(30.0);
+
+  let enemies = sim.tagged("Swampeter");
+  enemies
+    .into_iter()
+    .filter_map(|entity| sim.translation(entity))
+    .collect()
+}
+
+#[test]
+fn seeded_waves_spawn_the_same_enemies() {
+  let first = waves(7);
+  let second = waves(7);
+
+  assert!(!first.is_empty());
+  assert_eq!(first, second);
+}
+
+fn enemy(sim: &mut Simulation, translation: Vector3<f32>) -> Entity {
+  let mut prefab = sim.prefab("Swampeter", translation);
+  prefab.components.push(Box::new(EnemyAiComponent::new()));
+  sim.create(prefab)
+}
+
+fn hurt(sim: &mut Simulation, attacker: Entity, target: Entity, amount: f32) {
+  GameEvents::emit(
+    sim.backpack_mut(),
+    GameEvent::DamageDealt {
+      attacker,
+      target,
+      amount,
+    },
+  );
+}
+
+fn wielding(sim: &mut Simulation, translation: Vector3<f32>, kind: WeaponKind) -> Entity {
+  let mut prefab = sim.player(translation);
+  for component in prefab.components.iter_mut() {
+    if let Some(weapon) = component.as_any_mut().downcast_mut::<WeaponComponent>() {
+      *weapon = WeaponComponent::new(kind);
+    }
+  }
+  sim.create(prefab)
+}
+
+fn target_of(sim: &mut Simulation, enemy: Entity, players: &[Entity]) -> Option<Entity> {
+  sim
+    .backpack_mut()
+    .get::<ThreatTables>()
+    .and_then(|tables| tables.get(enemy))
+    .and_then(|table| table.target(players.iter().copied()))
+}
+
+#[test]
+fn enemies_go_after_whoever_hurts_them_most() {
+  let mut sim = Simulation::new(1).with_system::<ThreatSystem>();
+  let enemy = enemy(&mut sim, Vector3::zeros());
+  let close = sim.spawn_player(Vector3::new(2.0, 0.0, 0.0));
+  let far = sim.spawn_player(Vector3::new(8.0, 0.0, 0.0));
+
+  // Standing closer is all it takes while nobody fights
+  sim.step_seconds(1.0);
+  assert_eq!(target_of(&mut sim, enemy, &[close, far]), Some(close));
+
+  hurt(&mut sim, far, enemy, 15.0);
+  sim.step(1);
+  assert_eq!(target_of(&mut sim, enemy, &[close, far]), Some(far));
+  assert_eq!(target_of(&mut sim, enemy, &[close]), Some(close));
+}
+
+#[test]
+fn threat_fades_away() {
+  let mut sim = Simulation::new(1).with_system::<ThreatSystem>();
+  let enemy = enemy(&mut sim, Vector3::zeros());
+  let player = sim.spawn_player(Vector3::new(50.0, 0.0, 0.0));
+
+  hurt(&mut sim, player, enemy, 10.0);
+  sim.step_seconds(1.0);
+  let threat = |sim: &mut Simulation| {
+    sim
+      .backpack_mut()
+      .get::<ThreatTables>()
+      .and_then(|tables| tables.get(enemy))
+      .map_or(0.0, |table| table.get(player))
+  };
+  let early = threat(&mut sim);
+  assert!(early > 8.0 && early < 10.0, "{}", early);
+
+  sim.step_seconds(90.0);
+  assert_eq!(threat(&mut sim), 0.0);
+  assert_eq!(target_of(&mut sim, enemy, &[player]), None);
+}
+
+#[test]
+fn enemies_leave_dead_players_alone() {
+  let mut sim = Simulation::new(1).with_system::<ThreatSystem>();
+  let enemy = enemy(&mut sim, Vector3::zeros());
+  let fallen = sim.spawn_player(Vector3::new(2.0, 0.0, 0.0));
+  let standing = sim.spawn_player(Vector3::new(8.0, 0.0, 0.0));
+
+  hurt(&mut sim, fallen, enemy, 50.0);
+  sim.step(1);
+  assert_eq!(target_of(&mut sim, enemy, &[fallen, standing]), Some(fallen));
+
+  sim.scene_mut().query_one_mut::<&mut RespawnComponent>(fallen).unwrap().die();
+  sim.step(1);
+  assert_eq!(target_of(&mut sim, enemy, &[fallen, standing]), Some(standing));
+}
+
+#[test]
+fn tanks_taunt_enemies_around_them() {
+  let mut sim = Simulation::new(1).with_system::<ThreatSystem>();
+  let enemy = enemy(&mut sim, Vector3::zeros());
+  let dealer = sim.spawn_player(Vector3::new(2.0, 0.0, 0.0));
+
+  let tank = wielding(&mut sim, Vector3::new(5.0, 0.0, 0.0), WeaponKind::Tank);
+
+  hurt(&mut sim, dealer, enemy, 50.0);
+  sim.step(1);
+  assert_eq!(target_of(&mut sim, enemy, &[dealer, tank]), Some(dealer));
+
+  let mut taunt = PlayerInput::default();
+  taunt.taunt = true;
+  let from = sim.tick();
+  sim.script(tank, from..from + 1, taunt);
+  sim.step(1);
+
+  // Locked on the tank no matter who hits harder, until the taunt wears off
+  hurt(&mut sim, dealer, enemy, 50.0);
+  sim.step_seconds(2.0);
+  assert_eq!(target_of(&mut sim, enemy, &[dealer, tank]), Some(tank));
+
+  sim.step_seconds(3.0);
+  assert_eq!(target_of(&mut sim, enemy, &[dealer, tank]), Some(dealer));
+}
+
+#[test]
+fn healing_whoever_an_enemy_fights_draws_its_attention() {
+  let mut sim = Simulation::new(1)
+    .with_system::<HealSystem>()
+    .with_system::<ThreatSystem>();
+  let enemy = enemy(&mut sim, Vector3::zeros());
+  let fighter = player_with(&mut sim, Vector3::new(6.0, 0.0, 0.0), 0);
+
+  // Too far for the enemy to notice, close enough to heal the fighter
+  let cleric = wielding(&mut sim, Vector3::new(11.0, 0.0, 0.0), WeaponKind::Cleric);
+
+  sim.scene_mut().query_one_mut::<&mut HealthComponent>(fighter).unwrap().health = 50.0;
+  sim.step(1);
+  assert_eq!(target_of(&mut sim, enemy, &[fighter, cleric]), Some(fighter));
+
+  let mut heal = PlayerInput::default();
+  heal.heal = true;
+  let from = sim.tick();
+  sim.script(cleric, from..from + 1, heal);
+  sim.step(1);
+
+  assert_eq!(sim.get::<HealthComponent>(fighter).unwrap().health, 70.0);
+  assert!(sim.events().contains(&GameEvent::Healed {
+    healer: cleric,
+    target: fighter,
+    amount: 20.0,
+  }));
+  assert_eq!(target_of(&mut sim, enemy, &[fighter, cleric]), Some(cleric));
+}
+
+fn current_action(planner: &Planner<SceneWorld>) -> Option<&'static str> {
+  planner
+    .current_plan()
+    .and_then(|plan| plan.current())
+    .and_then(|index| planner.action_name(index))
+}
+
+#[test]
+fn enemies_investigate_what_they_heard_until_they_forget_it() {
+  GoalRegistry::register();
+  let definition: PlannerDefinition = serde_json::from_value(serde_json::json!({
+    "id": "7c1b0e0a-5d4e-4f43-9a55-52d8f3c1a8e2",
+    "name": "Hunter",
+    "goals": [{"AggroCharacter": {}}],
+    "actions": [
+      {"Patrol": {}},
+      {"Investigate": {}},
+      {"Attack": {"max_distance": {"meters": 2}}}
+    ],
+    "sensors": [
+      {"SensePlayer": {"max_distance": {"meters": 30}, "memory": {"seconds": 5}}}
+    ]
+  }))
+  .unwrap();
+  let mut planner = definition.instantiate().unwrap();
+
+  let mut sim = Simulation::new(1);
+  let enemy = enemy(&mut sim, Vector3::zeros());
+  let player = player_with(&mut sim, Vector3::new(0.0, 0.0, -10.0), 0);
+
+  let mut backpack = Backpack::new();
+  backpack.insert(Time::new(TICK));
+  let mut local = Backpack::new();
+  let mut update = |sim: &mut Simulation, planner: &mut Planner<SceneWorld>, ticks: u32| {
+    for _ in 0..ticks {
+      planner.update(enemy, sim.scene_mut(), &mut backpack, &mut local);
+    }
+  };
+
+  // Standing quietly behind it goes unnoticed
+  update(&mut sim, &mut planner, 1);
+  assert_eq!(current_action(&planner), None);
+
+  // Chopping wood doesn't
+  sim.scene_mut().query_one_mut::<&mut InventoryComponent>(player).unwrap().chop_timer = 0.5;
+  update(&mut sim, &mut planner, 1);
+  assert_eq!(current_action(&planner), Some("Investigate"));
+
+  // Nothing to back the lead up once it's forgotten
+  sim.scene_mut().query_one_mut::<&mut InventoryComponent>(player).unwrap().chop_timer = 0.0;
+  update(&mut sim, &mut planner, (6.0 / TICK) as u32);
+  assert_eq!(current_action(&planner), None);
+
+  // Right in front of it, it goes straight for them
+  let transform = sim.scene_mut().query_one_mut::<&mut TransformComponent>(player).unwrap();
+  transform.translation = Vector3::new(0.0, 0.0, 10.0);
+  update(&mut sim, &mut planner, 1);
+  assert_eq!(current_action(&planner), Some("Patrol"));
+}
+
+fn saboteur() -> Planner<SceneWorld> {
+  GoalRegistry::register();
+  let definition: PlannerDefinition = serde_json::from_value(serde_json::json!({
+    "id": "5a2e8c7d-1b3f-4e6a-8d9c-0f1e2d3c4b5a",
+    "name": "Saboteur",
+    "goals": [{"SabotageFire": {"insistence": 1.5}}],
+    "actions": [
+      {"ApproachFire": {}},
+      {"DrainFire": {"drain_rate": 5}},
+      {"Flee": {"safe_distance": {"meters": 12}}}
+    ],
+    "sensors": [
+      {"SenseGuards": {"guard_radius": {"meters": 10}}},
+      {"SenseThreat": {"engage_distance": {"meters": 6}}},
+      {"SenseFire": {"max_distance": {"meters": 100}}}
+    ]
+  }))
+  .unwrap();
+  definition.instantiate().unwrap()
+}
+
+fn plan_of(planner: &Planner<SceneWorld>) -> Vec<&'static str> {
+  planner
+    .current_plan()
+    .map(|plan| plan.actions().iter().filter_map(|index| planner.action_name(*index)).collect())
+    .unwrap_or_default()
+}
+
+fn teleport(sim: &mut Simulation, entity: Entity, translation: Vector3<f32>) {
+  sim.scene_mut().query_one_mut::<&mut TransformComponent>(entity).unwrap().translation = translation;
+}
+
+#[test]
+fn saboteurs_care_less_about_guarded_fires() {
+  let mut sim = Simulation::new(1);
+  fire(&mut sim, FireComponent::default());
+  let enemy = enemy(&mut sim, Vector3::new(0.0, 0.0, 30.0));
+
+  let mut backpack = Backpack::new();
+  backpack.insert(Time::new(TICK));
+  let mut urgency =
+    |sim: &mut Simulation| saboteur().prepare(enemy, sim.scene_mut(), &mut backpack, &mut Backpack::new());
+
+  assert_eq!(urgency(&mut sim), Some(1.5));
+  sim.spawn_player(Vector3::new(5.0, 0.0, 0.0));
+  assert_eq!(urgency(&mut sim), Some(0.75));
+  sim.spawn_player(Vector3::new(-5.0, 0.0, 0.0));
+  assert_eq!(urgency(&mut sim), Some(0.5));
+
+  // Out of the guard radius, and dead players don't guard anything
+  sim.spawn_player(Vector3::new(0.0, 0.0, -20.0));
+  let fallen = sim.spawn_player(Vector3::new(0.0, 0.0, 5.0));
+  sim.scene_mut().query_one_mut::<&mut RespawnComponent>(fallen).unwrap().die();
+  assert_eq!(urgency(&mut sim), Some(0.5));
+}
+
+#[test]
+fn saboteurs_shake_players_off_before_draining_the_fire() {
+  let mut sim = Simulation::new(1);
+  let mut component = FireComponent::default();
+  component.fuel = 1.0;
+  let fire = fire(&mut sim, component);
+  let enemy = enemy(&mut sim, Vector3::new(0.0, 0.0, 30.0));
+  let player = sim.spawn_player(Vector3::new(0.0, 0.0, 33.0));
+
+  let mut planner = saboteur();
+  let mut backpack = Backpack::new();
+  backpack.insert(Time::new(TICK));
+  let mut local = Backpack::new();
+  let mut update = |sim: &mut Simulation, planner: &mut Planner<SceneWorld>, ticks: u32| {
+    for _ in 0..ticks {
+      planner.update(enemy, sim.scene_mut(), &mut backpack, &mut local);
+    }
+  };
+
+  update(&mut sim, &mut planner, 1);
+  assert_eq!(plan_of(&planner), vec!["Flee", "ApproachFire", "DrainFire"]);
+  assert_eq!(current_action(&planner), Some("Flee"));
+
+  // Nobody around anymore, off to the fire
+  teleport(&mut sim, player, Vector3::new(0.0, 0.0, 80.0));
+  update(&mut sim, &mut planner, 1);
+  assert_eq!(current_action(&planner), Some("ApproachFire"));
+
+  teleport(&mut sim, enemy, Vector3::new(0.0, 0.0, 2.0));
+  update(&mut sim, &mut planner, 1);
+  assert_eq!(current_action(&planner), Some("DrainFire"));
+
+  update(&mut sim, &mut planner, (1.0 / TICK) as u32);
+  assert_eq!(sim.get::<FireComponent>(fire).unwrap().fuel, 0.0);
+}
+
+#[test]
+fn saboteurs_flee_as_soon_as_someone_engages_them() {
+  let mut sim = Simulation::new(1);
+  let fire = fire(&mut sim, FireComponent::default());
+  let enemy = enemy(&mut sim, Vector3::new(0.0, 0.0, 2.0));
+  let player = sim.spawn_player(Vector3::new(0.0, 0.0, 40.0));
+
+  let mut planner = saboteur();
+  let mut backpack = Backpack::new();
+  backpack.insert(Time::new(TICK));
+  let mut local = Backpack::new();
+  let mut update = |sim: &mut Simulation, planner: &mut Planner<SceneWorld>| {
+    planner.update(enemy, sim.scene_mut(), &mut backpack, &mut local);
+  };
+
+  update(&mut sim, &mut planner);
+  update(&mut sim, &mut planner);
+  assert_eq!(current_action(&planner), Some("DrainFire"));
+  let fuel = sim.get::<FireComponent>(fire).unwrap().fuel;
+  assert!(fuel < 100.0);
+
+  // The plan gets thrown away within the tick, nothing more gets drained
+  teleport(&mut sim, player, Vector3::new(0.0, 0.0, 5.0));
+  update(&mut sim, &mut planner);
+  assert_eq!(current_action(&planner), Some("Flee"));
+  assert_eq!(sim.get::<FireComponent>(fire).unwrap().fuel, fuel);
+}
+
+fn health(health: f32) -> HealthComponent {
+  HealthComponent {
+    health,
+    max_health: 100.0,
+    pending_damage: 0.0,
+  }
+}
+
+fn no_shield() -> ShieldComponent {
+  ShieldComponent {
+    shield: 0.0,
+    max_shield: 0.0,
+    shield_regen_per_second: 0.0,
+    current_undamaged_duration: 5.0,
+    time_last_damage: 0.0,
+  }
+}
+
+fn fighter(sim: &mut Simulation, translation: Vector3<f32>) -> Entity {
+  let attack: AttackComponent = serde_json::from_value(serde_json::json!({
+    "cooldown": 0.0,
+    "heavy_timer_max": 2.0,
+    "damage": 5.0,
+    "max_distance": 3.0,
+    "light_anim_start_time": 600.0,
+    "light_anim_end_time": 1800.0,
+    "heavy_anim_start_time": 1200.0,
+    "heavy_anim_end_time": 2200.0
+  }))
+  .unwrap();
+
+  let mut prefab = sim.player(translation);
+  prefab.components.push(Box::new(health(100.0)));
+  prefab.components.push(Box::new(no_shield()));
+  prefab.components.push(Box::new(attack));
+  sim.create(prefab)
+}
+
+fn dummy(sim: &mut Simulation, translation: Vector3<f32>, hit_points: f32) -> Entity {
+  let mut prefab = sim.body("Swampeter", translation);
+  prefab.components.push(Box::new(health(hit_points)));
+  prefab.components.push(Box::new(no_shield()));
+  prefab.components.push(Box::new(EnemyAiComponent::new()));
+  sim.create(prefab)
+}
+
+#[test]
+fn light_attacks_hurt_whoever_stands_in_front() {
+  let mut sim = Simulation::new(1)
+    .with_level("arena.lvl")
+    .with_physics()
+    .with_system::<CombatSystem>();
+  // High above the level, so that nothing in it gets in the way
+  let attacker = fighter(&mut sim, Vector3::new(0.0, 100.0, 0.0));
+  let target = dummy(&mut sim, Vector3::new(0.0, 100.0, 1.5), 100.0);
+  let behind = dummy(&mut sim, Vector3::new(0.0, 100.0, -1.5), 100.0);
+
+  let mut light = PlayerInput::default();
+  light.light_attack = true;
+  sim.script(attacker, 0..45, light);
+  sim.step(45);
+
+  assert!(sim.get::<HealthComponent>(target).unwrap().health < 100.0);
+  assert_eq!(sim.get::<HealthComponent>(behind).unwrap().health, 100.0);
+  assert_eq!(sim.get::<HealthComponent>(attacker).unwrap().health, 100.0);
+  assert!(sim.events().iter().any(|event| match event {
+    GameEvent::DamageDealt {
+      attacker: from,
+      target: to,
+      ..
+    } => *from == attacker && *to == target,
+    _ => false,
+  }));
+}
+
+#[test]
+fn dead_players_cant_swing() {
+  let mut sim = Simulation::new(1)
+    .with_level("arena.lvl")
+    .with_physics()
+    .with_system::<CombatSystem>();
+  let attacker = fighter(&mut sim, Vector3::new(0.0, 100.0, 0.0));
+  let target = dummy(&mut sim, Vector3::new(0.0, 100.0, 1.5), 100.0);
+  sim.scene_mut().query_one_mut::<&mut RespawnComponent>(attacker).unwrap().die();
+
+  let mut light = PlayerInput::default();
+  light.light_attack = true;
+  sim.script(attacker, 0..45, light);
+  sim.step(45);
+
+  assert_eq!(sim.get::<HealthComponent>(target).unwrap().health, 100.0);
+  assert!(!sim.get::<AttackComponent>(attacker).unwrap().anim_running);
+}
+
+fn kill(sim: &mut Simulation, player: Entity) {
+  sim.scene_mut().query_one_mut::<&mut RespawnComponent>(player).unwrap().die();
+}
+
+#[test]
+fn dead_players_cant_chop() {
+  let mut sim = Simulation::new(1).with_system::<HarvestSystem>();
+  let tree = tree(&mut sim, Vector3::zeros());
+  let player = player_with(&mut sim, Vector3::new(1.0, 0.0, 0.0), 0);
+  kill(&mut sim, player);
+
+  let mut chop = PlayerInput::default();
+  chop.chop = true;
+  sim.script(player, 0..180, chop);
+  sim.step(180);
+
+  assert_eq!(sim.get::<ResourceComponent>(tree).unwrap().available, 5);
+  assert_eq!(sim.get::<InventoryComponent>(player).unwrap().chop_timer, 0.0);
+  assert!(sim.tagged("Wood").is_empty());
+}
+
+#[test]
+fn dead_players_cant_feed_the_fire() {
+  let mut sim = Simulation::new(1)
+    .with_system::<FireSystem>()
+    .with_system::<DepositSystem>();
+  let mut component = FireComponent::default();
+  component.fuel = 50.0;
+  component.burn_rate = 0.0;
+  component.auto_deposit = true;
+  let fire = fire(&mut sim, component);
+  let player = player_with(&mut sim, Vector3::new(1.0, 0.0, 0.0), 3);
+  kill(&mut sim, player);
+
+  sim.step_seconds(5.0);
+
+  assert_eq!(sim.get::<InventoryComponent>(player).unwrap().get_wood(), 3);
+  assert_eq!(sim.get::<FireComponent>(fire).unwrap().fuel, 50.0);
+}
+
+#[test]
+fn dead_players_dont_freeze() {
+  let mut sim = Simulation::new(1).with_system::<WarmthSystem>();
+  fire(&mut sim, FireComponent::default());
+  let player = player_with(&mut sim, Vector3::new(50.0, 0.0, 0.0), 0);
+  let warmth = sim.get::<WarmthComponent>(player).unwrap().warmth;
+  kill(&mut sim, player);
+
+  sim.step_seconds(60.0);
+
+  assert_eq!(sim.get::<WarmthComponent>(player).unwrap().warmth, warmth);
+  assert_eq!(sim.get::<HealthComponent>(player).unwrap().pending_damage, 0.0);
+}
+
+fn wood(sim: &mut Simulation, translation: Vector3<f32>) -> Entity {
+  let mut prefab = sim.scene_mut().get_prefab("Wood").cloned().unwrap();
+  prefab.transform.translation = translation;
+  sim.own_bodies(&mut prefab);
+  sim.create(prefab)
+}
+
+#[test]
+fn dead_players_dont_pick_up_wood() {
+  let mut sim = Simulation::new(1)
+    .with_level("arena.lvl")
+    .with_physics()
+    .with_system::<CollisionSystem>();
+  let alive = player_with(&mut sim, Vector3::new(0.0, 100.0, 0.0), 0);
+  let dead = player_with(&mut sim, Vector3::new(20.0, 100.0, 0.0), 0);
+  kill(&mut sim, dead);
+  wood(&mut sim, Vector3::new(0.0, 100.0, 0.0));
+  let left = wood(&mut sim, Vector3::new(20.0, 100.0, 0.0));
+
+  sim.step(10);
+
+  assert_eq!(sim.get::<InventoryComponent>(alive).unwrap().get_wood(), 1);
+  assert_eq!(sim.get::<InventoryComponent>(dead).unwrap().get_wood(), 0);
+  assert!(sim.get::<TagComponent>(left).is_some());
+}
+
+fn spawn_points(sim: &mut Simulation, translations: &[Vector3<f32>]) {
+  let mut spawn_points = SpawnPoints::new();
+  for translation in translations {
+    spawn_points.insert(sim.prefab("SpawnPoint", *translation).transform);
+  }
+  sim.backpack_mut().insert(spawn_points);
+}
+
+fn joined(sim: &mut Simulation) -> Entity {
+  let mut prefab = sim.player(Vector3::zeros());
+  prefab.components.push(Box::new(health(100.0)));
+  prefab.components.push(Box::new(ShieldComponent {
+    shield: 50.0,
+    max_shield: 50.0,
+    shield_regen_per_second: 0.0,
+    current_undamaged_duration: 5.0,
+    time_last_damage: 0.0,
+  }));
+  prefab.components.push(Box::new(InventoryComponent::default()));
+  sim.join(prefab)
+}
+
+fn respawning() -> Simulation {
+  Simulation::new(1)
+    .with_level("arena.lvl")
+    .with_physics()
+    .with_system::<RespawnSystem>()
+}
+
+#[test]
+fn the_respawn_delay_grows_with_every_death() {
+  let mut sim = respawning();
+  spawn_points(&mut sim, &[Vector3::new(0.0, 100.0, 0.0)]);
+  let player = joined(&mut sim);
+
+  let mut delays = vec![];
+  for _ in 0..3 {
+    sim.scene_mut().query_one_mut::<&mut HealthComponent>(player).unwrap().health = 0.0;
+    sim.step(1);
+
+    let respawn = sim.get::<RespawnComponent>(player).unwrap();
+    assert!(!respawn.is_alive());
+    delays.push(*respawn.respawn_in);
+
+    sim.step_seconds(*respawn.respawn_in + 0.1);
+    assert!(sim.get::<RespawnComponent>(player).unwrap().is_alive());
+  }
+
+  assert_eq!(delays, vec![3.0, 5.0, 7.0]);
+  assert_eq!(sim.get::<RespawnComponent>(player).unwrap().deaths, 3);
+}
+
+#[test]
+fn players_respawn_at_their_spawn_point_good_as_new() {
+  let mut sim = respawning();
+  let home = Vector3::new(0.0, 100.0, 0.0);
+  spawn_points(&mut sim, &[home, Vector3::new(40.0, 100.0, 0.0)]);
+  let player = joined(&mut sim);
+  let id = sim.get::<IdComponent>(player).unwrap();
+
+  teleport(&mut sim, player, Vector3::new(20.0, 100.0, 20.0));
+  sim.scene_mut().query_one_mut::<&mut InventoryComponent>(player).unwrap().add_wood(3);
+  sim.scene_mut().query_one_mut::<&mut ShieldComponent>(player).unwrap().shield = 0.0;
+  sim.scene_mut().query_one_mut::<&mut HealthComponent>(player).unwrap().health = 0.0;
+
+  sim.step(1);
+  sim.step_seconds(3.5);
+
+  // Same entity, so nothing keyed by it loses track of the player
+  assert_eq!(*sim.get::<IdComponent>(player).unwrap(), *id);
+  assert!(sim.despawned().is_empty());
+  assert!(sim.get::<RespawnComponent>(player).unwrap().is_alive());
+  assert_eq!(sim.get::<HealthComponent>(player).unwrap().health, 100.0);
+  assert_eq!(sim.get::<ShieldComponent>(player).unwrap().shield, 50.0);
+  assert_eq!(sim.get::<InventoryComponent>(player).unwrap().get_wood(), 0);
+
+  let translation = sim.translation(player).unwrap();
+  assert!((translation.xz() - home.xz()).norm() < 1.0, "{:?}", translation);
+
+  let events = sim
+    .events()
+    .iter()
+    .filter(|event| match event {
+      GameEvent::PlayerDied { player: dead } | GameEvent::PlayerRespawned { player: dead } => *dead == player,
+      _ => false,
+    })
+    .cloned()
+    .collect::<Vec<_>>();
+  assert_eq!(
+    events,
+    vec![GameEvent::PlayerDied { player }, GameEvent::PlayerRespawned { player }]
+  );
+}
+
+#[test]
+fn players_respawn_at_the_safest_spawn_point_when_theirs_is_overrun() {
+  let mut sim = respawning();
+  let safe = Vector3::new(40.0, 100.0, 0.0);
+  spawn_points(&mut sim, &[Vector3::new(0.0, 100.0, 0.0), safe]);
+  let player = joined(&mut sim);
+  dummy(&mut sim, Vector3::new(2.0, 100.0, 0.0), 100.0);
+
+  sim.scene_mut().query_one_mut::<&mut HealthComponent>(player).unwrap().health = 0.0;
+  sim.step(1);
+  sim.step_seconds(3.5);
+
+  let translation = sim.translation(player).unwrap();
+  assert!((translation.xz() - safe.xz()).norm() < 1.0, "{:?}", translation);
+}
+
+fn match_with(sim: &mut Simulation, rules: MatchRules) {
+  sim.backpack_mut().get_mut::<MatchState>().unwrap().rules = rules;
+}
+
+fn phases(sim: &Simulation) -> Vec<MatchPhase> {
+  sim
+    .events()
+    .iter()
+    .filter_map(|event| match event {
+      GameEvent::MatchPhaseChanged { phase } => Some(*phase),
+      _ => None,
+    })
+    .collect()
+}
+
+/// A fire that's part of the level, so that resets bring it back.
+fn level_fire(sim: &mut Simulation, fuel: f32) {
+  let mut component = FireComponent::default();
+  component.fuel = fuel;
+  component.burn_rate = 1.0;
+  let mut prefab = sim.prefab("Fire", Vector3::zeros());
+  prefab.components.push(Box::new(component));
+
+  let mut snapshot = LevelSnapshot::new();
+  snapshot.insert_entity(prefab.clone());
+  sim.backpack_mut().insert(snapshot);
+  sim.create(prefab);
+}
+
+#[test]
+fn lost_matches_go_back_to_a_fresh_level() {
+  let mut sim = Simulation::new(1)
+    .with_level("arena.lvl")
+    .with_physics()
+    .with_system::<FireSystem>()
+    .with_system::<MatchSystem>();
+  match_with(&mut sim, MatchRules {
+    min_players: 1,
+    countdown: Seconds::new(1.0),
+    time_limit: Seconds::new(60.0),
+    results_duration: Seconds::new(2.0),
+  });
+  level_fire(&mut sim, 2.0);
+  let home = Vector3::new(0.0, 100.0, 10.0);
+  spawn_points(&mut sim, &[home]);
+  let player = joined(&mut sim);
+
+  sim.step_seconds(1.5);
+  assert_eq!(phases(&sim), vec![MatchPhase::Countdown, MatchPhase::Active]);
+
+  // Leave a mark on the level during the round
+  teleport(&mut sim, player, Vector3::new(30.0, 100.0, 30.0));
+  sim.scene_mut().query_one_mut::<&mut InventoryComponent>(player).unwrap().add_wood(2);
+  let leftover = wood(&mut sim, Vector3::new(-30.0, 100.0, 0.0));
+
+  sim.step_seconds(2.5);
+  assert_eq!(phases(&sim).last(), Some(&MatchPhase::Lost));
+  assert!(sim.all::<FireComponent>().iter().all(|(_, fire)| fire.is_out()));
+
+  sim.step_seconds(1.5);
+  assert_eq!(
+    phases(&sim)[..4],
+    [MatchPhase::Countdown, MatchPhase::Active, MatchPhase::Lost, MatchPhase::Lobby]
+  );
+
+  let fires = sim.all::<FireComponent>();
+  assert_eq!(fires.len(), 1);
+  assert_eq!(fires[0].1.fuel, 2.0);
+  assert!(sim.get::<TagComponent>(leftover).is_none());
+  assert_eq!(sim.get::<InventoryComponent>(player).unwrap().get_wood(), 0);
+  let translation = sim.translation(player).unwrap();
+  assert!((translation.xz() - home.xz()).norm() < 1.0, "{:?}", translation);
+
+  // Someone's still there, so the next round starts right away
+  assert_eq!(phases(&sim)[4..], [MatchPhase::Countdown]);
+}
+
+#[test]
+fn outlasting_the_time_limit_wins_the_match() {
+  let mut sim = Simulation::new(1)
+    .with_physics()
+    .with_system::<FireSystem>()
+    .with_system::<MatchSystem>();
+  match_with(&mut sim, MatchRules {
+    min_players: 1,
+    countdown: Seconds::new(1.0),
+    time_limit: Seconds::new(2.0),
+    results_duration: Seconds::new(1.0),
+  });
+  level_fire(&mut sim, 100.0);
+  joined(&mut sim);
+
+  sim.step_seconds(4.5);
+
+  assert_eq!(
+    phases(&sim)[..4],
+    [MatchPhase::Countdown, MatchPhase::Active, MatchPhase::Won, MatchPhase::Lobby]
+  );
+}
+
+#[test]
+fn countdowns_go_back_to_the_lobby_without_enough_players() {
+  let mut sim = Simulation::new(1)
+    .with_physics()
+    .with_system::<MatchSystem>();
+  match_with(&mut sim, MatchRules {
+    min_players: 2,
+    countdown: Seconds::new(1.0),
+    time_limit: Seconds::new(60.0),
+    results_duration: Seconds::new(1.0),
+  });
+
+  let first = joined(&mut sim);
+  sim.step(10);
+  assert!(phases(&sim).is_empty());
+
+  joined(&mut sim);
+  sim.step(1);
+  assert_eq!(phases(&sim), vec![MatchPhase::Countdown]);
+
+  let id = sim.get::<IdComponent>(first).unwrap();
+  sim
+    .backpack_mut()
+    .get_mut::<ConnectedPlayers>()
+    .unwrap()
+    .remove(&PlayerId::from(***id));
+  sim.step(1);
+  assert_eq!(phases(&sim), vec![MatchPhase::Countdown, MatchPhase::Lobby]);
+}
+
+#[test]
+fn dead_enemies_drop_a_dreamstone() {
+  let mut sim = Simulation::new(1)
+    .with_level("arena.lvl")
+    .with_physics()
+    .with_system::<DeathSystem>();
+  let dreamstone = sim.prefab("Dreamstone", Vector3::zeros());
+  sim.scene_mut().store_prefab("Dreamstone", dreamstone);
+
+  let alive = dummy(&mut sim, Vector3::new(0.0, 100.0, 0.0), 100.0);
+  let dead = dummy(&mut sim, Vector3::new(5.0, 100.0, 0.0), 0.0);
+  let dead_id = sim.get::<IdComponent>(dead).unwrap();
+  // Players go through the respawn flow instead
+  let player = fighter(&mut sim, Vector3::new(10.0, 100.0, 0.0));
+  sim.scene_mut().query_one_mut::<&mut HealthComponent>(player).unwrap().health = 0.0;
+
+  sim.step(1);
+
+  assert_eq!(sim.tagged("Swampeter"), vec![alive]);
+  assert_eq!(sim.tagged("Dreamstone").len(), 1);
+  assert!(sim.get::<HealthComponent>(player).is_some());
+  // Clients drop it too
+  assert_eq!(sim.despawned(), &[*dead_id]);
+}
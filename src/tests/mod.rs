@@ -0,0 +1,2 @@
+mod gameplay;
+mod simulation;
@@ -0,0 +1,333 @@
+use crate::server::{level::LevelSnapshot, replication::ConnectedPlayers, spawn_points::SpawnPoints};
+use crate::shared::{
+  components::{
+    respawn_component::RespawnComponent, warmth_component::WarmthComponent,
+    weapon_component::WeaponComponent,
+  },
+  events::{GameEvent, GameEvents},
+  input::PlayerInput,
+  player_inputs::PlayerInputs,
+  replication::ReplicationQueue,
+  rng::GameRng,
+  systems::sky::SkySystem,
+};
+use crate::shared::systems::goal::{PlannerDefinition, PlannerDefinitions};
+
+use engine::{
+  application::{
+    components::PhysicsComponent,
+    gamefile::Gamefile,
+    scene::{IdComponent, Prefab, PrefabId, Scene, TagComponent, TransformComponent},
+  },
+  networking::connection::PlayerId,
+  systems::{physics::PhysicsSystem, Backpack, Initializable, Inventory, System},
+  utils::units::Time,
+  Entity,
+};
+use nalgebra::Vector3;
+use rand::Rng;
+use std::ops::Range;
+use uuid::{Builder, Uuid};
+
+/// Length of a tick, the server runs at 60 frames per second.
+pub const TICK: f32 = 1.0 / 60.0;
+
+struct ScriptedInput {
+  player: IdComponent,
+  ticks: Range<u64>,
+  input: PlayerInput,
+}
+
+/// Runs the gameplay systems without a renderer or a network, one fixed tick
+/// at a time.
+///
+/// Randomness comes from a seeded `GameRng` and player inputs come from a
+/// script, so the same simulation always ends up in the same state. Systems
+/// that need a `PhysicsController` can be attached after `with_physics`.
+pub struct Simulation {
+  scene: Scene,
+  backpack: Backpack,
+  inventory: Inventory,
+  systems: Vec<Box<dyn System>>,
+  script: Vec<ScriptedInput>,
+  events: Vec<GameEvent>,
+  despawned: Vec<PrefabId>,
+  tick: u64,
+}
+
+impl Simulation {
+  pub fn new(seed: u64) -> Self {
+    let mut backpack = Backpack::new();
+    backpack.insert(GameRng::from_seed(seed));
+    backpack.insert(PlayerInputs::new());
+    backpack.insert(Time::new(TICK));
+
+    Self {
+      scene: Scene::new(),
+      backpack,
+      inventory: Inventory::new(),
+      systems: vec![],
+      script: vec![],
+      events: vec![],
+      despawned: vec![],
+      tick: 0,
+    }
+  }
+
+  /// Loads a level from `resources`, the same way the `NetworkController`
+  /// does, minus the assets only the renderer cares about.
+  pub fn with_level(mut self, name: &str) -> Self {
+    let path = format!("{}/resources/{}", env!("CARGO_MANIFEST_DIR"), name);
+    let contents = std::fs::read_to_string(&path).unwrap();
+    let gamefile: Gamefile = serde_json::from_str(&contents).unwrap();
+
+    let mut planners = PlannerDefinitions::new();
+    for (_, asset) in gamefile.scene.planners {
+      planners.insert(PlannerDefinition::from_asset(&asset).unwrap());
+    }
+    self.backpack.insert(planners);
+
+    for (_, prefab) in gamefile.scene.prefabs {
+      match prefab.tag.name.as_str() {
+        "DamageParticle" | "Spell" | "Wizard" => {
+          self.scene.store_prefab(&prefab.tag.name.clone(), prefab);
+        }
+        "Flame Monster" => {
+          self.scene.store_prefab("Swampeter", prefab);
+        }
+        "EnemySpawn1" | "EnemySpawn2" | "EnemySpawn3" | "EnemySpawn4" => {}
+        "Wood" => {
+          self.scene.store_prefab("Wood", prefab.clone());
+          self.create(prefab);
+        }
+        _ => {
+          self.create(prefab);
+        }
+      }
+    }
+    SkySystem::spawn_clock(&mut self.scene);
+
+    self
+  }
+
+  /// Steps a headless physics world before every other system, and provides
+  /// its `PhysicsController` and `CollisionsReader` to the systems attached
+  /// after it, the way the `HdrPipeline` does on the server.
+  pub fn with_physics(self) -> Self {
+    self.with_system::<PhysicsSystem>()
+  }
+
+  pub fn with_system<S: Initializable + System + 'static>(mut self) -> Self {
+    let mut system = S::initialize(&self.inventory);
+    system.provide(&self.inventory);
+    system.attach(&mut self.scene, &mut self.backpack);
+    self.systems.push(Box::new(system));
+    self
+  }
+
+  /// An empty prefab to build entities from, components get pushed onto it.
+  pub fn prefab(&mut self, name: &str, translation: Vector3<f32>) -> Prefab {
+    let entity = self.scene.create_raw_entity(name);
+    let mut prefab = Prefab::pack(&self.scene, entity).unwrap();
+    let _ = self.scene.despawn(entity);
+
+    prefab.id = self.next_id();
+    prefab.transform.translation = translation;
+    prefab
+  }
+
+  /// An empty prefab with the rigid body and collider of the level's
+  /// "Wizard", for anything that has to take up room in the physics world.
+  pub fn body(&mut self, name: &str, translation: Vector3<f32>) -> Prefab {
+    let mut prefab = self.prefab(name, translation);
+    if let Some(mut wizard) = self.scene.get_prefab("Wizard").cloned() {
+      for component in wizard.components.iter_mut() {
+        if let Some(physics) = component.as_any_mut().downcast_mut::<PhysicsComponent>() {
+          prefab.components.push(Box::new(physics.clone()));
+        }
+      }
+    }
+
+    self.own_bodies(&mut prefab);
+    prefab
+  }
+
+  pub fn create(&mut self, prefab: Prefab) -> Entity {
+    let entity = self.scene.create_raw_entity(prefab.tag.name.as_str());
+    self.scene.create_with_prefab(entity, prefab);
+    entity
+  }
+
+  /// A player prefab, set up the way the `NetworkController` does when
+  /// someone joins. Comes from the level's "Wizard" when there's one.
+  pub fn player(&mut self, translation: Vector3<f32>) -> Prefab {
+    let mut prefab = match self.scene.get_prefab("Wizard").cloned() {
+      Some(mut prefab) => {
+        prefab.id = self.next_id();
+        prefab.transform.translation = translation;
+        self.own_bodies(&mut prefab);
+        prefab
+      }
+      None => self.prefab("Wizard", translation),
+    };
+
+    prefab.components.push(Box::new(WarmthComponent::default()));
+    prefab.components.push(Box::new(RespawnComponent::default()));
+    prefab.components.push(Box::new(WeaponComponent::default()));
+    prefab
+  }
+
+  pub fn spawn_player(&mut self, translation: Vector3<f32>) -> Entity {
+    let prefab = self.player(translation);
+    self.create(prefab)
+  }
+
+  /// Has someone join with `prefab` the way the `NetworkController` lets
+  /// them in: at the spawn point they get assigned, if there are any, kept
+  /// in the `LevelSnapshot` and counted as connected.
+  pub fn join(&mut self, mut prefab: Prefab) -> Entity {
+    let player_id = PlayerId::from(***prefab.id);
+
+    let spawn = self
+      .backpack
+      .get_mut::<SpawnPoints>()
+      .and_then(|spawn_points| spawn_points.assign(player_id));
+    if let Some(spawn) = spawn {
+      prefab.transform.translation = spawn.translation;
+      prefab.transform.rotation = spawn.rotation;
+    }
+
+    if self.backpack.get::<LevelSnapshot>().is_none() {
+      self.backpack.insert(LevelSnapshot::new());
+    }
+    self.backpack.get_mut::<LevelSnapshot>().unwrap().insert_player(player_id, prefab.clone());
+
+    if self.backpack.get::<ConnectedPlayers>().is_none() {
+      self.backpack.insert(ConnectedPlayers::new());
+    }
+    self.backpack.get_mut::<ConnectedPlayers>().unwrap().insert(player_id);
+
+    self.create(prefab)
+  }
+
+  /// Has the player hold `input` for every tick in the range.
+  pub fn script(&mut self, player: Entity, ticks: Range<u64>, input: PlayerInput) {
+    let player = self.get::<IdComponent>(player).unwrap();
+    self.script.push(ScriptedInput {
+      player,
+      ticks,
+      input,
+    });
+  }
+
+  pub fn step(&mut self, ticks: u64) {
+    for _ in 0..ticks {
+      self.backpack.insert(Time::new(TICK));
+
+      let mut inputs = PlayerInputs::new();
+      for scripted in &self.script {
+        if scripted.ticks.contains(&self.tick) {
+          inputs.insert_for(&scripted.player, scripted.input.clone());
+        }
+      }
+      self.backpack.insert(inputs);
+
+      for system in self.systems.iter_mut() {
+        system.run(&mut self.scene, &mut self.backpack);
+      }
+
+      // What the server's `ReplicationSystem` does at the end of a frame
+      if let Some(events) = self.backpack.get_mut::<GameEvents>() {
+        self.events.extend(events.iter().cloned());
+        events.clear();
+      }
+      if let Some(queue) = self.backpack.get_mut::<ReplicationQueue>() {
+        queue.drain().for_each(drop);
+        self.despawned.extend(queue.drain_despawned());
+      }
+
+      self.tick += 1;
+    }
+  }
+
+  pub fn step_seconds(&mut self, seconds: f32) {
+    self.step((seconds / TICK).round() as u64);
+  }
+
+  pub fn tick(&self) -> u64 {
+    self.tick
+  }
+
+  /// Every event raised since the simulation started, in order.
+  pub fn events(&self) -> &[GameEvent] {
+    &self.events
+  }
+
+  /// Ids of every entity despawned for the clients since the simulation
+  /// started, in order.
+  pub fn despawned(&self) -> &[PrefabId] {
+    &self.despawned
+  }
+
+  pub fn get<T: Clone + Send + Sync + 'static>(&mut self, entity: Entity) -> Option<T> {
+    self.scene.query_one_mut::<&T>(entity).ok().cloned()
+  }
+
+  pub fn all<T: Clone + Send + Sync + 'static>(&mut self) -> Vec<(Entity, T)> {
+    self
+      .scene
+      .query_mut::<&T>()
+      .into_iter()
+      .map(|(entity, component)| (entity, component.clone()))
+      .collect()
+  }
+
+  pub fn count<T: Send + Sync + 'static>(&mut self) -> usize {
+    self.scene.query_mut::<&T>().into_iter().count()
+  }
+
+  pub fn tagged(&mut self, name: &str) -> Vec<Entity> {
+    self
+      .scene
+      .query_mut::<&TagComponent>()
+      .into_iter()
+      .filter(|(_, tag)| tag.name == name)
+      .map(|(entity, _)| entity)
+      .collect()
+  }
+
+  pub fn translation(&mut self, entity: Entity) -> Option<Vector3<f32>> {
+    self
+      .get::<TransformComponent>(entity)
+      .map(|transform| transform.translation)
+  }
+
+  pub fn scene_mut(&mut self) -> &mut Scene {
+    &mut self.scene
+  }
+
+  pub fn backpack_mut(&mut self) -> &mut Backpack {
+    &mut self.backpack
+  }
+
+  /// Gives a cloned prefab rigid bodies of its own, the way the `DeathSystem`
+  /// does, so it doesn't share them with the original in the physics world.
+  pub fn own_bodies(&mut self, prefab: &mut Prefab) {
+    for component in prefab.components.iter_mut() {
+      if let Some(physics) = component.as_any_mut().downcast_mut::<PhysicsComponent>() {
+        physics.joint.id = self.next_uuid();
+        physics.joint.body.id = self.next_uuid();
+      }
+    }
+  }
+
+  /// Ids come out of the seeded generator too, so they're stable between runs.
+  fn next_id(&mut self) -> IdComponent {
+    IdComponent::with_id(PrefabId::with_id(self.next_uuid()))
+  }
+
+  fn next_uuid(&mut self) -> Uuid {
+    let bytes = GameRng::get(&mut self.backpack).gen::<[u8; 16]>();
+    Builder::from_random_bytes(bytes).into_uuid()
+  }
+}
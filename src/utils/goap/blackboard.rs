@@ -0,0 +1,185 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
+pub enum Value {
+  Bool(bool),
+  Number(u32),
+  Str(String),
+}
+
+impl From<bool> for Value {
+  fn from(data: bool) -> Self {
+    Value::Bool(data)
+  }
+}
+
+impl From<u32> for Value {
+  fn from(data: u32) -> Self {
+    Value::Number(data)
+  }
+}
+
+impl From<&str> for Value {
+  fn from(data: &str) -> Self {
+    Value::Str(String::from(data))
+  }
+}
+
+/// The world state the planner searches over. Keys are kept in order, so
+/// two blackboards holding the same entries are equal and hash the same.
+#[derive(Debug, Default, Hash, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Blackboard {
+  map: BTreeMap<String, Value>,
+}
+
+impl Blackboard {
+  pub fn new() -> Self {
+    Self {
+      map: BTreeMap::new(),
+    }
+  }
+
+  pub fn insert(&mut self, key: &str, data: impl Into<Value>) {
+    self.map.insert(String::from(key), data.into());
+  }
+
+  pub fn get(&self, key: &str) -> Option<&Value> {
+    self.map.get(key)
+  }
+
+  pub fn insert_bool(&mut self, key: &str, data: bool) {
+    self.map.insert(String::from(key), Value::Bool(data));
+  }
+
+  pub fn insert_number(&mut self, key: &str, data: u32) {
+    self.map.insert(String::from(key), Value::Number(data));
+  }
+
+  pub fn insert_str(&mut self, key: &str, data: &str) {
+    self.map.insert(String::from(key), Value::Str(String::from(data)));
+  }
+
+  pub fn get_bool(&self, key: &str) -> Option<&bool> {
+    match self.map.get(key) {
+      Some(Value::Bool(v)) => Some(v),
+      _ => None,
+    }
+  }
+
+  pub fn get_number(&self, key: &str) -> Option<&u32> {
+    match self.map.get(key) {
+      Some(Value::Number(v)) => Some(v),
+      _ => None,
+    }
+  }
+
+  pub fn get_str(&self, key: &str) -> Option<&String> {
+    match self.map.get(key) {
+      Some(Value::Str(v)) => Some(v),
+      _ => None,
+    }
+  }
+
+  pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+    self.map.get_mut(key)
+  }
+
+  pub fn take(&mut self, key: &str) -> Option<Value> {
+    self.map.remove(key)
+  }
+
+  pub fn contains(&self, key: &str) -> bool {
+    self.map.contains_key(key)
+  }
+
+  pub fn keys(&self) -> impl Iterator<Item = &String> {
+    self.map.keys()
+  }
+
+  /// Copies every entry of `other` over this one.
+  pub fn extend(&mut self, other: &Blackboard) {
+    for (key, value) in &other.map {
+      self.map.insert(key.clone(), value.clone());
+    }
+  }
+
+  /// Applies the changes in order. Missing numbers count as zero and never
+  /// go below it.
+  pub fn apply(&mut self, effects: &Effects) {
+    for (key, change) in effects.changes() {
+      let current = self.get_number(key).copied().unwrap_or(0);
+
+      match change {
+        Change::Set(value) => {
+          self.map.insert(key.clone(), value.clone());
+        }
+        Change::Add(amount) => {
+          self.map.insert(key.clone(), Value::Number(current.saturating_add(*amount)));
+        }
+        Change::Subtract(amount) => {
+          self.map.insert(key.clone(), Value::Number(current.saturating_sub(*amount)));
+        }
+      }
+    }
+  }
+}
+
+/// A change an action makes to an entry of the blackboard.
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub enum Change {
+  Set(Value),
+  Add(u32),
+  Subtract(u32),
+}
+
+/// The changes an action makes to the blackboard, applied in order.
+#[derive(Debug, Default, Clone, Hash, Eq, PartialEq)]
+pub struct Effects {
+  changes: Vec<(String, Change)>,
+}
+
+impl Effects {
+  pub fn new() -> Self {
+    Self {
+      changes: vec![],
+    }
+  }
+
+  pub fn set(&mut self, key: &str, data: impl Into<Value>) {
+    self.changes.push((String::from(key), Change::Set(data.into())));
+  }
+
+  pub fn insert_bool(&mut self, key: &str, data: bool) {
+    self.set(key, data);
+  }
+
+  pub fn add(&mut self, key: &str, amount: u32) {
+    self.changes.push((String::from(key), Change::Add(amount)));
+  }
+
+  pub fn subtract(&mut self, key: &str, amount: u32) {
+    self.changes.push((String::from(key), Change::Subtract(amount)));
+  }
+
+  pub fn changes(&self) -> &[(String, Change)] {
+    &self.changes
+  }
+
+  pub fn get(&self, key: &str) -> Option<&Change> {
+    self
+      .changes
+      .iter()
+      .rev()
+      .find(|(other, _)| other == key)
+      .map(|(_, change)| change)
+  }
+
+  pub fn contains(&self, key: &str) -> bool {
+    self.get(key).is_some()
+  }
+
+  pub fn keys(&self) -> impl Iterator<Item = &String> {
+    self.changes.iter().map(|(key, _)| key)
+  }
+}
@@ -0,0 +1,186 @@
+use serde::{Deserialize, Serialize};
+
+use super::blackboard::{Blackboard, Change, Value};
+
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Operator {
+  Equal,
+  NotEqual,
+  Less,
+  GreaterOrEqual,
+}
+
+/// A single check against an entry of the blackboard. `Less` and
+/// `GreaterOrEqual` only hold between numbers, missing ones counting as zero.
+#[derive(Debug, Clone, Hash, Eq, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Condition {
+  key: String,
+  operator: Operator,
+  value: Value,
+}
+
+impl Condition {
+  pub fn new(key: &str, operator: Operator, value: impl Into<Value>) -> Self {
+    Self {
+      key: String::from(key),
+      operator,
+      value: value.into(),
+    }
+  }
+
+  pub fn key(&self) -> &str {
+    &self.key
+  }
+
+  pub fn operator(&self) -> Operator {
+    self.operator
+  }
+
+  pub fn value(&self) -> &Value {
+    &self.value
+  }
+
+  pub fn is_met(&self, blackboard: &Blackboard) -> bool {
+    let current = blackboard.get(&self.key);
+
+    match (self.operator, current, &self.value) {
+      (Operator::Equal, Some(current), value) => current == value,
+      (Operator::NotEqual, Some(current), value) => current != value,
+      // Nothing is known about the key, so it can't be equal to anything
+      (Operator::NotEqual, None, _) => true,
+      // Like `Blackboard::apply`, a missing number counts as zero
+      (Operator::Less, None, Value::Number(value)) => 0 < *value,
+      (Operator::Less, Some(Value::Number(current)), Value::Number(value)) => current < value,
+      (Operator::GreaterOrEqual, None, Value::Number(value)) => *value == 0,
+      (Operator::GreaterOrEqual, Some(Value::Number(current)), Value::Number(value)) => {
+        current >= value
+      }
+      _ => false,
+    }
+  }
+
+  /// What has to hold before `change` for the condition to hold after it.
+  /// Nothing means the change can never lead to it.
+  pub fn regress(&self, change: &Change) -> Option<Regression> {
+    use Operator::*;
+
+    let target = match (change, &self.value) {
+      (Change::Set(value), _) => {
+        let mut after = Blackboard::new();
+        after.insert(&self.key, value.clone());
+        return if self.is_met(&after) {
+          Some(Regression::Met)
+        } else {
+          None
+        };
+      }
+      (_, Value::Number(target)) => *target,
+      _ => return None,
+    };
+
+    let becomes = |operator, value| {
+      Some(Regression::Becomes(Condition::new(&self.key, operator, value)))
+    };
+
+    match (change, self.operator) {
+      (Change::Add(amount), Equal) if target >= *amount => becomes(Equal, target - amount),
+      (Change::Add(_), Equal) => None,
+      (Change::Add(amount), NotEqual) if target >= *amount => becomes(NotEqual, target - amount),
+      (Change::Add(_), NotEqual) => Some(Regression::Met),
+      (Change::Add(amount), Less) if target > *amount => becomes(Less, target - amount),
+      (Change::Add(_), Less) => None,
+      (Change::Add(amount), GreaterOrEqual) if target > *amount => {
+        becomes(GreaterOrEqual, target - amount)
+      }
+      (Change::Add(_), GreaterOrEqual) => Some(Regression::Met),
+      // Subtracting stops at zero, so reaching zero takes anything up to the amount
+      (Change::Subtract(amount), Equal) if target == 0 => becomes(Less, amount.saturating_add(1)),
+      (Change::Subtract(amount), Equal) => becomes(Equal, target.saturating_add(*amount)),
+      (Change::Subtract(amount), NotEqual) if target == 0 => {
+        becomes(GreaterOrEqual, amount.saturating_add(1))
+      }
+      (Change::Subtract(amount), NotEqual) => becomes(NotEqual, target.saturating_add(*amount)),
+      (Change::Subtract(_), Less) if target == 0 => None,
+      (Change::Subtract(amount), Less) => becomes(Less, target.saturating_add(*amount)),
+      (Change::Subtract(_), GreaterOrEqual) if target == 0 => Some(Regression::Met),
+      (Change::Subtract(amount), GreaterOrEqual) => {
+        becomes(GreaterOrEqual, target.saturating_add(*amount))
+      }
+      (Change::Set(_), _) => None,
+    }
+  }
+
+  /// Whether `change` moves the blackboard towards meeting the condition.
+  pub fn is_helped_by(&self, change: &Change) -> bool {
+    match (change, self.operator) {
+      (Change::Set(_), _) => matches!(self.regress(change), Some(Regression::Met)),
+      (Change::Add(_), Operator::Less) => false,
+      (Change::Subtract(_), Operator::GreaterOrEqual) => false,
+      _ => self.regress(change).is_some(),
+    }
+  }
+}
+
+/// A condition seen from before a change was made.
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub enum Regression {
+  /// The change meets it whatever came before.
+  Met,
+  /// It's met after the change if this was met before.
+  Becomes(Condition),
+}
+
+/// What a goal asks of the world. It's met once every one of its
+/// conditions holds on a blackboard.
+#[derive(Debug, Default, Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub struct GoalState {
+  conditions: Vec<Condition>,
+}
+
+impl GoalState {
+  pub fn new() -> Self {
+    Self {
+      conditions: vec![],
+    }
+  }
+
+  /// Conditions in a fixed order without duplicates, so equal states can
+  /// be told apart from the order they were built in.
+  pub(super) fn normalized(mut self) -> Self {
+    self.conditions.sort();
+    self.conditions.dedup();
+    self
+  }
+
+  pub fn insert(&mut self, key: &str, operator: Operator, data: impl Into<Value>) {
+    self.conditions.push(Condition::new(key, operator, data));
+  }
+
+  pub fn insert_bool(&mut self, key: &str, data: bool) {
+    self.insert(key, Operator::Equal, data);
+  }
+
+  pub fn insert_number(&mut self, key: &str, data: u32) {
+    self.insert(key, Operator::Equal, data);
+  }
+
+  pub fn insert_str(&mut self, key: &str, data: &str) {
+    self.insert(key, Operator::Equal, data);
+  }
+
+  pub fn push(&mut self, condition: Condition) {
+    self.conditions.push(condition);
+  }
+
+  pub fn conditions(&self) -> &[Condition] {
+    &self.conditions
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.conditions.is_empty()
+  }
+
+  pub fn is_met(&self, blackboard: &Blackboard) -> bool {
+    self.conditions.iter().all(|condition| condition.is_met(blackboard))
+  }
+}
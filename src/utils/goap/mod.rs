@@ -0,0 +1,167 @@
+mod blackboard;
+mod condition;
+mod planner;
+mod scheduler;
+mod search;
+mod trace;
+mod world;
+#[cfg(test)]
+mod tests;
+
+pub use blackboard::{Blackboard, Change, Effects, Value};
+pub use condition::{Condition, GoalState, Operator, Regression};
+pub use planner::{Analysis, Plan, Planner, DEFAULT_HYSTERESIS, DEFAULT_ITERATION_LIMIT};
+pub use scheduler::{PlanningScheduler, PlanningStats, DEFAULT_BUDGET};
+pub use world::World;
+#[cfg(feature = "debug-goap")]
+pub use trace::{Outcome, Rejection, SearchTrace, TraceNode, TraceState};
+
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+
+/// Sense the world, and creates an initial set of effects to put
+/// inside of the blackboard. This helps make the creation of actions
+/// that are a bit easier to maintain.
+/// They always run, and they must modify the blackboard, if their sensor
+/// is triggered.
+/// They must not modify the world
+pub trait Sensor<W: World>: Debug + Sync + Send {
+  fn name(&self) -> &'static str;
+
+  fn sense(
+    &mut self,
+    entity: W::Agent,
+    scene: &mut W::Scene,
+    backpack: &mut W::Backpack,
+    local: &mut W::Local,
+    blackboard: &mut Blackboard,
+  );
+
+  /// Whether the change between what was sensed last tick and now makes
+  /// the current plan worthless, like its target dying. The agent then
+  /// re-plans right away.
+  fn invalidates(
+    &self,
+    previous: &Blackboard,
+    current: &Blackboard,
+  ) -> bool {
+    false
+  }
+}
+
+pub trait Action<W: World>: Debug + Sync + Send {
+  fn name(&self) -> &'static str;
+
+  /// What running the action would cost the agent, lower being better.
+  /// Negative costs are treated as zero.
+  fn cost(
+    &self,
+    entity: W::Agent,
+    scene: &mut W::Scene,
+    local: &W::Local,
+    blackboard: &Blackboard,
+  ) -> i32;
+
+  /// Conditions the blackboard has to meet for the action to run. Actions
+  /// that declare them along with their `effects` don't need to implement
+  /// `check_readyness` and `apply_effect`, and can be searched backwards.
+  fn preconditions(&self) -> Option<GoalState> {
+    None
+  }
+
+  /// Changes the action makes to the blackboard once it's done.
+  fn effects(&self) -> Option<Effects> {
+    None
+  }
+
+  fn is_declarative(&self) -> bool {
+    self.preconditions().is_some() && self.effects().is_some()
+  }
+
+  fn check_readyness(
+    &mut self,
+    entity: W::Agent,
+    scene: &mut W::Scene,
+    local: &W::Local,
+    blackboard: &Blackboard,
+  ) -> bool {
+    match self.preconditions() {
+      Some(preconditions) => preconditions.is_met(blackboard),
+      None => true,
+    }
+  }
+
+  fn apply_effect(
+    &mut self,
+    local: &mut W::Local,
+    blackboard: &mut Blackboard,
+  ) {
+    if let Some(effects) = self.effects() {
+      blackboard.apply(&effects);
+    }
+  }
+
+  /// Seconds the action can keep running before it counts as failed.
+  fn timeout(&self) -> Option<f32> {
+    None
+  }
+
+  /// Runs the action for a tick. The plan moves on to the next action once
+  /// it succeeds, and the agent re-plans when it fails.
+  fn execute(
+    &mut self,
+    entity: W::Agent,
+    scene: &mut W::Scene,
+    backpack: &mut W::Backpack,
+    local: &mut W::Local,
+  ) -> ActionStatus;
+
+  /// Called when the plan gets dropped while the action is running, be it
+  /// because it failed, timed out or got replaced. Actions keeping track of
+  /// something between ticks forget it here, so they start over next time.
+  fn reset(&mut self) {}
+}
+
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum ActionStatus {
+  Running,
+  Success,
+  Failure,
+}
+
+impl<W: World> Eq for dyn Action<W> { }
+
+impl<W: World> PartialEq for dyn Action<W> {
+  fn eq(&self, other: &Self) -> bool {
+    self.name() == other.name()
+  }
+}
+
+impl<W: World> Hash for dyn Action<W> {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.name().hash(state);
+  }
+}
+
+pub trait Goal<W: World>: Debug + Sync + Send {
+  fn name() -> &'static str where Self:Sized;
+
+  fn get_goal(
+    &self,
+    entity: W::Agent,
+    scene: &mut W::Scene,
+    local: &mut W::Local,
+  ) -> GoalState;
+
+  /// How urgent the goal is right now. The planner goes after the most
+  /// insistent goal it can reach, ties going to the one inserted first.
+  fn insistence(
+    &self,
+    entity: W::Agent,
+    scene: &mut W::Scene,
+    local: &W::Local,
+    blackboard: &Blackboard,
+  ) -> f32 {
+    1.0
+  }
+}
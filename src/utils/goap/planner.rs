@@ -0,0 +1,667 @@
+use std::collections::HashSet;
+
+use super::{
+  search::{BackwardSearch, ForwardSearch, Search, Step},
+  Action, ActionStatus, Blackboard, Goal, Sensor, World,
+};
+#[cfg(feature = "debug-goap")]
+use super::trace::SearchTrace;
+
+/// Goals have to be this much more insistent than the current one to take
+/// over, so agents don't flip between two goals that are about as urgent.
+pub const DEFAULT_HYSTERESIS: f32 = 0.1;
+
+/// Nodes a search for a single goal can expand before it's given up on.
+pub const DEFAULT_ITERATION_LIMIT: usize = 50;
+
+/// An ordered list of actions that takes the agent from the current world
+/// state to the one its goal asks for. Goals and actions are referred to by
+/// their index in the `Planner` that made the plan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plan {
+  goal: usize,
+  actions: Vec<usize>,
+  cost: i32,
+  step: usize,
+}
+
+impl Plan {
+  pub(super) fn new(goal: usize, actions: Vec<usize>, cost: i32) -> Self {
+    Self {
+      goal,
+      actions,
+      cost,
+      step: 0,
+    }
+  }
+
+  pub fn goal(&self) -> usize {
+    self.goal
+  }
+
+  pub fn actions(&self) -> &[usize] {
+    &self.actions
+  }
+
+  /// Actions that haven't been completed yet, the current one first.
+  pub fn remaining(&self) -> &[usize] {
+    &self.actions[self.step.min(self.actions.len())..]
+  }
+
+  pub fn cost(&self) -> i32 {
+    self.cost
+  }
+
+  pub fn current(&self) -> Option<usize> {
+    self.actions.get(self.step).copied()
+  }
+
+  pub fn advance(&mut self) {
+    self.step += 1;
+  }
+
+  pub fn is_complete(&self) -> bool {
+    self.step >= self.actions.len()
+  }
+}
+
+/// Goals still to be searched for, most insistent first, along with the
+/// search that's under way. Kept between ticks when the budget runs out.
+#[derive(Debug)]
+struct PendingSearch {
+  goals: Vec<usize>,
+  search: Option<Search>,
+}
+
+/// Plans for a single agent, going after the most insistent goal it can
+/// reach.
+///
+/// The current plan is kept between ticks and followed one action at a
+/// time. It's only thrown away once it can't be carried out anymore, or
+/// when a goal more insistent by at least the hysteresis becomes reachable.
+///
+/// A tick goes through `prepare`, `think` and `act`. `update` runs all
+/// three with no limit on planning, the `PlanningScheduler` runs them for
+/// many agents sharing a budget.
+#[derive(Debug)]
+pub struct Planner<W: World> {
+  actions: Vec<Box<dyn Action<W>>>,
+  goals: Vec<Box<dyn Goal<W>>>,
+  sensors: Vec<Box<dyn Sensor<W>>>,
+  current: Option<Plan>,
+  pending: Option<PendingSearch>,
+  hysteresis: f32,
+  iteration_limit: usize,
+  /// What the sensors saw last.
+  blackboard: Blackboard,
+  /// Time spent on the current action.
+  elapsed: f32,
+  #[cfg(feature = "debug-goap")]
+  tracing: bool,
+  /// Searches of the latest round of planning, one per goal tried.
+  #[cfg(feature = "debug-goap")]
+  traces: Vec<SearchTrace>,
+}
+
+impl<W: World> Planner<W> {
+  pub fn new() -> Self {
+    Self {
+      actions: vec![],
+      goals: vec![],
+      sensors: vec![],
+      current: None,
+      pending: None,
+      hysteresis: DEFAULT_HYSTERESIS,
+      iteration_limit: DEFAULT_ITERATION_LIMIT,
+      blackboard: Blackboard::new(),
+      elapsed: 0.0,
+      #[cfg(feature = "debug-goap")]
+      tracing: false,
+      #[cfg(feature = "debug-goap")]
+      traces: vec![],
+    }
+  }
+
+  pub fn set_hysteresis(&mut self, hysteresis: f32) {
+    self.hysteresis = hysteresis;
+  }
+
+  pub fn set_iteration_limit(&mut self, iteration_limit: usize) {
+    self.iteration_limit = iteration_limit;
+  }
+
+  pub fn iteration_limit(&self) -> usize {
+    self.iteration_limit
+  }
+
+  pub fn insert_action(&mut self, action: impl Action<W> + 'static) {
+    self.actions.push(Box::new(action));
+  }
+
+  pub fn insert_goal(&mut self, goal: impl Goal<W> + 'static) {
+    self.goals.push(Box::new(goal));
+  }
+
+  pub fn insert_sensor(&mut self, sensor: impl Sensor<W> + 'static) {
+    self.sensors.push(Box::new(sensor));
+  }
+
+  pub fn insert_boxed_action(&mut self, action: Box<dyn Action<W>>) {
+    self.actions.push(action);
+  }
+
+  pub fn insert_boxed_goal(&mut self, goal: Box<dyn Goal<W>>) {
+    self.goals.push(goal);
+  }
+
+  pub fn insert_boxed_sensor(&mut self, sensor: Box<dyn Sensor<W>>) {
+    self.sensors.push(sensor);
+  }
+
+  pub fn current_plan(&self) -> Option<&Plan> {
+    self.current.as_ref()
+  }
+
+  pub fn action_name(&self, index: usize) -> Option<&'static str> {
+    self.actions.get(index).map(|action| action.name())
+  }
+
+  /// Whether a search was left half way, waiting for more budget.
+  pub fn is_searching(&self) -> bool {
+    self.pending.is_some()
+  }
+
+  pub fn is_declarative(&self) -> bool {
+    self.actions.iter().all(|action| action.is_declarative())
+  }
+
+  /// Records every search from now on, see `traces`.
+  #[cfg(feature = "debug-goap")]
+  pub fn set_tracing(&mut self, tracing: bool) {
+    if self.tracing && !tracing {
+      self.traces.clear();
+    }
+    self.tracing = tracing;
+  }
+
+  /// Searches of the latest round of planning, in the order the goals were
+  /// tried. Empty unless tracing is on.
+  #[cfg(feature = "debug-goap")]
+  pub fn traces(&self) -> &[SearchTrace] {
+    &self.traces
+  }
+
+  #[cfg(feature = "debug-goap")]
+  fn tracing(&self) -> bool {
+    self.tracing
+  }
+
+  #[cfg(not(feature = "debug-goap"))]
+  fn tracing(&self) -> bool {
+    false
+  }
+
+  /// Senses the world, re-plans as needed with no limit on the search and
+  /// runs the current action.
+  pub fn update(
+    &mut self,
+    entity: W::Agent,
+    scene: &mut W::Scene,
+    backpack: &mut W::Backpack,
+    local: &mut W::Local,
+  ) {
+    if self.prepare(entity, scene, backpack, local).is_some() {
+      self.think(entity, scene, local, usize::MAX);
+    }
+
+    if let Some(ActionStatus::Failure) = self.act(entity, scene, backpack, local) {
+      if self.consider(entity, scene, local).is_some() {
+        self.think(entity, scene, local, usize::MAX);
+      }
+    }
+  }
+
+  /// Senses the world and throws away a plan that doesn't hold anymore.
+  /// Returns how urgently the agent needs to plan, if it does.
+  pub fn prepare(
+    &mut self,
+    entity: W::Agent,
+    scene: &mut W::Scene,
+    backpack: &mut W::Backpack,
+    local: &mut W::Local,
+  ) -> Option<f32> {
+    let blackboard = self.sense(entity, scene, backpack, local);
+
+    let invalidated = self
+      .sensors
+      .iter()
+      .any(|sensor| sensor.invalidates(&self.blackboard, &blackboard));
+    if invalidated {
+      self.drop_plan();
+      self.pending = None;
+    }
+    self.blackboard = blackboard;
+
+    self.consider(entity, scene, local)
+  }
+
+  /// Keeps searching for a better plan, expanding at most `budget` nodes.
+  /// Returns how many it expanded.
+  pub fn think(
+    &mut self,
+    entity: W::Agent,
+    scene: &mut W::Scene,
+    local: &mut W::Local,
+    budget: usize,
+  ) -> usize {
+    let mut pending = match self.pending.take() {
+      Some(pending) => pending,
+      None => return 0,
+    };
+
+    let blackboard = self.blackboard.clone();
+    let mut left = budget;
+
+    if let Some(plan) = self.run(&mut pending, entity, scene, local, &blackboard, &mut left) {
+      #[cfg(feature = "debug-goap")]
+      {
+        let names = plan
+          .actions()
+          .iter()
+          .filter_map(|index| self.action_name(*index))
+          .collect::<Vec<_>>();
+        log::debug!("plan for goal {}: {:?} ({})", plan.goal(), &names, plan.cost());
+      }
+
+      self.drop_plan();
+      self.current = Some(plan);
+    } else if pending.search.is_some() {
+      self.pending = Some(pending);
+    }
+
+    budget - left
+  }
+
+  /// Runs the current action for a tick, moving on to the next one when it
+  /// succeeds and dropping the plan when it fails or times out.
+  pub fn act(
+    &mut self,
+    entity: W::Agent,
+    scene: &mut W::Scene,
+    backpack: &mut W::Backpack,
+    local: &mut W::Local,
+  ) -> Option<ActionStatus> {
+    let delta = W::delta(backpack);
+
+    let mut plan = self.current.take()?;
+    let index = plan.current()?;
+
+    let action = &mut self.actions[index];
+    let mut status = action.execute(entity, scene, backpack, local);
+    self.elapsed += delta;
+
+    if let (ActionStatus::Running, Some(timeout)) = (status, action.timeout()) {
+      if self.elapsed >= timeout {
+        status = ActionStatus::Failure;
+      }
+    }
+
+    match status {
+      ActionStatus::Running => {
+        self.current = Some(plan);
+      }
+      ActionStatus::Success => {
+        plan.advance();
+        self.elapsed = 0.0;
+        if !plan.is_complete() {
+          self.current = Some(plan);
+        }
+      }
+      ActionStatus::Failure => {
+        #[cfg(feature = "debug-goap")]
+        log::debug!("{} failed, replanning", self.actions[index].name());
+
+        self.current = Some(plan);
+        self.drop_plan();
+      }
+    }
+
+    Some(status)
+  }
+
+  /// Finds a plan for the most insistent goal that can be reached from the
+  /// given world state. Goals that are already satisfied are skipped.
+  pub fn plan(
+    &mut self,
+    entity: W::Agent,
+    scene: &mut W::Scene,
+    local: &mut W::Local,
+    blackboard: &Blackboard,
+  ) -> Option<Plan> {
+    let goals = self
+      .rank(entity, scene, local, blackboard)
+      .into_iter()
+      .map(|(goal, _)| goal)
+      .collect::<Vec<_>>();
+
+    #[cfg(feature = "debug-goap")]
+    self.traces.clear();
+
+    let mut pending = PendingSearch {
+      goals,
+      search: None,
+    };
+    let mut budget = usize::MAX;
+
+    self.run(&mut pending, entity, scene, local, blackboard, &mut budget)
+  }
+
+  /// Drops the current plan when it can't be carried out anymore, and lines
+  /// up a search for the goals worth switching to. Returns the insistence
+  /// of the most insistent one.
+  fn consider(
+    &mut self,
+    entity: W::Agent,
+    scene: &mut W::Scene,
+    local: &mut W::Local,
+  ) -> Option<f32> {
+    let blackboard = self.blackboard.clone();
+
+    if let Some(plan) = self.current.take() {
+      let valid = self.is_valid(&plan, entity, scene, local, &blackboard);
+      self.current = Some(plan);
+      if !valid {
+        self.drop_plan();
+      }
+    }
+
+    let mut ranked = self.rank(entity, scene, local, &blackboard);
+
+    // Only goals clearly more insistent than the one being worked on can
+    // take over
+    if let Some(plan) = &self.current {
+      let current = ranked
+        .iter()
+        .find(|(goal, _)| *goal == plan.goal)
+        .map(|(_, insistence)| *insistence)
+        .unwrap_or(0.0);
+      let hysteresis = self.hysteresis;
+      ranked.retain(|(_, insistence)| *insistence > current + hysteresis);
+    }
+
+    // Goals that are already met don't need planning
+    let goals = &self.goals;
+    ranked.retain(|(goal, _)| !goals[*goal].get_goal(entity, scene, local).is_met(&blackboard));
+
+    let urgency = match ranked.first() {
+      Some((_, insistence)) => *insistence,
+      None => {
+        self.pending = None;
+        return None;
+      }
+    };
+
+    if self.pending.is_none() {
+      #[cfg(feature = "debug-goap")]
+      self.traces.clear();
+
+      self.pending = Some(PendingSearch {
+        goals: ranked.into_iter().map(|(goal, _)| goal).collect(),
+        search: None,
+      });
+    }
+
+    Some(urgency)
+  }
+
+  /// Searches the pending goals in order until a plan turns up or the
+  /// budget runs out. Nothing is found either way when it returns `None`,
+  /// the search is still under way if `pending` holds one.
+  fn run(
+    &mut self,
+    pending: &mut PendingSearch,
+    entity: W::Agent,
+    scene: &mut W::Scene,
+    local: &mut W::Local,
+    blackboard: &Blackboard,
+    budget: &mut usize,
+  ) -> Option<Plan> {
+    loop {
+      let mut search = match pending.search.take() {
+        Some(search) => search,
+        None if pending.goals.is_empty() => return None,
+        None => {
+          let goal = pending.goals.remove(0);
+          self.start(goal, entity, scene, local, blackboard)
+        }
+      };
+
+      let limit = self.iteration_limit;
+      match search.expand(&mut self.actions, entity, scene, local, budget, limit) {
+        Step::Paused => {
+          pending.search = Some(search);
+          return None;
+        }
+        Step::Found(plan) => {
+          let valid =
+            !plan.actions().is_empty() && self.is_valid(&plan, entity, scene, local, blackboard);
+
+          #[cfg(feature = "debug-goap")]
+          self.traces.extend(search.take_trace(valid));
+
+          if valid {
+            return Some(plan);
+          }
+        }
+        Step::Exhausted => {
+          #[cfg(feature = "debug-goap")]
+          self.traces.extend(search.take_trace(true));
+        }
+      }
+
+      // Backward searches can miss plans that need non-declarative checks
+      // or get ahead of themselves, the forward search has the last word
+      if search.is_backward() {
+        let goal = search.goal();
+        let goal_state = self.goals[goal].get_goal(entity, scene, local);
+        let tracing = self.tracing();
+        pending.search = Some(Search::Forward(ForwardSearch::new(goal, goal_state, blackboard, tracing)));
+      }
+    }
+  }
+
+  /// Searches backwards from the goal when every action is declarative,
+  /// which only ever looks at actions that help, forwards otherwise.
+  fn start(
+    &mut self,
+    goal: usize,
+    entity: W::Agent,
+    scene: &mut W::Scene,
+    local: &mut W::Local,
+    blackboard: &Blackboard,
+  ) -> Search {
+    let goal_state = self.goals[goal].get_goal(entity, scene, local);
+
+    let tracing = self.tracing();
+
+    if self.is_declarative() {
+      Search::Backward(BackwardSearch::new(goal, goal_state, blackboard, tracing))
+    } else {
+      Search::Forward(ForwardSearch::new(goal, goal_state, blackboard, tracing))
+    }
+  }
+
+  /// Throws the current plan away, letting the action it was on know.
+  fn drop_plan(&mut self) {
+    if let Some(index) = self.current.take().and_then(|plan| plan.current()) {
+      self.actions[index].reset();
+    }
+    self.elapsed = 0.0;
+  }
+
+  /// Goals along with their insistence, the most insistent first.
+  fn rank(
+    &self,
+    entity: W::Agent,
+    scene: &mut W::Scene,
+    local: &W::Local,
+    blackboard: &Blackboard,
+  ) -> Vec<(usize, f32)> {
+    let mut ranked = self
+      .goals
+      .iter()
+      .enumerate()
+      .map(|(index, goal)| (index, goal.insistence(entity, scene, local, blackboard)))
+      .collect::<Vec<_>>();
+
+    // Stable, so equally insistent goals keep their insertion order
+    ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    ranked
+  }
+
+  fn sense(
+    &mut self,
+    entity: W::Agent,
+    scene: &mut W::Scene,
+    backpack: &mut W::Backpack,
+    local: &mut W::Local,
+  ) -> Blackboard {
+    let mut blackboard = Blackboard::new();
+
+    for sensor in &mut self.sensors {
+      sensor.sense(entity, scene, backpack, local, &mut blackboard);
+    }
+
+    blackboard
+  }
+
+  /// Replays what's left of the plan on top of the current world state. It
+  /// still holds if every action is ready when its turn comes, and the goal
+  /// is met at the end.
+  fn is_valid(
+    &mut self,
+    plan: &Plan,
+    entity: W::Agent,
+    scene: &mut W::Scene,
+    local: &mut W::Local,
+    blackboard: &Blackboard,
+  ) -> bool {
+    let goal_state = match self.goals.get(plan.goal) {
+      Some(goal) => goal.get_goal(entity, scene, local),
+      None => return false,
+    };
+
+    let mut blackboard = blackboard.clone();
+    for index in plan.remaining() {
+      let action = &mut self.actions[*index];
+      if !action.check_readyness(entity, scene, local, &blackboard) {
+        return false;
+      }
+      action.apply_effect(local, &mut blackboard);
+    }
+
+    goal_state.is_met(&blackboard)
+  }
+
+  /// Looks for goals the actions can never reach and actions that never
+  /// help any goal. Meant to be run once a planner is put together, it can
+  /// only tell for declarative actions and skips the others.
+  pub fn analyze(
+    &mut self,
+    entity: W::Agent,
+    scene: &mut W::Scene,
+    local: &mut W::Local,
+  ) -> Analysis {
+    let goals = self
+      .goals
+      .iter()
+      .map(|goal| goal.get_goal(entity, scene, local))
+      .collect::<Vec<_>>();
+
+    let mut analysis = Analysis::default();
+
+    // Anything goes once an action can't be looked into
+    if !self.is_declarative() {
+      return analysis;
+    }
+
+    let effects = self
+      .actions
+      .iter()
+      .filter_map(|action| action.effects())
+      .collect::<Vec<_>>();
+
+    for (index, goal) in goals.iter().enumerate() {
+      let reachable = goal.conditions().iter().all(|condition| {
+        effects.iter().any(|effects| match effects.get(condition.key()) {
+          Some(change) => condition.is_helped_by(change),
+          None => false,
+        })
+      });
+
+      if !reachable {
+        analysis.unreachable_goals.push(index);
+      }
+    }
+
+    // Keys that matter, starting from the goals and growing with the
+    // preconditions of every action that writes one of them
+    let mut wanted = goals
+      .iter()
+      .flat_map(|goal| goal.conditions().iter().map(|condition| condition.key().to_string()))
+      .collect::<HashSet<_>>();
+    let mut useful = vec![false; self.actions.len()];
+
+    loop {
+      let mut changed = false;
+
+      for (index, action) in self.actions.iter().enumerate() {
+        if useful[index] {
+          continue;
+        }
+
+        let helps = action
+          .effects()
+          .map(|effects| effects.keys().any(|key| wanted.contains(key)))
+          .unwrap_or(false);
+
+        if helps {
+          useful[index] = true;
+          changed = true;
+
+          if let Some(preconditions) = action.preconditions() {
+            for condition in preconditions.conditions() {
+              wanted.insert(condition.key().to_string());
+            }
+          }
+        }
+      }
+
+      if !changed {
+        break;
+      }
+    }
+
+    analysis.dead_actions = useful
+      .iter()
+      .enumerate()
+      .filter(|(_, useful)| !**useful)
+      .map(|(index, _)| index)
+      .collect();
+
+    analysis
+  }
+}
+
+/// What `Planner::analyze` found wrong with a planner, by index.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Analysis {
+  pub unreachable_goals: Vec<usize>,
+  pub dead_actions: Vec<usize>,
+}
+
+impl Analysis {
+  pub fn is_clean(&self) -> bool {
+    self.unreachable_goals.is_empty() && self.dead_actions.is_empty()
+  }
+}
@@ -0,0 +1,153 @@
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use instant::Instant;
+
+use super::{Planner, World};
+
+/// Nodes all planners get to expand between them in a single tick.
+pub const DEFAULT_BUDGET: usize = 500;
+
+/// How much urgency an agent gains for every tick it's left waiting, so the
+/// least urgent ones still get to plan eventually.
+const AGING: f32 = 0.05;
+
+/// How planning went, last tick and since the scheduler was made.
+#[derive(Debug, Default, Clone)]
+pub struct PlanningStats {
+  /// Nodes expanded last tick.
+  pub expansions: usize,
+  /// Time spent planning last tick.
+  pub time: Duration,
+  /// Agents that got to plan last tick.
+  pub planned: usize,
+  /// Agents left waiting for budget last tick.
+  pub deferred: usize,
+  pub total_expansions: u64,
+  pub total_time: Duration,
+}
+
+/// Shares a budget of node expansions between every agent that needs to
+/// plan, the most urgent ones going first. Searches that run out of budget
+/// pick up where they left off on the next tick. Kept by whatever runs the
+/// planners, which can read how it's doing off `stats`.
+#[derive(Debug)]
+pub struct PlanningScheduler<W: World> {
+  budget: usize,
+  /// Ticks each agent has been waiting for budget.
+  waiting: HashMap<W::Agent, u32>,
+  stats: PlanningStats,
+  /// Agent whose searches get recorded.
+  #[cfg(feature = "debug-goap")]
+  traced: Option<W::Agent>,
+}
+
+impl<W: World> Default for PlanningScheduler<W> {
+  fn default() -> Self {
+    Self::new(DEFAULT_BUDGET)
+  }
+}
+
+impl<W: World> PlanningScheduler<W> {
+  pub fn new(budget: usize) -> Self {
+    Self {
+      budget,
+      waiting: HashMap::new(),
+      stats: PlanningStats::default(),
+      #[cfg(feature = "debug-goap")]
+      traced: None,
+    }
+  }
+
+  pub fn budget(&self) -> usize {
+    self.budget
+  }
+
+  pub fn set_budget(&mut self, budget: usize) {
+    self.budget = budget;
+  }
+
+  pub fn stats(&self) -> &PlanningStats {
+    &self.stats
+  }
+
+  /// Records the searches of a single agent, read back with
+  /// `Planner::traces`.
+  #[cfg(feature = "debug-goap")]
+  pub fn trace(&mut self, entity: Option<W::Agent>) {
+    self.traced = entity;
+  }
+
+  /// Runs a tick for every agent. They all sense the world first, then the
+  /// ones that need to plan search within the budget, and finally they all
+  /// carry on with their current action.
+  pub fn update<'a>(
+    &mut self,
+    agents: impl IntoIterator<Item = (W::Agent, &'a mut Planner<W>, &'a mut W::Local)>,
+    scene: &mut W::Scene,
+    backpack: &mut W::Backpack,
+  ) {
+    let mut agents = agents.into_iter().collect::<Vec<_>>();
+    let mut queue = vec![];
+
+    for (index, (entity, planner, local)) in agents.iter_mut().enumerate() {
+      #[cfg(feature = "debug-goap")]
+      planner.set_tracing(self.traced == Some(*entity));
+
+      match planner.prepare(*entity, scene, backpack, local) {
+        Some(urgency) => {
+          let waited = self.waiting.get(entity).copied().unwrap_or(0);
+          queue.push((index, urgency + waited as f32 * AGING));
+        }
+        None => {
+          self.waiting.remove(entity);
+        }
+      }
+    }
+
+    // Stable, so equally urgent agents keep their order
+    queue.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+    let start = Instant::now();
+    let mut left = self.budget;
+    let mut planned = 0;
+    let mut deferred = 0;
+
+    for (index, _) in queue {
+      let (entity, planner, local) = &mut agents[index];
+
+      if left > 0 {
+        left -= planner.think(*entity, scene, local, left);
+        planned += 1;
+      }
+
+      if planner.is_searching() {
+        *self.waiting.entry(*entity).or_insert(0) += 1;
+        deferred += 1;
+      } else {
+        self.waiting.remove(entity);
+      }
+    }
+
+    let time = start.elapsed();
+    let expansions = self.budget - left;
+
+    self.stats.expansions = expansions;
+    self.stats.time = time;
+    self.stats.planned = planned;
+    self.stats.deferred = deferred;
+    self.stats.total_expansions += expansions as u64;
+    self.stats.total_time += time;
+
+    // Agents that are gone don't wait anymore
+    let alive = agents.iter().map(|(entity, _, _)| *entity).collect::<HashSet<_>>();
+    self.waiting.retain(|entity, _| alive.contains(entity));
+
+    for (entity, planner, local) in agents.iter_mut() {
+      planner.act(*entity, scene, backpack, local);
+    }
+  }
+}
@@ -0,0 +1,498 @@
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use priority_queue::PriorityQueue;
+
+use super::{
+  planner::Plan, Action, Blackboard, Effects, GoalState, Regression, World,
+};
+#[cfg(feature = "debug-goap")]
+use super::trace::{Outcome, Recorder, SearchTrace, TraceState};
+
+/// How many times a single action can show up in a plan. Keeps actions
+/// that only ever add to a number from being chained forever.
+const MAX_REPEATS: usize = 16;
+
+/// Where a search for a single goal got to.
+#[derive(Debug)]
+pub(super) enum Step {
+  Found(Plan),
+  /// Nothing left to expand, or the iteration limit was hit.
+  Exhausted,
+  /// Out of budget, the search can pick up from here later.
+  Paused,
+}
+
+/// A search for a plan that can be run a few expansions at a time.
+#[derive(Debug)]
+pub(super) enum Search {
+  Forward(ForwardSearch),
+  Backward(BackwardSearch),
+}
+
+impl Search {
+  pub(super) fn goal(&self) -> usize {
+    match self {
+      Search::Forward(search) => search.goal,
+      Search::Backward(search) => search.goal,
+    }
+  }
+
+  pub(super) fn is_backward(&self) -> bool {
+    matches!(self, Search::Backward(_))
+  }
+
+  pub(super) fn expand<W: World>(
+    &mut self,
+    actions: &mut [Box<dyn Action<W>>],
+    entity: W::Agent,
+    scene: &mut W::Scene,
+    local: &mut W::Local,
+    budget: &mut usize,
+    limit: usize,
+  ) -> Step {
+    match self {
+      Search::Forward(search) => search.expand(actions, entity, scene, local, budget, limit),
+      Search::Backward(search) => search.expand(actions, entity, scene, local, budget, limit),
+    }
+  }
+
+  /// The trace of a finished search, marked invalid when the plan it found
+  /// didn't hold up.
+  #[cfg(feature = "debug-goap")]
+  pub(super) fn take_trace(&mut self, valid: bool) -> Option<SearchTrace> {
+    let recorder = match self {
+      Search::Forward(search) => &mut search.recorder,
+      Search::Backward(search) => &mut search.recorder,
+    };
+
+    let mut trace = recorder.take()?;
+    if !valid && trace.outcome == Outcome::Found {
+      trace.outcome = Outcome::Invalid;
+    }
+    Some(trace)
+  }
+}
+
+#[derive(Debug)]
+struct PlanningNode {
+  name: &'static str,
+  blackboard: Blackboard,
+  cost: i32,
+  action: Option<usize>,
+  parent: Option<usize>,
+}
+
+impl Hash for PlanningNode {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.blackboard.hash(state);
+    self.name.hash(state);
+  }
+}
+
+impl Eq for PlanningNode {
+}
+
+impl PartialEq for PlanningNode {
+  fn eq(&self, other: &Self) -> bool {
+    self.blackboard == other.blackboard && self.name == other.name
+  }
+}
+
+/// A* over the world states reachable with the planner's actions, from
+/// the given state to the one the goal asks for.
+#[derive(Debug)]
+pub(super) struct ForwardSearch {
+  goal: usize,
+  goal_state: GoalState,
+  open_set: PriorityQueue<usize, Reverse<i32>>,
+  closed_set: HashSet<Blackboard>,
+  parents: Vec<PlanningNode>,
+  iterations: usize,
+  #[cfg(feature = "debug-goap")]
+  recorder: Recorder,
+}
+
+impl ForwardSearch {
+  #[cfg_attr(not(feature = "debug-goap"), allow(unused_variables))]
+  pub(super) fn new(goal: usize, goal_state: GoalState, blackboard: &Blackboard, tracing: bool) -> Self {
+    let mut open_set = PriorityQueue::new();
+    let mut parents = vec![];
+
+    let root = PlanningNode {
+      name: "root",
+      blackboard: blackboard.clone(),
+      cost: 0,
+      action: None,
+      parent: None,
+    };
+    let root_index = put(&mut parents, root);
+    open_set.push(root_index, Reverse(0));
+
+    #[cfg(feature = "debug-goap")]
+    let recorder = {
+      let mut recorder = Recorder::new(tracing, goal, "forward");
+      recorder.node(root_index, None, None, || TraceState::Blackboard(blackboard.clone()), 0);
+      recorder
+    };
+
+    Self {
+      goal,
+      goal_state,
+      open_set,
+      closed_set: HashSet::new(),
+      parents,
+      iterations: 0,
+      #[cfg(feature = "debug-goap")]
+      recorder,
+    }
+  }
+
+  fn expand<W: World>(
+    &mut self,
+    actions: &mut [Box<dyn Action<W>>],
+    entity: W::Agent,
+    scene: &mut W::Scene,
+    local: &mut W::Local,
+    budget: &mut usize,
+    limit: usize,
+  ) -> Step {
+    while *budget > 0 {
+      let (current_index, Reverse(cost)) = match self.open_set.pop() {
+        Some(next) => next,
+        None => {
+          #[cfg(feature = "debug-goap")]
+          self.recorder.finish(Outcome::Exhausted, vec![]);
+          return Step::Exhausted;
+        }
+      };
+
+      if self.iterations >= limit {
+        #[cfg(feature = "debug-goap")]
+        self.recorder.finish(Outcome::Exhausted, vec![]);
+        return Step::Exhausted;
+      }
+
+      if self.goal_state.is_met(&self.parents[current_index].blackboard) {
+        let mut curr = current_index;
+        let mut plan = vec![];
+        #[cfg(feature = "debug-goap")]
+        let mut path = vec![current_index];
+
+        while let PlanningNode { action: Some(action), parent: Some(parent), .. } = self.parents[curr] {
+          plan.push(action);
+          curr = parent;
+          #[cfg(feature = "debug-goap")]
+          path.push(curr);
+        }
+        plan.reverse();
+
+        #[cfg(feature = "debug-goap")]
+        {
+          path.reverse();
+          self.recorder.finish(Outcome::Found, path);
+        }
+
+        return Step::Found(Plan::new(self.goal, plan, cost));
+      }
+
+      if !self.closed_set.contains(&self.parents[current_index].blackboard) {
+        self.closed_set.insert(self.parents[current_index].blackboard.clone());
+        #[cfg(feature = "debug-goap")]
+        self.recorder.expand(current_index);
+
+        for (index, action) in actions.iter_mut().enumerate() {
+          if repeats(&self.parents, current_index, index, |node| (node.action, node.parent)) >= MAX_REPEATS {
+            #[cfg(feature = "debug-goap")]
+            self.recorder.reject(current_index, action.name(), "repeated too often");
+            continue;
+          }
+
+          if action.check_readyness(entity, scene, local, &self.parents[current_index].blackboard) {
+            let mut next_blackboard = self.parents[current_index].blackboard.clone();
+            let next_cost = cost + action.cost(entity, scene, local, &next_blackboard).max(0);
+            action.apply_effect(local, &mut next_blackboard);
+
+            if !self.closed_set.contains(&next_blackboard) {
+              let node = PlanningNode {
+                name: action.name(),
+                blackboard: next_blackboard,
+                cost: next_cost,
+                action: Some(index),
+                parent: Some(current_index),
+              };
+              let tree_index = put(&mut self.parents, node);
+              self.open_set.push(tree_index, Reverse(next_cost));
+
+              #[cfg(feature = "debug-goap")]
+              {
+                let parents = &self.parents;
+                self.recorder.node(
+                  tree_index,
+                  Some(current_index),
+                  Some(action.name()),
+                  || TraceState::Blackboard(parents[tree_index].blackboard.clone()),
+                  next_cost,
+                );
+              }
+            } else {
+              #[cfg(feature = "debug-goap")]
+              self.recorder.reject(current_index, action.name(), "already explored");
+            }
+          } else {
+            #[cfg(feature = "debug-goap")]
+            self.recorder.reject(current_index, action.name(), "not ready");
+          }
+        }
+      }
+
+      self.iterations += 1;
+      *budget -= 1;
+    }
+
+    Step::Paused
+  }
+}
+
+#[derive(Debug)]
+struct RegressionNode {
+  outstanding: GoalState,
+  action: Option<usize>,
+  parent: Option<usize>,
+}
+
+/// A* over the conditions still left to meet, from the goal back to the
+/// given state. An action is tried when its effects meet at least one of
+/// them without breaking any other, and its preconditions take their
+/// place.
+#[derive(Debug)]
+pub(super) struct BackwardSearch {
+  goal: usize,
+  blackboard: Blackboard,
+  open_set: PriorityQueue<usize, Reverse<i32>>,
+  closed_set: HashSet<GoalState>,
+  parents: Vec<RegressionNode>,
+  iterations: usize,
+  #[cfg(feature = "debug-goap")]
+  recorder: Recorder,
+}
+
+impl BackwardSearch {
+  #[cfg_attr(not(feature = "debug-goap"), allow(unused_variables))]
+  pub(super) fn new(goal: usize, goal_state: GoalState, blackboard: &Blackboard, tracing: bool) -> Self {
+    let mut open_set = PriorityQueue::new();
+    let mut parents = vec![];
+
+    let root = RegressionNode {
+      outstanding: unmet(goal_state, blackboard),
+      action: None,
+      parent: None,
+    };
+    let root_index = put(&mut parents, root);
+    open_set.push(root_index, Reverse(0));
+
+    #[cfg(feature = "debug-goap")]
+    let recorder = {
+      let mut recorder = Recorder::new(tracing, goal, "backward");
+      let outstanding = &parents[root_index].outstanding;
+      recorder.node(root_index, None, None, || TraceState::Outstanding(outstanding.clone()), 0);
+      recorder
+    };
+
+    Self {
+      goal,
+      blackboard: blackboard.clone(),
+      open_set,
+      closed_set: HashSet::new(),
+      parents,
+      iterations: 0,
+      #[cfg(feature = "debug-goap")]
+      recorder,
+    }
+  }
+
+  fn expand<W: World>(
+    &mut self,
+    actions: &mut [Box<dyn Action<W>>],
+    entity: W::Agent,
+    scene: &mut W::Scene,
+    local: &mut W::Local,
+    budget: &mut usize,
+    limit: usize,
+  ) -> Step {
+    while *budget > 0 {
+      let (current_index, Reverse(cost)) = match self.open_set.pop() {
+        Some(next) => next,
+        None => {
+          #[cfg(feature = "debug-goap")]
+          self.recorder.finish(Outcome::Exhausted, vec![]);
+          return Step::Exhausted;
+        }
+      };
+
+      if self.iterations >= limit {
+        #[cfg(feature = "debug-goap")]
+        self.recorder.finish(Outcome::Exhausted, vec![]);
+        return Step::Exhausted;
+      }
+
+      if self.parents[current_index].outstanding.is_empty() {
+        // Walking up from here already gives the actions in order
+        let mut curr = current_index;
+        let mut plan = vec![];
+        #[cfg(feature = "debug-goap")]
+        let mut path = vec![current_index];
+
+        while let RegressionNode { action: Some(action), parent: Some(parent), .. } = self.parents[curr] {
+          plan.push(action);
+          curr = parent;
+          #[cfg(feature = "debug-goap")]
+          path.push(curr);
+        }
+
+        #[cfg(feature = "debug-goap")]
+        self.recorder.finish(Outcome::Found, path);
+
+        return Step::Found(Plan::new(self.goal, plan, cost));
+      }
+
+      if !self.closed_set.contains(&self.parents[current_index].outstanding) {
+        self.closed_set.insert(self.parents[current_index].outstanding.clone());
+        #[cfg(feature = "debug-goap")]
+        self.recorder.expand(current_index);
+
+        for (index, action) in actions.iter().enumerate() {
+          let (preconditions, effects) = match (action.preconditions(), action.effects()) {
+            (Some(preconditions), Some(effects)) => (preconditions, effects),
+            _ => {
+              #[cfg(feature = "debug-goap")]
+              self.recorder.reject(current_index, action.name(), "not declarative");
+              continue;
+            }
+          };
+
+          if repeats(&self.parents, current_index, index, |node| (node.action, node.parent)) >= MAX_REPEATS {
+            #[cfg(feature = "debug-goap")]
+            self.recorder.reject(current_index, action.name(), "repeated too often");
+            continue;
+          }
+
+          let mut next = match regress(&self.parents[current_index].outstanding, &effects) {
+            Some(outstanding) => outstanding,
+            None => {
+              #[cfg(feature = "debug-goap")]
+              self.recorder.reject(current_index, action.name(), "doesn't help");
+              continue;
+            }
+          };
+
+          for condition in preconditions.conditions() {
+            next.push(condition.clone());
+          }
+          let next = unmet(next, &self.blackboard);
+
+          if !self.closed_set.contains(&next) {
+            let next_cost = cost + action.cost(entity, scene, local, &self.blackboard).max(0);
+            let node = RegressionNode {
+              outstanding: next,
+              action: Some(index),
+              parent: Some(current_index),
+            };
+            let tree_index = put(&mut self.parents, node);
+            self.open_set.push(tree_index, Reverse(next_cost));
+
+            #[cfg(feature = "debug-goap")]
+            {
+              let parents = &self.parents;
+              self.recorder.node(
+                tree_index,
+                Some(current_index),
+                Some(action.name()),
+                || TraceState::Outstanding(parents[tree_index].outstanding.clone()),
+                next_cost,
+              );
+            }
+          } else {
+            #[cfg(feature = "debug-goap")]
+            self.recorder.reject(current_index, action.name(), "already explored");
+          }
+        }
+      }
+
+      self.iterations += 1;
+      *budget -= 1;
+    }
+
+    Step::Paused
+  }
+}
+
+fn put<T>(v: &mut Vec<T>, item: T) -> usize {
+  let idx = v.len();
+  v.push(item);
+  idx
+}
+
+/// The conditions of `state` the blackboard doesn't meet yet.
+fn unmet(state: GoalState, blackboard: &Blackboard) -> GoalState {
+  let mut left = GoalState::new();
+
+  for condition in state.conditions() {
+    if !condition.is_met(blackboard) {
+      left.push(condition.clone());
+    }
+  }
+
+  left.normalized()
+}
+
+/// The conditions left to meet before `effects` are applied, or nothing
+/// when the effects don't help with any of them or rule one out.
+fn regress(outstanding: &GoalState, effects: &Effects) -> Option<GoalState> {
+  let mut left = GoalState::new();
+  let mut helps = false;
+
+  for condition in outstanding.conditions() {
+    let change = match effects.get(condition.key()) {
+      Some(change) => change,
+      None => {
+        left.push(condition.clone());
+        continue;
+      }
+    };
+
+    helps |= condition.is_helped_by(change);
+
+    match condition.regress(change)? {
+      Regression::Met => {}
+      Regression::Becomes(condition) => left.push(condition),
+    }
+  }
+
+  if helps {
+    Some(left)
+  } else {
+    None
+  }
+}
+
+/// How many times `action` shows up on the way from the root to `node`.
+fn repeats<T>(
+  parents: &[T],
+  node: usize,
+  action: usize,
+  link: impl Fn(&T) -> (Option<usize>, Option<usize>),
+) -> usize {
+  let mut count = 0;
+  let mut current = Some(node);
+
+  while let Some(index) = current {
+    let (node_action, parent) = link(&parents[index]);
+    if node_action == Some(action) {
+      count += 1;
+    }
+    current = parent;
+  }
+
+  count
+}
@@ -0,0 +1,948 @@
+use crate::utils::goap::{
+  Action, ActionStatus, Blackboard, Effects, Goal, GoalState, Operator, Planner,
+  PlanningScheduler, Sensor, World, DEFAULT_HYSTERESIS, DEFAULT_ITERATION_LIMIT,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::collections::{BTreeMap, BinaryHeap};
+use std::cmp::Reverse;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// A world with nothing in it, agents only ever plan on their blackboard.
+/// Every tick takes a second.
+#[derive(Debug)]
+struct Memory;
+
+impl World for Memory {
+  type Agent = u32;
+  type Scene = ();
+  type Backpack = ();
+  type Local = ();
+
+  fn delta(_: &()) -> f32 {
+    1.0
+  }
+}
+
+const AGENT: u32 = 0;
+
+#[derive(Debug)]
+struct Want(GoalState);
+
+impl Goal<Memory> for Want {
+  fn name() -> &'static str {
+    "Want"
+  }
+
+  fn get_goal(&self, _: u32, _: &mut (), _: &mut ()) -> GoalState {
+    self.0.clone()
+  }
+}
+
+fn want(key: &str) -> Want {
+  let mut goal = GoalState::new();
+  goal.insert_bool(key, true);
+  Want(goal)
+}
+
+/// A goal whose insistence can be changed from the outside.
+#[derive(Debug)]
+struct Urge {
+  goal: GoalState,
+  insistence: Arc<Mutex<f32>>,
+}
+
+impl Goal<Memory> for Urge {
+  fn name() -> &'static str {
+    "Urge"
+  }
+
+  fn get_goal(&self, _: u32, _: &mut (), _: &mut ()) -> GoalState {
+    self.goal.clone()
+  }
+
+  fn insistence(&self, _: u32, _: &mut (), _: &(), _: &Blackboard) -> f32 {
+    *self.insistence.lock().unwrap()
+  }
+}
+
+fn urge(key: &str, insistence: f32) -> (Urge, Arc<Mutex<f32>>) {
+  let insistence = Arc::new(Mutex::new(insistence));
+  let mut goal = GoalState::new();
+  goal.insert_bool(key, true);
+  let urge = Urge {
+    goal,
+    insistence: insistence.clone(),
+  };
+  (urge, insistence)
+}
+
+/// An action that only looks at the blackboard. Opaque ones hide their
+/// preconditions and effects, so only the forward search can use them.
+#[derive(Debug, Clone)]
+struct Step {
+  name: &'static str,
+  cost: i32,
+  preconditions: GoalState,
+  effects: Effects,
+  opaque: bool,
+}
+
+impl Step {
+  fn new(name: &'static str, cost: i32) -> Self {
+    Self {
+      name,
+      cost,
+      preconditions: GoalState::new(),
+      effects: Effects::new(),
+      opaque: false,
+    }
+  }
+
+  fn requires(mut self, key: &str) -> Self {
+    self.preconditions.insert_bool(key, true);
+    self
+  }
+
+  fn forbids(mut self, key: &str) -> Self {
+    self.preconditions.insert(key, Operator::NotEqual, true);
+    self
+  }
+
+  fn sets(mut self, key: &str, value: bool) -> Self {
+    self.effects.insert_bool(key, value);
+    self
+  }
+
+  fn adds(mut self, key: &str, amount: u32) -> Self {
+    self.effects.add(key, amount);
+    self
+  }
+
+  fn opaque(mut self) -> Self {
+    self.opaque = true;
+    self
+  }
+}
+
+impl Action<Memory> for Step {
+  fn name(&self) -> &'static str {
+    self.name
+  }
+
+  fn cost(&self, _: u32, _: &mut (), _: &(), _: &Blackboard) -> i32 {
+    self.cost
+  }
+
+  fn preconditions(&self) -> Option<GoalState> {
+    if self.opaque {
+      None
+    } else {
+      Some(self.preconditions.clone())
+    }
+  }
+
+  fn effects(&self) -> Option<Effects> {
+    if self.opaque {
+      None
+    } else {
+      Some(self.effects.clone())
+    }
+  }
+
+  fn check_readyness(&mut self, _: u32, _: &mut (), _: &(), blackboard: &Blackboard) -> bool {
+    self.preconditions.is_met(blackboard)
+  }
+
+  fn apply_effect(&mut self, _: &mut (), blackboard: &mut Blackboard) {
+    blackboard.apply(&self.effects);
+  }
+
+  fn execute(&mut self, _: u32, _: &mut (), _: &mut (), _: &mut ()) -> ActionStatus {
+    ActionStatus::Success
+  }
+}
+
+fn planner(goals: Vec<Want>, actions: Vec<Step>) -> Planner<Memory> {
+  let mut planner = Planner::new();
+  for goal in goals {
+    planner.insert_goal(goal);
+  }
+  for action in actions {
+    planner.insert_action(action);
+  }
+  planner
+}
+
+fn plan(planner: &mut Planner<Memory>, blackboard: &Blackboard) -> Option<(Vec<&'static str>, i32)> {
+  let plan = planner.plan(AGENT, &mut (), &mut (), blackboard)?;
+  let names = plan
+    .actions()
+    .iter()
+    .filter_map(|index| planner.action_name(*index))
+    .collect();
+  Some((names, plan.cost()))
+}
+
+fn errands() -> Vec<Step> {
+  vec![
+    Step::new("Walk", 10).sets("AtShop", true),
+    Step::new("GetKeys", 1).sets("HasKeys", true),
+    Step::new("Drive", 2).requires("HasKeys").sets("AtShop", true),
+    Step::new("Buy", 1).requires("AtShop").sets("HasFood", true),
+  ]
+}
+
+#[test]
+fn picks_the_cheapest_plan() {
+  let mut planner = planner(vec![want("HasFood")], errands());
+
+  assert_eq!(
+    plan(&mut planner, &Blackboard::new()),
+    Some((vec!["GetKeys", "Drive", "Buy"], 4))
+  );
+}
+
+#[test]
+fn forward_search_picks_the_cheapest_plan_too() {
+  let actions = errands().into_iter().map(Step::opaque).collect();
+  let mut planner = planner(vec![want("HasFood")], actions);
+  assert!(!planner.is_declarative());
+
+  assert_eq!(
+    plan(&mut planner, &Blackboard::new()),
+    Some((vec!["GetKeys", "Drive", "Buy"], 4))
+  );
+}
+
+#[test]
+fn uses_what_the_agent_already_has() {
+  let mut planner = planner(vec![want("HasFood")], errands());
+  let mut blackboard = Blackboard::new();
+  blackboard.insert_bool("AtShop", true);
+
+  assert_eq!(plan(&mut planner, &blackboard), Some((vec!["Buy"], 1)));
+}
+
+#[test]
+fn unreachable_goals_are_skipped() {
+  let mut planner = planner(vec![want("Flying"), want("HasFood")], errands());
+
+  let (actions, _) = plan(&mut planner, &Blackboard::new()).unwrap();
+  assert_eq!(actions.last(), Some(&"Buy"));
+
+  let analysis = planner.analyze(AGENT, &mut (), &mut ());
+  assert_eq!(analysis.unreachable_goals, vec![0]);
+  assert!(analysis.dead_actions.is_empty());
+}
+
+#[test]
+fn nothing_is_planned_when_no_goal_can_be_reached() {
+  let actions = vec![Step::new("Buy", 1).requires("AtShop").sets("HasFood", true)];
+
+  let mut declarative = planner(vec![want("HasFood")], actions.clone());
+  assert_eq!(plan(&mut declarative, &Blackboard::new()), None);
+
+  let mut opaque = planner(vec![want("HasFood")], actions.into_iter().map(Step::opaque).collect());
+  assert_eq!(plan(&mut opaque, &Blackboard::new()), None);
+}
+
+#[test]
+fn analysis_finds_actions_that_never_help() {
+  let mut actions = errands();
+  actions.push(Step::new("Whistle", 1).sets("Whistled", true));
+  let mut planner = planner(vec![want("HasFood")], actions);
+
+  let analysis = planner.analyze(AGENT, &mut (), &mut ());
+  assert_eq!(analysis.dead_actions, vec![4]);
+}
+
+#[test]
+fn cycles_end_the_search() {
+  let actions = vec![
+    Step::new("Open", 1).forbids("Open").sets("Open", true),
+    Step::new("Close", 1).requires("Open").sets("Open", false),
+    Step::new("Leave", 1).requires("HasKeys").requires("Open").sets("Outside", true),
+  ];
+
+  let mut declarative = planner(vec![want("Outside")], actions.clone());
+  assert_eq!(plan(&mut declarative, &Blackboard::new()), None);
+
+  let mut opaque = planner(vec![want("Outside")], actions.into_iter().map(Step::opaque).collect());
+  assert_eq!(plan(&mut opaque, &Blackboard::new()), None);
+}
+
+#[test]
+fn cycles_dont_get_in_the_way_of_a_plan() {
+  let actions = vec![
+    Step::new("Open", 1).forbids("Open").sets("Open", true),
+    Step::new("Close", 1).requires("Open").sets("Open", false),
+    Step::new("Leave", 1).requires("Open").sets("Outside", true),
+  ];
+
+  let mut declarative = planner(vec![want("Outside")], actions.clone());
+  assert_eq!(plan(&mut declarative, &Blackboard::new()), Some((vec!["Open", "Leave"], 2)));
+
+  let mut opaque = planner(vec![want("Outside")], actions.into_iter().map(Step::opaque).collect());
+  assert_eq!(plan(&mut opaque, &Blackboard::new()), Some((vec!["Open", "Leave"], 2)));
+}
+
+const LINKS: [&str; 12] = [
+  "L0", "L1", "L2", "L3", "L4", "L5", "L6", "L7", "L8", "L9", "L10", "L11",
+];
+
+fn chain(length: usize) -> Vec<Step> {
+  (0..length)
+    .map(|index| {
+      let step = Step::new(LINKS[index], 1).sets(LINKS[index + 1], true);
+      if index == 0 {
+        step
+      } else {
+        step.requires(LINKS[index])
+      }
+    })
+    .collect()
+}
+
+#[test]
+fn iteration_limit_gives_up_on_long_plans() {
+  let goal = || want(LINKS[10]);
+
+  let mut planner = planner(vec![goal()], chain(10));
+  assert_eq!(planner.iteration_limit(), DEFAULT_ITERATION_LIMIT);
+  assert_eq!(plan(&mut planner, &Blackboard::new()).map(|(actions, _)| actions.len()), Some(10));
+
+  planner.set_iteration_limit(5);
+  assert_eq!(plan(&mut planner, &Blackboard::new()), None);
+}
+
+#[test]
+fn repeated_actions_are_capped() {
+  let mut goal = GoalState::new();
+  goal.insert("Wood", Operator::GreaterOrEqual, 5u32);
+  let mut reachable = Planner::new();
+  reachable.insert_goal(Want(goal));
+  reachable.insert_action(Step::new("Chop", 1).adds("Wood", 1));
+  reachable.set_iteration_limit(1000);
+
+  assert_eq!(plan(&mut reachable, &Blackboard::new()).map(|(_, cost)| cost), Some(5));
+
+  let mut goal = GoalState::new();
+  goal.insert("Wood", Operator::GreaterOrEqual, 100u32);
+  let mut unreachable = Planner::new();
+  unreachable.insert_goal(Want(goal));
+  unreachable.insert_action(Step::new("Chop", 1).adds("Wood", 1));
+  unreachable.set_iteration_limit(1000);
+
+  assert_eq!(plan(&mut unreachable, &Blackboard::new()), None);
+}
+
+/// An action that keeps failing, counting how many times it got reset.
+#[derive(Debug)]
+struct Flaky {
+  resets: Arc<AtomicUsize>,
+}
+
+impl Action<Memory> for Flaky {
+  fn name(&self) -> &'static str {
+    "Flaky"
+  }
+
+  fn cost(&self, _: u32, _: &mut (), _: &(), _: &Blackboard) -> i32 {
+    1
+  }
+
+  fn preconditions(&self) -> Option<GoalState> {
+    Some(GoalState::new())
+  }
+
+  fn effects(&self) -> Option<Effects> {
+    let mut effects = Effects::new();
+    effects.insert_bool("Done", true);
+    Some(effects)
+  }
+
+  fn execute(&mut self, _: u32, _: &mut (), _: &mut (), _: &mut ()) -> ActionStatus {
+    ActionStatus::Failure
+  }
+
+  fn reset(&mut self) {
+    self.resets.fetch_add(1, Ordering::SeqCst);
+  }
+}
+
+#[test]
+fn dropped_plans_reset_the_running_action() {
+  let resets = Arc::new(AtomicUsize::new(0));
+  let mut planner = Planner::new();
+  planner.insert_goal(want("Done"));
+  planner.insert_action(Flaky {
+    resets: resets.clone(),
+  });
+
+  planner.update(AGENT, &mut (), &mut (), &mut ());
+  assert_eq!(resets.load(Ordering::SeqCst), 1);
+  // Re-planned right away, the same action is up again
+  assert!(planner.current_plan().is_some());
+
+  planner.update(AGENT, &mut (), &mut (), &mut ());
+  assert_eq!(resets.load(Ordering::SeqCst), 2);
+}
+
+/// Cheapest way to the goal over every combination of the keys, or `None`
+/// if there's no way.
+fn cheapest(actions: &[Step], keys: &[&str], goal: &GoalState) -> Option<i32> {
+  let to_blackboard = |state: usize| {
+    let mut blackboard = Blackboard::new();
+    for (bit, key) in keys.iter().enumerate() {
+      if state & (1 << bit) != 0 {
+        blackboard.insert_bool(key, true);
+      }
+    }
+    blackboard
+  };
+  let from_blackboard = |blackboard: &Blackboard| {
+    keys
+      .iter()
+      .enumerate()
+      .filter(|(_, key)| blackboard.get_bool(key) == Some(&true))
+      .fold(0, |state, (bit, _)| state | (1 << bit))
+  };
+
+  let mut best = BTreeMap::new();
+  let mut open = BinaryHeap::new();
+  open.push(Reverse((0, 0usize)));
+
+  while let Some(Reverse((cost, state))) = open.pop() {
+    if best.contains_key(&state) {
+      continue;
+    }
+    best.insert(state, cost);
+
+    let blackboard = to_blackboard(state);
+    if goal.is_met(&blackboard) {
+      return Some(cost);
+    }
+
+    for action in actions {
+      if action.preconditions.is_met(&blackboard) {
+        let mut next = blackboard.clone();
+        next.apply(&action.effects);
+        open.push(Reverse((cost + action.cost, from_blackboard(&next))));
+      }
+    }
+  }
+
+  None
+}
+
+#[test]
+fn plans_are_as_cheap_as_an_exhaustive_search() {
+  const KEYS: [&str; 4] = ["A", "B", "C", "D"];
+  const NAMES: [&str; 6] = ["S0", "S1", "S2", "S3", "S4", "S5"];
+
+  let mut rng = StdRng::seed_from_u64(7);
+
+  for _ in 0..200 {
+    let actions = NAMES
+      .iter()
+      .map(|name| {
+        let mut step = Step::new(name, rng.gen_range(1..6));
+        for key in KEYS.iter() {
+          match rng.gen_range(0..6) {
+            0 => step = step.requires(key),
+            1 => step = step.forbids(key),
+            _ => {}
+          }
+        }
+        for key in KEYS.iter() {
+          match rng.gen_range(0..4) {
+            0 => step = step.sets(key, true),
+            1 => step = step.sets(key, false),
+            _ => {}
+          }
+        }
+        step
+      })
+      .collect::<Vec<_>>();
+
+    let goal = want(KEYS[rng.gen_range(0..KEYS.len())]);
+    let expected = cheapest(&actions, &KEYS, &goal.0);
+
+    let mut declarative = planner(vec![Want(goal.0.clone())], actions.clone());
+    declarative.set_iteration_limit(1000);
+    let found = plan(&mut declarative, &Blackboard::new()).map(|(_, cost)| cost);
+    assert_eq!(found, expected, "backward search on {:?}", actions);
+
+    let mut opaque = planner(vec![goal], actions.iter().cloned().map(Step::opaque).collect());
+    opaque.set_iteration_limit(1000);
+    let found = plan(&mut opaque, &Blackboard::new()).map(|(_, cost)| cost);
+    assert_eq!(found, expected, "forward search on {:?}", actions);
+  }
+}
+
+/// Runs a tick of the scheduler for every planner, agents being numbered
+/// after their index.
+fn tick(scheduler: &mut PlanningScheduler<Memory>, planners: &mut [Planner<Memory>]) {
+  let mut locals = vec![(); planners.len()];
+  let agents = planners
+    .iter_mut()
+    .zip(locals.iter_mut())
+    .enumerate()
+    .map(|(index, (planner, local))| (index as u32, planner, local));
+  scheduler.update(agents, &mut (), &mut ());
+}
+
+fn action_names(planner: &Planner<Memory>) -> Option<Vec<&'static str>> {
+  let plan = planner.current_plan()?;
+  Some(plan.actions().iter().filter_map(|index| planner.action_name(*index)).collect())
+}
+
+#[test]
+fn agents_share_the_planning_budget() {
+  let mut scheduler = PlanningScheduler::new(12);
+  let mut planners = (0..3)
+    .map(|_| planner(vec![want(LINKS[10])], chain(10)))
+    .collect::<Vec<_>>();
+  let mut planned_at = vec![None; planners.len()];
+
+  for ticks in 1..=10 {
+    tick(&mut scheduler, &mut planners);
+    assert!(scheduler.stats().expansions <= 12);
+    if ticks == 1 {
+      assert!(scheduler.stats().deferred >= 1);
+    }
+
+    for (index, planner) in planners.iter().enumerate() {
+      if planned_at[index].is_none() && planner.current_plan().is_some() {
+        planned_at[index] = Some(ticks);
+      }
+    }
+  }
+
+  // Nobody plans all three in one tick, and everyone gets there
+  assert!(planned_at.iter().all(Option::is_some));
+  assert!(planned_at.iter().any(|ticks| *ticks > Some(1)));
+  // Equally urgent agents go in order
+  assert!(planned_at.windows(2).all(|pair| pair[0] <= pair[1]));
+}
+
+#[test]
+fn searches_resume_on_the_next_tick() {
+  let expected = plan(&mut planner(vec![want(LINKS[10])], chain(10)), &Blackboard::new())
+    .map(|(actions, _)| actions);
+
+  let mut scheduler = PlanningScheduler::new(3);
+  let mut planners = vec![planner(vec![want(LINKS[10])], chain(10))];
+
+  tick(&mut scheduler, &mut planners);
+  assert_eq!(scheduler.stats().expansions, 3);
+  assert_eq!(scheduler.stats().deferred, 1);
+  assert!(planners[0].is_searching());
+  assert!(planners[0].current_plan().is_none());
+
+  let mut ticks = 1;
+  while planners[0].current_plan().is_none() {
+    assert!(ticks < 10, "the search never finished");
+    tick(&mut scheduler, &mut planners);
+    ticks += 1;
+  }
+
+  assert_eq!(action_names(&planners[0]), expected);
+}
+
+#[test]
+fn waiting_agents_age_ahead_of_newcomers() {
+  let mut scheduler = PlanningScheduler::new(1);
+
+  let (patient, _) = urge(LINKS[10], 1.0);
+  let mut planners = vec![planner(vec![], chain(10))];
+  planners[0].insert_goal(patient);
+
+  for _ in 0..6 {
+    tick(&mut scheduler, &mut planners);
+  }
+  assert!(planners[0].is_searching());
+
+  // More insistent than the first agent to begin with, but not more than
+  // it has become by waiting
+  let (urgent, _) = urge(LINKS[10], 1.2);
+  planners.push(planner(vec![], chain(10)));
+  planners[1].insert_goal(urgent);
+
+  let mut planned_at = vec![None; planners.len()];
+  for ticks in 1..=30 {
+    tick(&mut scheduler, &mut planners);
+    for (index, planner) in planners.iter().enumerate() {
+      if planned_at[index].is_none() && planner.current_plan().is_some() {
+        planned_at[index] = Some(ticks);
+      }
+    }
+  }
+
+  assert!(planned_at.iter().all(Option::is_some));
+  assert!(planned_at[0] < planned_at[1]);
+}
+
+/// An action that keeps running once started, so the plan it's in sticks
+/// around.
+#[derive(Debug)]
+struct Chore {
+  name: &'static str,
+  key: &'static str,
+}
+
+impl Action<Memory> for Chore {
+  fn name(&self) -> &'static str {
+    self.name
+  }
+
+  fn cost(&self, _: u32, _: &mut (), _: &(), _: &Blackboard) -> i32 {
+    1
+  }
+
+  fn preconditions(&self) -> Option<GoalState> {
+    Some(GoalState::new())
+  }
+
+  fn effects(&self) -> Option<Effects> {
+    let mut effects = Effects::new();
+    effects.insert_bool(self.key, true);
+    Some(effects)
+  }
+
+  fn execute(&mut self, _: u32, _: &mut (), _: &mut (), _: &mut ()) -> ActionStatus {
+    ActionStatus::Running
+  }
+}
+
+fn chores() -> Vec<Chore> {
+  vec![
+    Chore {
+      name: "DoA",
+      key: "A",
+    },
+    Chore {
+      name: "DoB",
+      key: "B",
+    },
+  ]
+}
+
+fn goal_of(planner: &Planner<Memory>) -> Option<usize> {
+  planner.current_plan().map(|plan| plan.goal())
+}
+
+#[test]
+fn the_most_insistent_goal_goes_first() {
+  let (a, _) = urge("A", 1.0);
+  let (b, _) = urge("B", 2.0);
+  let mut planner = Planner::new();
+  planner.insert_goal(a);
+  planner.insert_goal(b);
+  for chore in chores() {
+    planner.insert_action(chore);
+  }
+
+  planner.update(AGENT, &mut (), &mut (), &mut ());
+  assert_eq!(goal_of(&planner), Some(1));
+  assert_eq!(action_names(&planner), Some(vec!["DoB"]));
+
+  // Ties go to the goal inserted first
+  let (a, _) = urge("A", 1.0);
+  let (b, _) = urge("B", 1.0);
+  let mut planner = Planner::new();
+  planner.insert_goal(a);
+  planner.insert_goal(b);
+  for chore in chores() {
+    planner.insert_action(chore);
+  }
+
+  planner.update(AGENT, &mut (), &mut (), &mut ());
+  assert_eq!(goal_of(&planner), Some(0));
+}
+
+#[test]
+fn goals_only_take_over_when_clearly_more_insistent() {
+  let (a, _) = urge("A", 1.0);
+  let (b, b_insistence) = urge("B", 0.5);
+  let mut planner = Planner::new();
+  planner.insert_goal(a);
+  planner.insert_goal(b);
+  for chore in chores() {
+    planner.insert_action(chore);
+  }
+
+  planner.update(AGENT, &mut (), &mut (), &mut ());
+  assert_eq!(goal_of(&planner), Some(0));
+
+  // More insistent, but not by the hysteresis
+  *b_insistence.lock().unwrap() = 1.0 + DEFAULT_HYSTERESIS / 2.0;
+  planner.update(AGENT, &mut (), &mut (), &mut ());
+  assert_eq!(goal_of(&planner), Some(0));
+  assert!(!planner.is_searching());
+
+  *b_insistence.lock().unwrap() = 1.0 + DEFAULT_HYSTERESIS * 2.0;
+  planner.update(AGENT, &mut (), &mut (), &mut ());
+  assert_eq!(goal_of(&planner), Some(1));
+  assert_eq!(action_names(&planner), Some(vec!["DoB"]));
+
+  // Dropping back below doesn't make it switch back either
+  *b_insistence.lock().unwrap() = 1.0 - DEFAULT_HYSTERESIS / 2.0;
+  planner.update(AGENT, &mut (), &mut (), &mut ());
+  assert_eq!(goal_of(&planner), Some(1));
+}
+
+#[test]
+fn hysteresis_can_be_turned_off() {
+  let (a, _) = urge("A", 1.0);
+  let (b, b_insistence) = urge("B", 0.5);
+  let mut planner = Planner::new();
+  planner.set_hysteresis(0.0);
+  planner.insert_goal(a);
+  planner.insert_goal(b);
+  for chore in chores() {
+    planner.insert_action(chore);
+  }
+
+  planner.update(AGENT, &mut (), &mut (), &mut ());
+  assert_eq!(goal_of(&planner), Some(0));
+
+  *b_insistence.lock().unwrap() = 1.0 + DEFAULT_HYSTERESIS / 2.0;
+  planner.update(AGENT, &mut (), &mut (), &mut ());
+  assert_eq!(goal_of(&planner), Some(1));
+}
+
+/// An action that never gets done, counting how many times it got reset.
+#[derive(Debug)]
+struct Stuck {
+  timeout: Option<f32>,
+  resets: Arc<AtomicUsize>,
+}
+
+impl Action<Memory> for Stuck {
+  fn name(&self) -> &'static str {
+    "Stuck"
+  }
+
+  fn cost(&self, _: u32, _: &mut (), _: &(), _: &Blackboard) -> i32 {
+    1
+  }
+
+  fn preconditions(&self) -> Option<GoalState> {
+    Some(GoalState::new())
+  }
+
+  fn effects(&self) -> Option<Effects> {
+    let mut effects = Effects::new();
+    effects.insert_bool("Done", true);
+    Some(effects)
+  }
+
+  fn timeout(&self) -> Option<f32> {
+    self.timeout
+  }
+
+  fn execute(&mut self, _: u32, _: &mut (), _: &mut (), _: &mut ()) -> ActionStatus {
+    ActionStatus::Running
+  }
+
+  fn reset(&mut self) {
+    self.resets.fetch_add(1, Ordering::SeqCst);
+  }
+}
+
+#[test]
+fn actions_fail_once_they_time_out() {
+  let resets = Arc::new(AtomicUsize::new(0));
+  let mut planner = Planner::new();
+  planner.insert_goal(want("Done"));
+  planner.insert_action(Stuck {
+    timeout: Some(3.0),
+    resets: resets.clone(),
+  });
+
+  planner.update(AGENT, &mut (), &mut (), &mut ());
+  planner.update(AGENT, &mut (), &mut (), &mut ());
+  assert_eq!(resets.load(Ordering::SeqCst), 0);
+
+  planner.update(AGENT, &mut (), &mut (), &mut ());
+  assert_eq!(resets.load(Ordering::SeqCst), 1);
+  // Re-planned right away, with the clock started over
+  assert!(planner.current_plan().is_some());
+
+  planner.update(AGENT, &mut (), &mut (), &mut ());
+  planner.update(AGENT, &mut (), &mut (), &mut ());
+  assert_eq!(resets.load(Ordering::SeqCst), 1);
+  planner.update(AGENT, &mut (), &mut (), &mut ());
+  assert_eq!(resets.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn actions_without_a_timeout_keep_running() {
+  let resets = Arc::new(AtomicUsize::new(0));
+  let mut planner = Planner::new();
+  planner.insert_goal(want("Done"));
+  planner.insert_action(Stuck {
+    timeout: None,
+    resets: resets.clone(),
+  });
+
+  for _ in 0..100 {
+    planner.update(AGENT, &mut (), &mut (), &mut ());
+  }
+  assert_eq!(resets.load(Ordering::SeqCst), 0);
+  assert!(planner.current_plan().is_some());
+}
+
+/// Senses a target, which the plan is no good for once it changes.
+#[derive(Debug)]
+struct Watch {
+  target: Arc<Mutex<u32>>,
+}
+
+impl Sensor<Memory> for Watch {
+  fn name(&self) -> &'static str {
+    "Watch"
+  }
+
+  fn sense(&mut self, _: u32, _: &mut (), _: &mut (), _: &mut (), blackboard: &mut Blackboard) {
+    blackboard.insert_number("Target", *self.target.lock().unwrap());
+  }
+
+  fn invalidates(&self, previous: &Blackboard, current: &Blackboard) -> bool {
+    previous.contains("Target") && previous.get_number("Target") != current.get_number("Target")
+  }
+}
+
+#[test]
+fn sensors_can_throw_the_plan_away() {
+  let resets = Arc::new(AtomicUsize::new(0));
+  let target = Arc::new(Mutex::new(1));
+  let mut planner = Planner::new();
+  planner.insert_goal(want("Done"));
+  planner.insert_action(Stuck {
+    timeout: None,
+    resets: resets.clone(),
+  });
+  planner.insert_sensor(Watch {
+    target: target.clone(),
+  });
+
+  planner.update(AGENT, &mut (), &mut (), &mut ());
+  planner.update(AGENT, &mut (), &mut (), &mut ());
+  assert_eq!(resets.load(Ordering::SeqCst), 0);
+
+  *target.lock().unwrap() = 2;
+  planner.update(AGENT, &mut (), &mut (), &mut ());
+  assert_eq!(resets.load(Ordering::SeqCst), 1);
+  // Re-planned on the same tick
+  assert!(planner.current_plan().is_some());
+
+  planner.update(AGENT, &mut (), &mut (), &mut ());
+  assert_eq!(resets.load(Ordering::SeqCst), 1);
+}
+
+#[cfg(feature = "debug-goap")]
+mod traces {
+  use super::*;
+  use crate::utils::goap::{Outcome, SearchTrace};
+
+  fn traced(actions: Vec<Step>) -> Planner<Memory> {
+    let mut planner = planner(vec![want("Flying"), want("HasFood")], actions);
+    planner.set_tracing(true);
+    planner
+  }
+
+  fn found(planner: &Planner<Memory>) -> &SearchTrace {
+    planner
+      .traces()
+      .iter()
+      .find(|trace| trace.outcome == Outcome::Found)
+      .unwrap()
+  }
+
+  #[test]
+  fn searches_are_only_recorded_when_tracing() {
+    let mut planner = planner(vec![want("HasFood")], errands());
+    plan(&mut planner, &Blackboard::new()).unwrap();
+    assert!(planner.traces().is_empty());
+
+    planner.set_tracing(true);
+    plan(&mut planner, &Blackboard::new()).unwrap();
+    assert!(!planner.traces().is_empty());
+
+    planner.set_tracing(false);
+    assert!(planner.traces().is_empty());
+  }
+
+  #[test]
+  fn every_goal_tried_gets_a_trace() {
+    let mut planner = traced(errands());
+    plan(&mut planner, &Blackboard::new()).unwrap();
+
+    let traces = planner.traces();
+    assert_eq!(traces.first().map(|trace| trace.goal), Some(0));
+    assert_eq!(traces.first().map(|trace| trace.outcome), Some(Outcome::Exhausted));
+    assert_eq!(traces.last().map(|trace| trace.goal), Some(1));
+    assert_eq!(traces.last().map(|trace| trace.outcome), Some(Outcome::Found));
+  }
+
+  #[test]
+  fn traces_follow_the_plan() {
+    let searches: Vec<(Vec<Step>, &str)> = vec![
+      (errands(), "backward"),
+      (errands().into_iter().map(Step::opaque).collect(), "forward"),
+    ];
+
+    for (actions, direction) in searches {
+      let mut planner = traced(actions);
+      let (steps, _) = plan(&mut planner, &Blackboard::new()).unwrap();
+      let trace = found(&planner);
+
+      assert_eq!(trace.direction, direction);
+      // The root along with a node for every action
+      assert_eq!(trace.path.len(), steps.len() + 1);
+      for id in &trace.path {
+        let node = trace.nodes.iter().find(|node| node.id == *id).unwrap();
+        assert!(node.action.map_or(true, |action| steps.contains(&action)));
+      }
+      assert!(trace.nodes.iter().any(|node| node.expanded.is_some()));
+    }
+  }
+
+  #[test]
+  fn traces_export_to_json() {
+    let mut planner = traced(errands());
+    plan(&mut planner, &Blackboard::new()).unwrap();
+    let trace = found(&planner);
+
+    let json: serde_json::Value = serde_json::from_str(&trace.to_json()).unwrap();
+    assert_eq!(json["goal"], 1);
+    assert_eq!(json["direction"], "backward");
+    assert_eq!(json["outcome"], "found");
+    assert_eq!(json["nodes"].as_array().map(Vec::len), Some(trace.nodes.len()));
+    assert_eq!(json["path"].as_array().map(Vec::len), Some(trace.path.len()));
+    // Backward searches go through what's left to meet
+    assert!(json["nodes"][0]["state"]["outstanding"].is_object());
+  }
+
+  #[test]
+  fn traces_export_to_dot() {
+    let mut planner = traced(errands());
+    let (steps, _) = plan(&mut planner, &Blackboard::new()).unwrap();
+    let trace = found(&planner);
+    let dot = trace.to_dot();
+
+    assert!(dot.starts_with("digraph goap {"));
+    assert!(dot.trim_end().ends_with('}'));
+    for node in &trace.nodes {
+      assert!(dot.contains(&format!("  n{} [label=", node.id)));
+    }
+    for step in steps {
+      assert!(dot.contains(step));
+    }
+    // The chosen path stands out
+    assert!(dot.contains("color=red"));
+  }
+}
@@ -0,0 +1,265 @@
+#[cfg(feature = "debug-goap")]
+pub use recording::*;
+
+#[cfg(feature = "debug-goap")]
+mod recording {
+  use serde::Serialize;
+  use std::fmt::Write;
+
+  use crate::utils::goap::{Blackboard, GoalState, Operator, Value};
+
+  /// What a node of the search stood for. Forward searches go through world
+  /// states, backward ones through the conditions left to meet.
+  #[derive(Debug, Clone, Serialize)]
+  #[serde(rename_all = "snake_case")]
+  pub enum TraceState {
+    Blackboard(Blackboard),
+    Outstanding(GoalState),
+  }
+
+  #[derive(Debug, Clone, Serialize)]
+  pub struct TraceNode {
+    pub id: usize,
+    pub parent: Option<usize>,
+    pub action: Option<&'static str>,
+    pub state: TraceState,
+    pub cost: i32,
+    /// When the node was expanded, if it was.
+    pub expanded: Option<usize>,
+  }
+
+  /// An action that wasn't tried from a node, and why.
+  #[derive(Debug, Clone, Serialize)]
+  pub struct Rejection {
+    pub node: usize,
+    pub action: &'static str,
+    pub reason: &'static str,
+  }
+
+  #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+  #[serde(rename_all = "snake_case")]
+  pub enum Outcome {
+    Searching,
+    Found,
+    /// A plan was found, but it didn't hold up when replayed.
+    Invalid,
+    Exhausted,
+  }
+
+  /// Everything a single search for a goal went through.
+  #[derive(Debug, Clone, Serialize)]
+  pub struct SearchTrace {
+    pub goal: usize,
+    pub direction: &'static str,
+    pub nodes: Vec<TraceNode>,
+    pub rejected: Vec<Rejection>,
+    /// Nodes of the plan that was found, root first.
+    pub path: Vec<usize>,
+    pub outcome: Outcome,
+  }
+
+  impl SearchTrace {
+    pub fn new(goal: usize, direction: &'static str) -> Self {
+      Self {
+        goal,
+        direction,
+        nodes: vec![],
+        rejected: vec![],
+        path: vec![],
+        outcome: Outcome::Searching,
+      }
+    }
+
+    pub fn to_json(&self) -> String {
+      serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// A Graphviz graph of the search. Expanded nodes are filled, the chosen
+    /// path is red and rejected actions hang off their node in grey.
+    pub fn to_dot(&self) -> String {
+      let mut dot = String::new();
+
+      let _ = writeln!(dot, "digraph goap {{");
+      let _ = writeln!(
+        dot,
+        "  label=\"goal {} ({}, {:?})\";",
+        self.goal, self.direction, self.outcome
+      );
+      let _ = writeln!(dot, "  rankdir=LR;");
+      let _ = writeln!(dot, "  node [shape=box, fontname=\"monospace\"];");
+
+      for node in &self.nodes {
+        let mut label = format!(
+          "{}\\ncost {}",
+          node.action.unwrap_or("root"),
+          node.cost
+        );
+        if let Some(order) = node.expanded {
+          let _ = write!(label, " (#{})", order);
+        }
+        for line in state_lines(&node.state) {
+          let _ = write!(label, "\\n{}", line);
+        }
+
+        let mut style = vec![];
+        if node.expanded.is_some() {
+          style.push("style=filled, fillcolor=lightgrey".to_string());
+        }
+        if self.path.contains(&node.id) {
+          style.push("color=red, penwidth=2".to_string());
+        }
+
+        let _ = writeln!(
+          dot,
+          "  n{} [label=\"{}\"{}{}];",
+          node.id,
+          escape(&label),
+          if style.is_empty() { "" } else { ", " },
+          style.join(", ")
+        );
+
+        if let Some(parent) = node.parent {
+          let on_path = self.path.contains(&node.id) && self.path.contains(&parent);
+          let _ = writeln!(
+            dot,
+            "  n{} -> n{}{};",
+            parent,
+            node.id,
+            if on_path { " [color=red, penwidth=2]" } else { "" }
+          );
+        }
+      }
+
+      for (index, rejection) in self.rejected.iter().enumerate() {
+        let _ = writeln!(
+          dot,
+          "  r{} [label=\"{}: {}\", shape=plaintext, fontcolor=grey];",
+          index,
+          escape(rejection.action),
+          escape(rejection.reason)
+        );
+        let _ = writeln!(
+          dot,
+          "  n{} -> r{} [style=dashed, color=grey];",
+          rejection.node, index
+        );
+      }
+
+      let _ = writeln!(dot, "}}");
+      dot
+    }
+  }
+
+  fn state_lines(state: &TraceState) -> Vec<String> {
+    match state {
+      TraceState::Blackboard(blackboard) => blackboard
+        .keys()
+        .filter_map(|key| {
+          blackboard
+            .get(key)
+            .map(|value| format!("{} = {}", key, value_label(value)))
+        })
+        .collect(),
+      TraceState::Outstanding(state) => state
+        .conditions()
+        .iter()
+        .map(|condition| {
+          let operator = match condition.operator() {
+            Operator::Equal => "==",
+            Operator::NotEqual => "!=",
+            Operator::Less => "<",
+            Operator::GreaterOrEqual => ">=",
+          };
+          format!(
+            "{} {} {}",
+            condition.key(),
+            operator,
+            value_label(condition.value())
+          )
+        })
+        .collect(),
+    }
+  }
+
+  fn value_label(value: &Value) -> String {
+    match value {
+      Value::Bool(value) => value.to_string(),
+      Value::Number(value) => value.to_string(),
+      Value::Str(value) => format!("'{}'", value),
+    }
+  }
+
+  fn escape(label: &str) -> String {
+    label.replace('"', "\\\"")
+  }
+
+  /// Feeds a `SearchTrace` as a search runs, when tracing was asked for.
+  #[derive(Debug, Default)]
+  pub struct Recorder {
+    trace: Option<SearchTrace>,
+    expansions: usize,
+  }
+
+  impl Recorder {
+    pub(in crate::utils::goap) fn new(enabled: bool, goal: usize, direction: &'static str) -> Self {
+      Self {
+        trace: if enabled {
+          Some(SearchTrace::new(goal, direction))
+        } else {
+          None
+        },
+        expansions: 0,
+      }
+    }
+
+    pub(in crate::utils::goap) fn node(
+      &mut self,
+      id: usize,
+      parent: Option<usize>,
+      action: Option<&'static str>,
+      state: impl FnOnce() -> TraceState,
+      cost: i32,
+    ) {
+      if let Some(trace) = &mut self.trace {
+        trace.nodes.push(TraceNode {
+          id,
+          parent,
+          action,
+          state: state(),
+          cost,
+          expanded: None,
+        });
+      }
+    }
+
+    pub(in crate::utils::goap) fn expand(&mut self, id: usize) {
+      if let Some(trace) = &mut self.trace {
+        if let Some(node) = trace.nodes.iter_mut().find(|node| node.id == id) {
+          node.expanded = Some(self.expansions);
+        }
+        self.expansions += 1;
+      }
+    }
+
+    pub(in crate::utils::goap) fn reject(&mut self, node: usize, action: &'static str, reason: &'static str) {
+      if let Some(trace) = &mut self.trace {
+        trace.rejected.push(Rejection {
+          node,
+          action,
+          reason,
+        });
+      }
+    }
+
+    pub(in crate::utils::goap) fn finish(&mut self, outcome: Outcome, path: Vec<usize>) {
+      if let Some(trace) = &mut self.trace {
+        trace.outcome = outcome;
+        trace.path = path;
+      }
+    }
+
+    pub(in crate::utils::goap) fn take(&mut self) -> Option<SearchTrace> {
+      self.trace.take()
+    }
+  }
+}
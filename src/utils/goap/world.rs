@@ -0,0 +1,19 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// What agents plan in. Sensors, actions and goals read and change it
+/// through these types, so the planner itself doesn't need to know about
+/// the engine.
+pub trait World: 'static {
+  /// Whoever the planning is for.
+  type Agent: Copy + Eq + Hash + Debug;
+  /// The state agents sense and act upon.
+  type Scene;
+  /// Resources shared by every agent.
+  type Backpack;
+  /// What a single agent keeps to itself between ticks.
+  type Local;
+
+  /// Seconds since the last tick.
+  fn delta(backpack: &Self::Backpack) -> f32;
+}